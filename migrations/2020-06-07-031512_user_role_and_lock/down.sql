@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "user"
+DROP COLUMN role,
+DROP COLUMN is_locked,
+DROP COLUMN failed_login_attempts,
+DROP COLUMN must_reset_password;
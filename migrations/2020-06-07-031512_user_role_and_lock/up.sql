@@ -0,0 +1,6 @@
+-- Your SQL goes here
+ALTER TABLE "user"
+ADD COLUMN role VARCHAR NOT NULL DEFAULT 'user',
+ADD COLUMN is_locked BOOLEAN NOT NULL DEFAULT false,
+ADD COLUMN failed_login_attempts INTEGER NOT NULL DEFAULT 0,
+ADD COLUMN must_reset_password BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,15 @@
+-- This file should undo anything in `up.sql`
+-- Hashed secrets can't be recovered, clients have to rotate their secret after rolling back
+ALTER TABLE client_credential
+ADD COLUMN redirect_uri VARCHAR NOT NULL DEFAULT '';
+
+UPDATE client_credential
+SET redirect_uri = COALESCE(redirect_uris[1], '');
+
+ALTER TABLE client_credential
+DROP COLUMN redirect_uris,
+DROP COLUMN name,
+DROP COLUMN logo_uri,
+DROP COLUMN grant_types,
+DROP COLUMN owner,
+DROP COLUMN created_at;
@@ -0,0 +1,15 @@
+-- Your SQL goes here
+ALTER TABLE client_credential
+ADD COLUMN redirect_uris TEXT[] NOT NULL DEFAULT '{}',
+ADD COLUMN name VARCHAR NOT NULL DEFAULT '',
+ADD COLUMN logo_uri VARCHAR,
+ADD COLUMN grant_types TEXT[] NOT NULL DEFAULT '{authorization_code}',
+ADD COLUMN owner VARCHAR NOT NULL DEFAULT '',
+ADD COLUMN created_at TIMESTAMP NOT NULL DEFAULT NOW();
+
+UPDATE client_credential
+SET redirect_uris = ARRAY[redirect_uri],
+    secret = encode(sha256(secret::bytea), 'hex');
+
+ALTER TABLE client_credential
+DROP COLUMN redirect_uri;
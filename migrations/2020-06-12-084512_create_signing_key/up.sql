@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE signing_key (
+    id VARCHAR NOT NULL PRIMARY KEY,
+    secret VARCHAR NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMP
+);
@@ -0,0 +1,12 @@
+-- Your SQL goes here
+CREATE TABLE federated_identity (
+    id SERIAL PRIMARY KEY,
+    provider VARCHAR NOT NULL,
+    subject VARCHAR NOT NULL,
+    user_id INTEGER NOT NULL REFERENCES "user" (id) ON DELETE CASCADE,
+    email VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (provider, subject)
+);
+
+CREATE INDEX federated_identity_user_id ON federated_identity (user_id);
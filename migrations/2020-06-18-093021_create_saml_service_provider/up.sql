@@ -0,0 +1,7 @@
+-- Your SQL goes here
+CREATE TABLE saml_service_provider (
+    entity_id VARCHAR NOT NULL PRIMARY KEY,
+    acs_url VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
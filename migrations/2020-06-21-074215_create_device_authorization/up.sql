@@ -0,0 +1,12 @@
+-- Your SQL goes here
+CREATE TABLE device_authorization (
+    device_code VARCHAR NOT NULL PRIMARY KEY,
+    user_code VARCHAR NOT NULL UNIQUE,
+    client_id VARCHAR NOT NULL REFERENCES client_credential(id) ON DELETE CASCADE,
+    username VARCHAR,
+    is_denied BOOLEAN NOT NULL DEFAULT false,
+    poll_interval INTEGER NOT NULL,
+    last_polled_at TIMESTAMP,
+    expires_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
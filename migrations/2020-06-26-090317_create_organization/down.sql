@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE url
+DROP COLUMN organization_id;
+
+DROP TABLE organization_member;
+DROP TABLE organization;
@@ -0,0 +1,22 @@
+-- Your SQL goes here
+CREATE TABLE organization (
+    id SERIAL PRIMARY KEY,
+    slug VARCHAR NOT NULL UNIQUE,
+    name VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE organization_member (
+    organization_id INTEGER NOT NULL REFERENCES organization (id) ON DELETE CASCADE,
+    username VARCHAR NOT NULL,
+    role VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (organization_id, username)
+);
+
+CREATE INDEX organization_member_username ON organization_member (username);
+
+ALTER TABLE url
+ADD COLUMN organization_id INTEGER REFERENCES organization (id);
+
+CREATE INDEX url_organization_id ON url (organization_id);
@@ -0,0 +1,12 @@
+-- Your SQL goes here
+CREATE TABLE url_click (
+    id SERIAL PRIMARY KEY,
+    url_key VARCHAR NOT NULL REFERENCES url (key) ON UPDATE CASCADE ON DELETE CASCADE,
+    clicked_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    referrer VARCHAR,
+    user_agent VARCHAR,
+    country VARCHAR,
+    ip_hash VARCHAR NOT NULL
+);
+
+CREATE INDEX url_click_url_key_clicked_at ON url_click (url_key, clicked_at);
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE url
+DROP COLUMN expires_at,
+DROP COLUMN activates_at,
+DROP COLUMN max_clicks,
+DROP COLUMN click_count;
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+ALTER TABLE url
+ADD COLUMN expires_at TIMESTAMP,
+ADD COLUMN activates_at TIMESTAMP,
+ADD COLUMN max_clicks INTEGER,
+ADD COLUMN click_count INTEGER NOT NULL DEFAULT 0;
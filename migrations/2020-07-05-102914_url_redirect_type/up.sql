@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE url
+ADD COLUMN redirect_type INTEGER NOT NULL DEFAULT 302;
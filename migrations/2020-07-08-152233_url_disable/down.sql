@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE url
+DROP COLUMN is_disabled,
+DROP COLUMN disabled_reason;
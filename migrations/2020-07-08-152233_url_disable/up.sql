@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER TABLE url
+ADD COLUMN is_disabled BOOLEAN NOT NULL DEFAULT false,
+ADD COLUMN disabled_reason VARCHAR;
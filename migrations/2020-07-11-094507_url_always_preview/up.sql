@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE url
+ADD COLUMN always_preview BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE url_tag;
+DROP TABLE tag;
+
+ALTER TABLE url
+DROP COLUMN created_at,
+DROP COLUMN updated_at,
+DROP COLUMN description;
@@ -0,0 +1,20 @@
+-- Your SQL goes here
+ALTER TABLE url
+ADD COLUMN created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+ADD COLUMN description VARCHAR;
+
+CREATE INDEX url_created_at ON url (created_at);
+
+CREATE TABLE tag (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR NOT NULL UNIQUE
+);
+
+CREATE TABLE url_tag (
+    url_key VARCHAR NOT NULL REFERENCES url (key) ON UPDATE CASCADE ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES tag (id) ON DELETE CASCADE,
+    PRIMARY KEY (url_key, tag_id)
+);
+
+CREATE INDEX url_tag_tag_id ON url_tag (tag_id);
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE url_revision;
+
+ALTER TABLE url
+DROP COLUMN deleted_at;
@@ -0,0 +1,23 @@
+-- Your SQL goes here
+ALTER TABLE url
+ADD COLUMN deleted_at TIMESTAMP;
+
+CREATE INDEX url_deleted_at ON url (deleted_at);
+
+CREATE TABLE url_revision (
+    id SERIAL PRIMARY KEY,
+    url_key VARCHAR NOT NULL REFERENCES url (key) ON UPDATE CASCADE ON DELETE CASCADE,
+    changed_by VARCHAR NOT NULL,
+    action VARCHAR NOT NULL,
+    key VARCHAR NOT NULL,
+    target VARCHAR NOT NULL,
+    expires_at TIMESTAMP,
+    activates_at TIMESTAMP,
+    max_clicks INTEGER,
+    redirect_type INTEGER NOT NULL,
+    always_preview BOOLEAN NOT NULL,
+    description VARCHAR,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX url_revision_url_key ON url_revision (url_key);
@@ -0,0 +1,46 @@
+-- This file should undo anything in `up.sql`
+DELETE FROM url WHERE domain_id IS NOT NULL;
+
+ALTER TABLE url_click
+ADD COLUMN url_key VARCHAR;
+UPDATE url_click SET url_key = url.key FROM url WHERE url.id = url_click.url_id;
+ALTER TABLE url_click
+DROP COLUMN url_id;
+
+ALTER TABLE url_tag
+ADD COLUMN url_key VARCHAR;
+UPDATE url_tag SET url_key = url.key FROM url WHERE url.id = url_tag.url_id;
+ALTER TABLE url_tag
+DROP COLUMN url_id;
+
+ALTER TABLE url_revision
+ADD COLUMN url_key VARCHAR;
+UPDATE url_revision SET url_key = url.key FROM url WHERE url.id = url_revision.url_id;
+ALTER TABLE url_revision
+DROP COLUMN url_id;
+
+DROP INDEX url_key;
+DROP INDEX url_domain_id_key;
+
+ALTER TABLE url
+DROP CONSTRAINT url_pkey,
+ADD PRIMARY KEY (key),
+DROP COLUMN id,
+DROP COLUMN domain_id;
+
+ALTER TABLE url_click
+ALTER COLUMN url_key SET NOT NULL,
+ADD FOREIGN KEY (url_key) REFERENCES url (key) ON UPDATE CASCADE ON DELETE CASCADE;
+CREATE INDEX url_click_url_key_clicked_at ON url_click (url_key, clicked_at);
+
+ALTER TABLE url_tag
+ALTER COLUMN url_key SET NOT NULL,
+ADD FOREIGN KEY (url_key) REFERENCES url (key) ON UPDATE CASCADE ON DELETE CASCADE,
+ADD PRIMARY KEY (url_key, tag_id);
+
+ALTER TABLE url_revision
+ALTER COLUMN url_key SET NOT NULL,
+ADD FOREIGN KEY (url_key) REFERENCES url (key) ON UPDATE CASCADE ON DELETE CASCADE;
+CREATE INDEX url_revision_url_key ON url_revision (url_key);
+
+DROP TABLE domain;
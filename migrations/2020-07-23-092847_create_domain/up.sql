@@ -0,0 +1,57 @@
+-- Your SQL goes here
+CREATE TABLE domain (
+    id SERIAL PRIMARY KEY,
+    hostname VARCHAR NOT NULL UNIQUE,
+    username VARCHAR NOT NULL,
+    organization_id INTEGER REFERENCES organization (id),
+    not_found_url VARCHAR,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX domain_username ON domain (username);
+CREATE INDEX domain_organization_id ON domain (organization_id);
+
+-- Keys are only unique within a domain now, so links get an id of their own to be referenced by
+ALTER TABLE url
+ADD COLUMN id SERIAL,
+ADD COLUMN domain_id INTEGER REFERENCES domain (id);
+
+ALTER TABLE url_click
+ADD COLUMN url_id INTEGER;
+UPDATE url_click SET url_id = url.id FROM url WHERE url.key = url_click.url_key;
+ALTER TABLE url_click
+DROP COLUMN url_key;
+
+ALTER TABLE url_tag
+ADD COLUMN url_id INTEGER;
+UPDATE url_tag SET url_id = url.id FROM url WHERE url.key = url_tag.url_key;
+ALTER TABLE url_tag
+DROP COLUMN url_key;
+
+ALTER TABLE url_revision
+ADD COLUMN url_id INTEGER;
+UPDATE url_revision SET url_id = url.id FROM url WHERE url.key = url_revision.url_key;
+ALTER TABLE url_revision
+DROP COLUMN url_key;
+
+ALTER TABLE url
+DROP CONSTRAINT url_pkey,
+ADD PRIMARY KEY (id);
+
+CREATE UNIQUE INDEX url_key ON url (key) WHERE domain_id IS NULL;
+CREATE UNIQUE INDEX url_domain_id_key ON url (domain_id, key) WHERE domain_id IS NOT NULL;
+
+ALTER TABLE url_click
+ALTER COLUMN url_id SET NOT NULL,
+ADD FOREIGN KEY (url_id) REFERENCES url (id) ON DELETE CASCADE;
+CREATE INDEX url_click_url_id_clicked_at ON url_click (url_id, clicked_at);
+
+ALTER TABLE url_tag
+ALTER COLUMN url_id SET NOT NULL,
+ADD FOREIGN KEY (url_id) REFERENCES url (id) ON DELETE CASCADE,
+ADD PRIMARY KEY (url_id, tag_id);
+
+ALTER TABLE url_revision
+ALTER COLUMN url_id SET NOT NULL,
+ADD FOREIGN KEY (url_id) REFERENCES url (id) ON DELETE CASCADE;
+CREATE INDEX url_revision_url_id ON url_revision (url_id);
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE url_click
+DROP COLUMN rule_id;
+
+DROP TABLE url_rule;
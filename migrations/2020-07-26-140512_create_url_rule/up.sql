@@ -0,0 +1,15 @@
+-- Your SQL goes here
+CREATE TABLE url_rule (
+    id SERIAL PRIMARY KEY,
+    url_id INTEGER NOT NULL REFERENCES url (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    kind VARCHAR NOT NULL,
+    value VARCHAR NOT NULL,
+    target VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX url_rule_url_id ON url_rule (url_id);
+
+ALTER TABLE url_click
+ADD COLUMN rule_id INTEGER REFERENCES url_rule (id) ON DELETE SET NULL;
@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE url_unlock_attempt (
+    url_id INTEGER NOT NULL REFERENCES url (id) ON DELETE CASCADE,
+    ip_hash VARCHAR NOT NULL,
+    failed_attempts INTEGER NOT NULL DEFAULT 0,
+    first_failed_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (url_id, ip_hash)
+);
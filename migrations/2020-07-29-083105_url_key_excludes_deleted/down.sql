@@ -0,0 +1,22 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE url
+DROP CONSTRAINT url_domain_id_fkey,
+ADD CONSTRAINT url_domain_id_fkey FOREIGN KEY (domain_id) REFERENCES domain (id);
+
+-- Keys have to be unique across deleted links again, the live link or the latest deletion wins
+DELETE FROM url
+WHERE id IN (
+    SELECT id FROM (
+        SELECT id, ROW_NUMBER() OVER (
+            PARTITION BY domain_id, key ORDER BY deleted_at IS NOT NULL, deleted_at DESC
+        ) AS position
+        FROM url
+    ) ranked
+    WHERE position > 1
+);
+
+DROP INDEX url_key;
+DROP INDEX url_domain_id_key;
+
+CREATE UNIQUE INDEX url_key ON url (key) WHERE domain_id IS NULL;
+CREATE UNIQUE INDEX url_domain_id_key ON url (domain_id, key) WHERE domain_id IS NOT NULL;
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+-- Deleted links keep their row until they are purged, but not their key
+DROP INDEX url_key;
+DROP INDEX url_domain_id_key;
+
+CREATE UNIQUE INDEX url_key ON url (key) WHERE domain_id IS NULL AND deleted_at IS NULL;
+CREATE UNIQUE INDEX url_domain_id_key ON url (domain_id, key)
+WHERE domain_id IS NOT NULL AND deleted_at IS NULL;
+
+-- Domains can only be deleted once their links are past the restore window, those go with it
+ALTER TABLE url
+DROP CONSTRAINT url_domain_id_fkey,
+ADD CONSTRAINT url_domain_id_fkey FOREIGN KEY (domain_id) REFERENCES domain (id) ON DELETE CASCADE;
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE domain
+DROP COLUMN approved_at,
+DROP COLUMN approved_by;
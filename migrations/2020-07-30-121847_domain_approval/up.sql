@@ -0,0 +1,7 @@
+-- Your SQL goes here
+-- Domains are only served once an admin checked the registrant controls them
+ALTER TABLE domain
+ADD COLUMN approved_at TIMESTAMP,
+ADD COLUMN approved_by VARCHAR;
+
+CREATE INDEX domain_approved_at ON domain (approved_at);
@@ -1,23 +1,64 @@
 use diesel::PgConnection;
 use tera::Tera;
 
+use crate::auth::directory::{Directory, LdapDirectory};
 use crate::auth::{Auth, AuthHandler};
 use crate::config::Config;
-use crate::database::handler::client_credential::ClientCredentialPostgresHandler;
+use crate::core::url_shortener::analytics::{spawn_click_writer, Click};
+use crate::core::url_shortener::blocklist::DomainBlocklist;
+use crate::core::url_shortener::domain_cache::DomainCache;
+use crate::database::handler::audit_log::{AuditLogHandler, AuditLogPostgresHandler};
+use crate::database::handler::client_credential::{
+    ClientCredentialHandler, ClientCredentialPostgresHandler,
+};
+use crate::database::handler::device_authorization::DeviceAuthorizationPostgresHandler;
+use crate::database::handler::domain::{DomainHandler, DomainPostgresHandler};
+use crate::database::handler::federated_identity::FederatedIdentityPostgresHandler;
+use crate::database::handler::organization::{OrganizationHandler, OrganizationPostgresHandler};
+use crate::database::handler::personal_access_token::PersonalAccessTokenPostgresHandler;
+use crate::database::handler::saml_service_provider::{
+    SamlServiceProviderHandler, SamlServiceProviderPostgresHandler,
+};
+use crate::database::handler::signing_key::{SigningKeyHandler, SigningKeyPostgresHandler};
 use crate::database::handler::url::{UrlHandler, UrlPostgresHandler};
-use crate::database::handler::user::UserPostgresHandler;
+use crate::database::handler::url_click::{UrlClickHandler, UrlClickPostgresHandler};
+use crate::database::handler::url_revision::{UrlRevisionHandler, UrlRevisionPostgresHandler};
+use crate::database::handler::url_rule::{UrlRuleHandler, UrlRulePostgresHandler};
+use crate::database::handler::url_unlock_attempt::{
+    UrlUnlockAttemptHandler, UrlUnlockAttemptPostgresHandler,
+};
+use crate::database::handler::user::{UserHandler, UserPostgresHandler};
 use crate::error::Error;
 use crate::templater::tera_based::TeraTemplater;
 use crate::templater::Templater;
+use image::DynamicImage;
 use lettre::smtp::authentication::Credentials;
 use lettre::{SmtpClient, SmtpTransport};
+use maxminddb::Reader;
 use std::rc::Rc;
+use std::sync::mpsc::Sender;
 
 pub struct AppData {
     pub connection: Rc<PgConnection>,
     pub auth_handler: Rc<dyn AuthHandler>,
     pub url_handler: Rc<dyn UrlHandler>,
+    pub organization_handler: Rc<dyn OrganizationHandler>,
+    pub url_click_handler: Rc<dyn UrlClickHandler>,
+    pub domain_handler: Rc<dyn DomainHandler>,
+    pub domain_cache: Rc<DomainCache>,
+    pub url_revision_handler: Rc<dyn UrlRevisionHandler>,
+    pub url_rule_handler: Rc<dyn UrlRuleHandler>,
+    pub url_unlock_attempt_handler: Rc<dyn UrlUnlockAttemptHandler>,
+    pub user_handler: Rc<dyn UserHandler>,
+    pub client_credential_handler: Rc<dyn ClientCredentialHandler>,
+    pub audit_log_handler: Rc<dyn AuditLogHandler>,
+    pub signing_key_handler: Rc<dyn SigningKeyHandler>,
+    pub saml_service_provider_handler: Rc<dyn SamlServiceProviderHandler>,
     pub templater: Box<dyn Templater>,
+    pub geoip: Option<Rc<Reader<Vec<u8>>>>,
+    pub click_sender: Sender<Click>,
+    pub domain_blocklist: Option<DomainBlocklist>,
+    pub qr_logo: Option<DynamicImage>,
     pub config: Config,
 }
 
@@ -30,21 +71,82 @@ impl AppData {
             Rc::new(ClientCredentialPostgresHandler::new(connection.clone()));
         let user_handler = Rc::new(UserPostgresHandler::new(connection.clone()));
         let url_handler = Rc::new(UrlPostgresHandler::new(connection.clone()));
+        let organization_handler = Rc::new(OrganizationPostgresHandler::new(connection.clone()));
+        let url_click_handler = Rc::new(UrlClickPostgresHandler::new(connection.clone()));
+        let domain_handler = Rc::new(DomainPostgresHandler::new(connection.clone()));
+        let domain_cache = Rc::new(DomainCache::new(domain_handler.clone()));
+        let url_revision_handler = Rc::new(UrlRevisionPostgresHandler::new(connection.clone()));
+        let url_rule_handler = Rc::new(UrlRulePostgresHandler::new(connection.clone()));
+        let url_unlock_attempt_handler =
+            Rc::new(UrlUnlockAttemptPostgresHandler::new(connection.clone()));
+        let audit_log_handler = Rc::new(AuditLogPostgresHandler::new(connection.clone()));
+        let signing_key_handler = Rc::new(SigningKeyPostgresHandler::new(connection.clone()));
+        let saml_service_provider_handler =
+            Rc::new(SamlServiceProviderPostgresHandler::new(connection.clone()));
+        let federated_identity_handler =
+            Rc::new(FederatedIdentityPostgresHandler::new(connection.clone()));
+        let device_authorization_handler =
+            Rc::new(DeviceAuthorizationPostgresHandler::new(connection.clone()));
+        let personal_access_token_handler =
+            Rc::new(PersonalAccessTokenPostgresHandler::new(connection.clone()));
+        let directory = config
+            .ldap
+            .as_ref()
+            .map(|ldap| Rc::new(LdapDirectory::new(ldap.clone())) as Rc<dyn Directory>);
 
         let auth_handler = Rc::new(Auth::new(
             config.auth.cypher_key.clone(),
+            config.auth.signing_key_id.clone(),
             config.auth.token_lifetime,
             config.auth.auth_code_lifetime,
             config.auth.activation_code_lifetime,
+            config.auth.password_reset_code_lifetime,
+            config.auth.device_code_lifetime,
+            config.auth.device_poll_interval,
+            config.auth.personal_access_token_lifetime,
+            config.auth.max_login_attempts,
             user_handler.clone(),
             client_credential_handler.clone(),
+            signing_key_handler.clone(),
+            federated_identity_handler,
+            device_authorization_handler,
+            personal_access_token_handler,
+            directory,
         ));
 
         AppData {
             connection: connection.clone(),
             auth_handler,
             url_handler,
+            organization_handler,
+            url_click_handler,
+            domain_handler,
+            domain_cache,
+            url_revision_handler,
+            url_rule_handler,
+            url_unlock_attempt_handler,
+            user_handler,
+            client_credential_handler,
+            audit_log_handler,
+            signing_key_handler,
+            saml_service_provider_handler,
             templater: Box::new(TeraTemplater::new(tera)),
+            geoip: config
+                .url
+                .geoip_database_path
+                .as_ref()
+                .map(|path| Rc::new(Reader::open_readfile(path).expect("Invalid GeoIP database"))),
+            click_sender: spawn_click_writer(config),
+            domain_blocklist: config
+                .url
+                .domain_blocklist_path
+                .as_ref()
+                .map(|path| DomainBlocklist::new(path.clone())),
+            qr_logo: config
+                .url
+                .qr_logo_path
+                .as_ref()
+                .map(|path| image::open(path).expect("Invalid QR logo")),
             config: config.clone(),
         }
     }
@@ -0,0 +1,236 @@
+use actix_web::error::BlockingError;
+use actix_web::web;
+use futures::future::{FutureExt, LocalBoxFuture};
+use ldap3::{ldap_escape, LdapConn, Scope, SearchEntry};
+
+use crate::auth::model::AuthResult;
+use crate::auth::AuthError;
+use crate::config::LdapConfig;
+
+const LDAP_INVALID_CREDENTIALS: u32 = 49;
+
+/// Directory accounts are linked to their mirrored local user through `federated_identity`
+/// under this provider id, so a directory login can never take over a local account.
+pub const DIRECTORY_PROVIDER: &str = "ldap";
+
+/// An external user directory consulted before the local `user` table.
+pub trait Directory {
+    /// Returns `None` when the directory doesn't know the user, so the caller can fall back to
+    /// local accounts.
+    fn authenticate(
+        &self,
+        username: &String,
+        password: &String,
+    ) -> LocalBoxFuture<'static, AuthResult<Option<DirectoryUser>>>;
+}
+
+#[derive(Debug, PartialEq)]
+pub struct DirectoryUser {
+    pub username: String,
+    pub email: Option<String>,
+}
+
+/// The directory's answer to a login attempt. It is resolved before the synchronous
+/// `AuthHandler` calls, which can't wait on the network.
+pub enum DirectoryLogin {
+    /// No directory is configured, it doesn't know the user or it can't be reached
+    Skipped,
+    Verified(DirectoryUser),
+    Rejected,
+}
+
+/// `AuthError` isn't `Send`, so the blocking pool reports failures with this instead.
+#[derive(Debug)]
+enum LdapFailure {
+    InvalidCredentials,
+    Unavailable(String),
+}
+
+impl From<ldap3::LdapError> for LdapFailure {
+    fn from(e: ldap3::LdapError) -> Self {
+        LdapFailure::Unavailable(e.to_string())
+    }
+}
+
+pub struct LdapDirectory {
+    config: LdapConfig,
+}
+
+impl LdapDirectory {
+    pub fn new(config: LdapConfig) -> LdapDirectory {
+        LdapDirectory { config }
+    }
+}
+
+impl Directory for LdapDirectory {
+    fn authenticate(
+        &self,
+        username: &String,
+        password: &String,
+    ) -> LocalBoxFuture<'static, AuthResult<Option<DirectoryUser>>> {
+        let config = self.config.clone();
+        let username = username.to_owned();
+        let password = password.to_owned();
+
+        async move {
+            // An empty password turns the bind into an unauthenticated one, which always succeeds
+            if password.is_empty() {
+                return Err(AuthError::WrongPassword);
+            }
+
+            // The blocking LDAP client runs its own runtime, which can't be nested in the
+            // worker's, so it binds on the blocking pool instead
+            match web::block(move || bind_as_user(&config, &username, &password)).await {
+                Ok(directory_user) => Ok(directory_user),
+                Err(BlockingError::Error(LdapFailure::InvalidCredentials)) => {
+                    Err(AuthError::WrongPassword)
+                }
+                Err(BlockingError::Error(LdapFailure::Unavailable(e))) => {
+                    Err(AuthError::DirectoryError(e))
+                }
+                Err(BlockingError::Canceled) => Err(AuthError::DirectoryError(
+                    "LDAP lookup was canceled".to_owned(),
+                )),
+            }
+        }
+        .boxed_local()
+    }
+}
+
+fn bind_as_user(
+    config: &LdapConfig,
+    username: &String,
+    password: &String,
+) -> Result<Option<DirectoryUser>, LdapFailure> {
+    let mut ldap = LdapConn::new(&config.url)?;
+
+    if let (Some(bind_dn), Some(bind_password)) = (&config.bind_dn, &config.bind_password) {
+        ldap.simple_bind(bind_dn, bind_password)?.success()?;
+    }
+
+    let filter = format!(
+        "(&{}({}={}))",
+        config.user_filter,
+        config.username_attribute,
+        ldap_escape(username.as_str())
+    );
+    let (entries, _) = ldap
+        .search(
+            &config.user_base_dn,
+            Scope::Subtree,
+            &filter,
+            vec![
+                config.username_attribute.as_str(),
+                config.email_attribute.as_str(),
+            ],
+        )?
+        .success()?;
+
+    let entry = match entries.into_iter().next() {
+        Some(entry) => SearchEntry::construct(entry),
+        None => return Ok(None),
+    };
+
+    let bind_result = ldap.simple_bind(&entry.dn, password)?;
+    let _ = ldap.unbind();
+
+    if bind_result.rc == LDAP_INVALID_CREDENTIALS {
+        return Err(LdapFailure::InvalidCredentials);
+    }
+    bind_result.success()?;
+
+    let first_value = |attribute: &String| {
+        entry
+            .attrs
+            .get(attribute)
+            .and_then(|values| values.first())
+            .cloned()
+    };
+
+    Ok(Some(DirectoryUser {
+        username: first_value(&config.username_attribute).unwrap_or(username.to_owned()),
+        email: first_value(&config.email_attribute),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    /// Points at the stand-in started by `tests/ldap/start.sh`
+    fn stand_in_config() -> LdapConfig {
+        LdapConfig {
+            url: env::var("DORAEMON_TEST_LDAP_URL")
+                .unwrap_or_else(|_| "ldap://localhost:3389".to_owned()),
+            bind_dn: Some("cn=admin,dc=example,dc=com".to_owned()),
+            bind_password: Some("admin".to_owned()),
+            user_base_dn: "ou=people,dc=example,dc=com".to_owned(),
+            user_filter: "(objectClass=inetOrgPerson)".to_owned(),
+            username_attribute: "uid".to_owned(),
+            email_attribute: "mail".to_owned(),
+        }
+    }
+
+    async fn authenticate(
+        config: LdapConfig,
+        username: &str,
+        password: &str,
+    ) -> AuthResult<Option<DirectoryUser>> {
+        LdapDirectory::new(config)
+            .authenticate(&username.to_owned(), &password.to_owned())
+            .await
+    }
+
+    #[actix_rt::test]
+    #[ignore]
+    async fn binds_known_user() {
+        let directory_user = authenticate(stand_in_config(), "nobita", "dorayaki")
+            .await
+            .unwrap()
+            .expect("nobita is seeded in the stand-in");
+
+        assert_eq!(directory_user.username, "nobita");
+        assert_eq!(directory_user.email, Some("nobita@example.com".to_owned()));
+    }
+
+    #[actix_rt::test]
+    #[ignore]
+    async fn rejects_wrong_password() {
+        match authenticate(stand_in_config(), "nobita", "melonpan").await {
+            Err(AuthError::WrongPassword) => (),
+            _ => panic!("expected WrongPassword"),
+        }
+    }
+
+    #[actix_rt::test]
+    #[ignore]
+    async fn skips_unknown_user() {
+        match authenticate(stand_in_config(), "gian", "dorayaki").await {
+            Ok(None) => (),
+            _ => panic!("expected the directory to not know gian"),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn rejects_empty_password_without_binding() {
+        match authenticate(stand_in_config(), "nobita", "").await {
+            Err(AuthError::WrongPassword) => (),
+            _ => panic!("expected WrongPassword"),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn reports_unreachable_directory() {
+        let config = LdapConfig {
+            url: "ldap://127.0.0.1:1".to_owned(),
+            ..stand_in_config()
+        };
+
+        match authenticate(config, "nobita", "dorayaki").await {
+            Err(AuthError::DirectoryError(_)) => (),
+            _ => panic!("expected DirectoryError"),
+        }
+    }
+}
@@ -12,13 +12,26 @@ use serde_json;
 pub enum AuthError {
     NotFound,
     NotActivated,
+    Locked,
+    PasswordResetRequired,
     WrongPassword,
     InvalidToken,
     ExpiredToken,
     InvalidRedirectUri,
     InvalidClientID,
+    InvalidClientMetadata(String),
+    UnauthorizedGrantType,
     UserAlreadyExist,
     UserAlreadyActivated,
+    MissingSigningKey,
+    SigningKeyPinned,
+    MissingFederatedEmail,
+    AuthorizationPending,
+    SlowDown,
+    AccessDenied,
+    InvalidScope,
+    InsufficientScope,
+    DirectoryError(String),
     BcryptError(bcrypt::BcryptError),
     DBError(DieselError),
     JSONError(serde_json::Error),
@@ -80,13 +93,33 @@ impl fmt::Display for AuthError {
             AuthError::ExpiredToken => write!(f, "Expired token"),
             AuthError::InvalidRedirectUri => write!(f, "Invalid redirect uri"),
             AuthError::InvalidClientID => write!(f, "Invalid client id"),
+            AuthError::InvalidClientMetadata(e) => write!(f, "Invalid client metadata: {}", e),
+            AuthError::UnauthorizedGrantType => {
+                write!(f, "Client is not allowed to use this grant type")
+            }
             AuthError::NotActivated => write!(f, "Not activated"),
+            AuthError::Locked => write!(f, "Account is locked"),
+            AuthError::PasswordResetRequired => write!(f, "Password reset required"),
             AuthError::UserAlreadyExist => write!(f, "User/Email already exist"),
             AuthError::BcryptError(e) => write!(f, "BcryptError {}", e),
             AuthError::DBError(e) => write!(f, "DBError {}", e),
             AuthError::JSONError(e) => write!(f, "JSONError {}", e),
             AuthError::InternalError(e) => write!(f, "InternalError {:?}", e),
             AuthError::UserAlreadyActivated => write!(f, "User already activated"),
+            AuthError::MissingSigningKey => write!(f, "Configured signing key doesn't exist"),
+            AuthError::SigningKeyPinned => write!(
+                f,
+                "Unset auth.signing_key_id before rotating, rotation would retire the configured key"
+            ),
+            AuthError::MissingFederatedEmail => {
+                write!(f, "Identity provider didn't share an email address")
+            }
+            AuthError::AuthorizationPending => write!(f, "Authorization pending"),
+            AuthError::SlowDown => write!(f, "Polling too frequently"),
+            AuthError::AccessDenied => write!(f, "Access denied"),
+            AuthError::InvalidScope => write!(f, "Invalid scope"),
+            AuthError::InsufficientScope => write!(f, "Token doesn't have the required scope"),
+            AuthError::DirectoryError(e) => write!(f, "DirectoryError {}", e),
         }
     }
 }
@@ -99,10 +132,21 @@ impl convert::From<AuthError> for actix_web::Error {
             AuthError::InvalidToken => actix_web::error::ErrorBadRequest(e),
             AuthError::InvalidRedirectUri => actix_web::error::ErrorBadRequest(e),
             AuthError::InvalidClientID => actix_web::error::ErrorBadRequest(e),
+            AuthError::InvalidClientMetadata(_) => actix_web::error::ErrorBadRequest(e),
+            AuthError::UnauthorizedGrantType => actix_web::error::ErrorBadRequest(e),
             AuthError::UserAlreadyExist => actix_web::error::ErrorBadRequest(e),
             AuthError::ExpiredToken => actix_web::error::ErrorUnauthorized(e),
             AuthError::NotActivated => actix_web::error::ErrorUnauthorized(e),
+            AuthError::Locked => actix_web::error::ErrorUnauthorized(e),
+            AuthError::PasswordResetRequired => actix_web::error::ErrorUnauthorized(e),
             AuthError::UserAlreadyActivated => actix_web::error::ErrorBadRequest(e),
+            AuthError::SigningKeyPinned => actix_web::error::ErrorConflict(e),
+            AuthError::MissingFederatedEmail => actix_web::error::ErrorBadRequest(e),
+            AuthError::AuthorizationPending => actix_web::error::ErrorBadRequest(e),
+            AuthError::SlowDown => actix_web::error::ErrorBadRequest(e),
+            AuthError::AccessDenied => actix_web::error::ErrorForbidden(e),
+            AuthError::InvalidScope => actix_web::error::ErrorBadRequest(e),
+            AuthError::InsufficientScope => actix_web::error::ErrorForbidden(e),
             _ => actix_web::error::ErrorInternalServerError(e),
         }
     }
@@ -4,23 +4,48 @@ use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use base64;
 use bcrypt;
+use chrono::Utc;
+use futures::future::{self, FutureExt, LocalBoxFuture};
 use magic_crypt;
 use magic_crypt::MagicCrypt;
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
 use serde_json;
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
 use url::Url;
 
 pub use error::AuthError;
 
-use crate::auth::error::AuthError::{InvalidClientID, InvalidRedirectUri, InvalidToken};
+use crate::auth::directory::{Directory, DirectoryLogin, DirectoryUser, DIRECTORY_PROVIDER};
+use crate::auth::error::AuthError::{
+    InvalidClientID, InvalidClientMetadata, InvalidRedirectUri, InvalidToken,
+};
 use crate::auth::model::{
-    ActivationCodePayload, AuthCode, AuthCodePayload, AuthResult, RefreshToken, Token, TokenPayload,
+    ActivationCodePayload, AuthCode, AuthCodePayload, AuthResult, ClientMetadata, ClientSecret,
+    DeviceAuthorizationGrant, FederatedIdentityClaims, FederationStatePayload,
+    PasswordResetCodePayload, RefreshToken, Token, TokenPayload, GRANT_TYPE_AUTHORIZATION_CODE,
+    GRANT_TYPE_DEVICE_CODE, PERSONAL_ACCESS_TOKEN_PREFIX, PERSONAL_ACCESS_TOKEN_SCOPES,
+    SUPPORTED_GRANT_TYPES,
+};
+use crate::database::handler::client_credential::{
+    ClientCredential, ClientCredentialHandler, ClientCredentialMetadata, NewClientCredential,
+};
+use crate::database::handler::device_authorization::{
+    DeviceAuthorizationHandler, NewDeviceAuthorization,
+};
+use crate::database::handler::federated_identity::{
+    FederatedIdentityHandler, NewFederatedIdentity,
 };
-use crate::database::handler::client_credential::ClientCredentialHandler;
+use crate::database::handler::personal_access_token::{
+    NewPersonalAccessToken, PersonalAccessToken, PersonalAccessTokenHandler,
+};
+use crate::database::handler::signing_key::{NewSigningKey, SigningKey, SigningKeyHandler};
 use crate::database::handler::user::{NewUser, User, UserHandler};
+use crate::database::handler::DbError;
 use std::rc::Rc;
 
+pub mod directory;
 mod error;
 pub mod model;
 
@@ -29,7 +54,24 @@ pub trait AuthHandler {
     fn generate_activation_code(&self, username: &String) -> AuthResult<String>;
     fn activate(&self, activation_code: &String) -> AuthResult<usize>;
 
-    fn get_token(&self, username: &String, password: &String) -> AuthResult<(Token, RefreshToken)>;
+    /// Asks the directory about a login before it reaches the synchronous calls below.
+    fn check_directory(
+        &self,
+        username: &String,
+        password: &String,
+    ) -> LocalBoxFuture<'static, DirectoryLogin>;
+    fn authenticate(
+        &self,
+        username: &String,
+        password: &String,
+        directory_login: &DirectoryLogin,
+    ) -> AuthResult<User>;
+    fn get_token(
+        &self,
+        username: &String,
+        password: &String,
+        directory_login: &DirectoryLogin,
+    ) -> AuthResult<(Token, RefreshToken)>;
     fn exchange_token(
         &self,
         auth_code_string: &String,
@@ -40,44 +82,160 @@ pub trait AuthHandler {
         &self,
         username: &String,
         password: &String,
+        directory_login: &DirectoryLogin,
         client_id: &String,
         redirect_uri: &String,
     ) -> AuthResult<AuthCode>;
 
     fn check_redirect_uri(&self, client_id: &String, redirect_uri: &String) -> AuthResult<bool>;
+    fn authenticate_client(
+        &self,
+        client_id: &String,
+        client_secret: &String,
+    ) -> AuthResult<ClientCredential>;
     fn register(&self, username: &String, email: &String, password: &String) -> AuthResult<()>;
     fn inspect(&self, encrypted_token: &String) -> AuthResult<TokenPayload>;
+
+    fn impersonate(&self, impersonator: &String, username: &String) -> AuthResult<Token>;
+    fn force_password_reset(&self, username: &String) -> AuthResult<(String, String)>;
+    fn reset_password(&self, reset_code: &String, password: &String) -> AuthResult<()>;
+
+    fn register_client(
+        &self,
+        owner: &String,
+        metadata: &ClientMetadata,
+    ) -> AuthResult<(ClientCredential, ClientSecret)>;
+    fn update_client(
+        &self,
+        client_id: &String,
+        metadata: &ClientMetadata,
+    ) -> AuthResult<ClientCredential>;
+    fn rotate_client_secret(
+        &self,
+        client_id: &String,
+    ) -> AuthResult<(ClientCredential, ClientSecret)>;
+
+    fn rotate_signing_key(&self, grace_period: u64) -> AuthResult<SigningKey>;
+
+    fn generate_federation_state(
+        &self,
+        provider: &String,
+        client_id: &String,
+        redirect_uri: &String,
+    ) -> AuthResult<(String, String)>;
+    fn verify_federation_state(&self, state: &String) -> AuthResult<FederationStatePayload>;
+    fn get_federated_authorization_code(
+        &self,
+        identity: &FederatedIdentityClaims,
+        client_id: &String,
+    ) -> AuthResult<AuthCode>;
+
+    fn request_device_authorization(
+        &self,
+        client_id: &String,
+    ) -> AuthResult<DeviceAuthorizationGrant>;
+    fn decide_device_authorization(
+        &self,
+        user_code: &String,
+        username: &String,
+        password: &String,
+        directory_login: &DirectoryLogin,
+        is_approved: bool,
+    ) -> AuthResult<()>;
+    fn exchange_device_code(
+        &self,
+        client_id: &String,
+        device_code: &String,
+    ) -> AuthResult<(Token, RefreshToken)>;
+
+    fn create_personal_access_token(
+        &self,
+        username: &String,
+        name: &String,
+        scopes: &Vec<String>,
+        lifetime: Option<u64>,
+    ) -> AuthResult<(PersonalAccessToken, Token)>;
+    fn get_personal_access_tokens(&self, username: &String)
+        -> AuthResult<Vec<PersonalAccessToken>>;
+    fn revoke_personal_access_token(&self, username: &String, id: i32) -> AuthResult<()>;
 }
 
 const ACTIVATION_CODE_PREFIX: &str = "activation-code-";
 const AUTH_CODE_PREFIX: &str = "authorization-code-";
 const TOKEN_PREFIX: &str = "token-";
+const PASSWORD_RESET_CODE_PREFIX: &str = "password-reset-code-";
+const FEDERATION_STATE_PREFIX: &str = "federation-state-";
+
+/// Tokens issued before the keyring existed carry no key id and are checked against
+/// `cypher_key`, which joins the keyring under this id on the first rotation.
+const LEGACY_KEY_ID: &str = "legacy";
+const KEY_ID_SEPARATOR: char = '.';
+
+/// User codes avoid vowels and look-alike characters so they are easy to read off one screen
+/// and type into another, see RFC 8628 section 6.1
+const USER_CODE_ALPHABET: &[u8] = b"BCDFGHJKLMNPQRSTVWXZ";
+const USER_CODE_LENGTH: usize = 8;
+/// RFC 8628 section 3.5, clients polling too fast have to add 5 seconds to their interval
+const SLOW_DOWN_INCREMENT: i32 = 5;
 
 pub struct Auth {
     cypher_key: String,
+    signing_key_id: Option<String>,
     token_lifetime: u64,
     auth_code_lifetime: u64,
     activation_code_lifetime: u64,
+    password_reset_code_lifetime: u64,
+    device_code_lifetime: u64,
+    device_poll_interval: u64,
+    personal_access_token_lifetime: u64,
+    max_login_attempts: i32,
     user_handler: Rc<dyn UserHandler>,
     client_credential_handler: Rc<dyn ClientCredentialHandler>,
+    signing_key_handler: Rc<dyn SigningKeyHandler>,
+    federated_identity_handler: Rc<dyn FederatedIdentityHandler>,
+    device_authorization_handler: Rc<dyn DeviceAuthorizationHandler>,
+    personal_access_token_handler: Rc<dyn PersonalAccessTokenHandler>,
+    directory: Option<Rc<dyn Directory>>,
 }
 
 impl Auth {
     pub fn new(
         cypher_key: String,
+        signing_key_id: Option<String>,
         token_lifetime: u64,
         auth_code_lifetime: u64,
         activation_code_lifetime: u64,
+        password_reset_code_lifetime: u64,
+        device_code_lifetime: u64,
+        device_poll_interval: u64,
+        personal_access_token_lifetime: u64,
+        max_login_attempts: i32,
         user_handler: Rc<dyn UserHandler>,
         client_credential_handler: Rc<dyn ClientCredentialHandler>,
+        signing_key_handler: Rc<dyn SigningKeyHandler>,
+        federated_identity_handler: Rc<dyn FederatedIdentityHandler>,
+        device_authorization_handler: Rc<dyn DeviceAuthorizationHandler>,
+        personal_access_token_handler: Rc<dyn PersonalAccessTokenHandler>,
+        directory: Option<Rc<dyn Directory>>,
     ) -> Auth {
         Auth {
             cypher_key,
+            signing_key_id,
             token_lifetime,
             auth_code_lifetime,
             activation_code_lifetime,
+            password_reset_code_lifetime,
+            device_code_lifetime,
+            device_poll_interval,
+            personal_access_token_lifetime,
+            max_login_attempts,
             user_handler,
             client_credential_handler,
+            signing_key_handler,
+            federated_identity_handler,
+            device_authorization_handler,
+            personal_access_token_handler,
+            directory,
         }
     }
 }
@@ -129,9 +287,47 @@ impl AuthHandler for Auth {
             .activate_by_username(&activation_code.username)?)
     }
 
-    fn get_token(&self, username: &String, password: &String) -> AuthResult<(Token, RefreshToken)> {
-        let potential_user = self.get_potential_user(username, password)?;
-        let token = self.generate_token(&potential_user.username)?;
+    fn check_directory(
+        &self,
+        username: &String,
+        password: &String,
+    ) -> LocalBoxFuture<'static, DirectoryLogin> {
+        let directory = match &self.directory {
+            Some(directory) => directory,
+            None => return future::ready(DirectoryLogin::Skipped).boxed_local(),
+        };
+
+        directory
+            .authenticate(username, password)
+            .map(|result| match result {
+                Ok(Some(directory_user)) => DirectoryLogin::Verified(directory_user),
+                Ok(None) => DirectoryLogin::Skipped,
+                Err(AuthError::DirectoryError(e)) => {
+                    println!("Directory unavailable, falling back to local users: {}", e);
+                    DirectoryLogin::Skipped
+                }
+                Err(_) => DirectoryLogin::Rejected,
+            })
+            .boxed_local()
+    }
+
+    fn authenticate(
+        &self,
+        username: &String,
+        password: &String,
+        directory_login: &DirectoryLogin,
+    ) -> AuthResult<User> {
+        self.get_potential_user(username, password, directory_login)
+    }
+
+    fn get_token(
+        &self,
+        username: &String,
+        password: &String,
+        directory_login: &DirectoryLogin,
+    ) -> AuthResult<(Token, RefreshToken)> {
+        let potential_user = self.get_potential_user(username, password, directory_login)?;
+        let token = self.generate_token(&potential_user.username, None, None)?;
         let refresh_token = self.generate_refresh_token(&potential_user.username)?;
 
         Ok((token, refresh_token))
@@ -164,11 +360,11 @@ impl AuthHandler for Auth {
             o => o,
         }?;
 
-        if !client_credential.secret.eq(client_secret) {
+        if !verify_secret(client_secret, &client_credential.secret) {
             return Err(InvalidClientID);
         };
 
-        let token = self.generate_token(&auth_code.username)?;
+        let token = self.generate_token(&auth_code.username, Some(&auth_code.client_id), None)?;
         let refresh_token = self.generate_refresh_token(&auth_code.username)?;
 
         Ok((token, refresh_token))
@@ -178,13 +374,15 @@ impl AuthHandler for Auth {
         &self,
         username: &String,
         password: &String,
+        directory_login: &DirectoryLogin,
         client_id: &String,
         redirect_uri: &String,
     ) -> AuthResult<AuthCode> {
         if !self.check_redirect_uri(client_id, redirect_uri)? {
             return Err(InvalidRedirectUri);
         }
-        let potential_user = self.get_potential_user(username, password)?;
+        self.check_grant_type(client_id, GRANT_TYPE_AUTHORIZATION_CODE)?;
+        let potential_user = self.get_potential_user(username, password, directory_login)?;
         self.generate_auth_code(&potential_user.username, client_id)
     }
 
@@ -199,7 +397,24 @@ impl AuthHandler for Auth {
         parsed_url.set_query(None);
         let url = parsed_url.into_string();
 
-        return Ok(client_credential.redirect_uri.eq(&url));
+        return Ok(client_credential.redirect_uris.contains(&url));
+    }
+
+    fn authenticate_client(
+        &self,
+        client_id: &String,
+        client_secret: &String,
+    ) -> AuthResult<ClientCredential> {
+        let client_credential = match self.client_credential_handler.get_by_id(client_id) {
+            Err(diesel::NotFound) => return Err(InvalidClientID),
+            o => o,
+        }?;
+
+        if verify_secret(client_secret, &client_credential.secret) {
+            Ok(client_credential)
+        } else {
+            Err(InvalidClientID)
+        }
     }
 
     fn register(&self, username: &String, email: &String, password: &String) -> AuthResult<()> {
@@ -215,6 +430,10 @@ impl AuthHandler for Auth {
         Ok(self.user_handler.new_user(&user)?)
     }
     fn inspect(&self, encrypted_token: &String) -> AuthResult<TokenPayload> {
+        if encrypted_token.starts_with(PERSONAL_ACCESS_TOKEN_PREFIX) {
+            return self.inspect_personal_access_token(encrypted_token);
+        }
+
         let token_bytes = self.decrypt(encrypted_token)?;
 
         let current_time = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
@@ -231,24 +450,659 @@ impl AuthHandler for Auth {
             Ok(token)
         }
     }
+
+    fn impersonate(&self, impersonator: &String, username: &String) -> AuthResult<Token> {
+        let user = self.user_handler.get_by_username(username)?;
+        self.generate_token(&user.username, None, Some(impersonator))
+    }
+
+    fn force_password_reset(&self, username: &String) -> AuthResult<(String, String)> {
+        let user = self.user_handler.set_must_reset_password(username, true)?;
+
+        let expiry_time = SystemTime::now()
+            .add(Duration::new(self.password_reset_code_lifetime, 0))
+            .duration_since(UNIX_EPOCH)?
+            .as_millis();
+
+        let reset_code_payload = PasswordResetCodePayload {
+            salt: PASSWORD_RESET_CODE_PREFIX.to_string() + generate_salt().as_ref(),
+            username: user.username.to_owned(),
+            password_salt: user.salt.to_owned(),
+            expiry_timestamp: expiry_time,
+        };
+
+        let reset_code_bytes = serde_json::to_vec(&reset_code_payload)?;
+        let reset_code = self.encrypt(&reset_code_bytes)?;
+        Ok((user.email, reset_code))
+    }
+
+    fn reset_password(&self, reset_code: &String, password: &String) -> AuthResult<()> {
+        let reset_code_bytes = self.decrypt(reset_code)?;
+
+        let reset_code: PasswordResetCodePayload = serde_json::from_slice(&reset_code_bytes)?;
+
+        if !reset_code.salt.starts_with(PASSWORD_RESET_CODE_PREFIX) {
+            return Err(AuthError::InvalidToken);
+        }
+
+        let current_time = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+        if reset_code.expiry_timestamp < current_time {
+            return Err(AuthError::ExpiredToken);
+        };
+
+        // The password salt changes on every reset, so a used code can't be replayed
+        let user = self.user_handler.get_by_username(&reset_code.username)?;
+        if user.salt != reset_code.password_salt {
+            return Err(AuthError::InvalidToken);
+        }
+
+        let salt = generate_salt();
+        self.user_handler.update_password(
+            &user.username,
+            &generate_password(password, &salt)?,
+            &salt,
+        )?;
+        Ok(())
+    }
+
+    fn register_client(
+        &self,
+        owner: &String,
+        metadata: &ClientMetadata,
+    ) -> AuthResult<(ClientCredential, ClientSecret)> {
+        let redirect_uris = validate_client_metadata(metadata)?;
+        let client_id = generate_client_id();
+        let client_secret = generate_client_secret();
+
+        let client_credential = self
+            .client_credential_handler
+            .insert(&NewClientCredential {
+                id: &client_id,
+                secret: &hash_secret(&client_secret),
+                redirect_uris: &redirect_uris,
+                name: &metadata.client_name,
+                logo_uri: metadata.logo_uri.as_ref(),
+                grant_types: &metadata.grant_types,
+                owner,
+            })?;
+
+        Ok((client_credential, client_secret))
+    }
+
+    fn update_client(
+        &self,
+        client_id: &String,
+        metadata: &ClientMetadata,
+    ) -> AuthResult<ClientCredential> {
+        let redirect_uris = validate_client_metadata(metadata)?;
+
+        Ok(self.client_credential_handler.update_metadata(
+            client_id,
+            &ClientCredentialMetadata {
+                redirect_uris: &redirect_uris,
+                name: &metadata.client_name,
+                logo_uri: metadata.logo_uri.as_ref(),
+                grant_types: &metadata.grant_types,
+            },
+        )?)
+    }
+
+    fn rotate_client_secret(
+        &self,
+        client_id: &String,
+    ) -> AuthResult<(ClientCredential, ClientSecret)> {
+        let client_secret = generate_client_secret();
+        let client_credential = self
+            .client_credential_handler
+            .update_secret(client_id, &hash_secret(&client_secret))?;
+
+        Ok((client_credential, client_secret))
+    }
+
+    fn rotate_signing_key(&self, grace_period: u64) -> AuthResult<SigningKey> {
+        // Tokens keep being signed with a pinned key, once it expires they can't be issued
+        if self.signing_key_id.is_some() {
+            return Err(AuthError::SigningKeyPinned);
+        }
+
+        if let Err(DbError::NotFound) = self.signing_key_handler.get_active() {
+            if let Err(DbError::NotFound) = self
+                .signing_key_handler
+                .get_by_id(&LEGACY_KEY_ID.to_owned())
+            {
+                self.signing_key_handler.insert(&NewSigningKey {
+                    id: &LEGACY_KEY_ID.to_owned(),
+                    secret: &self.cypher_key,
+                    is_active: true,
+                })?;
+            }
+        }
+
+        let retired_expires_at =
+            Utc::now().naive_utc() + chrono::Duration::seconds(grace_period as i64);
+
+        Ok(self.signing_key_handler.rotate(
+            &NewSigningKey {
+                id: &generate_key_id(),
+                secret: &generate_key_secret(),
+                is_active: true,
+            },
+            &retired_expires_at,
+        )?)
+    }
+
+    fn generate_federation_state(
+        &self,
+        provider: &String,
+        client_id: &String,
+        redirect_uri: &String,
+    ) -> AuthResult<(String, String)> {
+        if !self.check_redirect_uri(client_id, redirect_uri)? {
+            return Err(InvalidRedirectUri);
+        }
+        self.check_grant_type(client_id, GRANT_TYPE_AUTHORIZATION_CODE)?;
+
+        let expiry_time = SystemTime::now()
+            .add(Duration::new(self.auth_code_lifetime, 0))
+            .duration_since(UNIX_EPOCH)?
+            .as_millis();
+
+        let state_payload = FederationStatePayload {
+            salt: FEDERATION_STATE_PREFIX.to_string() + generate_salt().as_ref(),
+            provider: provider.to_owned(),
+            client_id: client_id.to_owned(),
+            redirect_uri: redirect_uri.to_owned(),
+            expiry_timestamp: expiry_time,
+        };
+
+        let state_bytes = serde_json::to_vec(&state_payload)?;
+        let state = self.encrypt(&state_bytes)?;
+        Ok((state, state_payload.salt))
+    }
+
+    fn verify_federation_state(&self, state: &String) -> AuthResult<FederationStatePayload> {
+        let state_bytes = self.decrypt(state)?;
+
+        let state: FederationStatePayload = serde_json::from_slice(&state_bytes)?;
+
+        if !state.salt.starts_with(FEDERATION_STATE_PREFIX) {
+            return Err(AuthError::InvalidToken);
+        }
+
+        let current_time = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+        if state.expiry_timestamp < current_time {
+            return Err(AuthError::ExpiredToken);
+        };
+
+        Ok(state)
+    }
+
+    fn get_federated_authorization_code(
+        &self,
+        identity: &FederatedIdentityClaims,
+        client_id: &String,
+    ) -> AuthResult<AuthCode> {
+        let user = self.get_or_provision_federated_user(identity)?;
+
+        if user.is_locked {
+            return Err(AuthError::Locked);
+        }
+        if !user.is_activated {
+            return Err(AuthError::NotActivated);
+        }
+        if user.must_reset_password {
+            return Err(AuthError::PasswordResetRequired);
+        }
+
+        self.generate_auth_code(&user.username, client_id)
+    }
+
+    fn request_device_authorization(
+        &self,
+        client_id: &String,
+    ) -> AuthResult<DeviceAuthorizationGrant> {
+        self.check_grant_type(client_id, GRANT_TYPE_DEVICE_CODE)?;
+
+        let device_code = generate_device_code();
+        let user_code = generate_user_code();
+        let expires_at =
+            Utc::now().naive_utc() + chrono::Duration::seconds(self.device_code_lifetime as i64);
+
+        self.device_authorization_handler
+            .insert(&NewDeviceAuthorization {
+                device_code: &hash_secret(&device_code),
+                user_code: &user_code,
+                client_id,
+                poll_interval: self.device_poll_interval as i32,
+                expires_at: &expires_at,
+            })?;
+
+        Ok(DeviceAuthorizationGrant {
+            device_code,
+            user_code: format_user_code(&user_code),
+            expires_in: self.device_code_lifetime,
+            interval: self.device_poll_interval,
+        })
+    }
+
+    fn decide_device_authorization(
+        &self,
+        user_code: &String,
+        username: &String,
+        password: &String,
+        directory_login: &DirectoryLogin,
+        is_approved: bool,
+    ) -> AuthResult<()> {
+        let user_code = normalize_user_code(user_code);
+        let device_authorization = self
+            .device_authorization_handler
+            .get_by_user_code(&user_code)
+            .map_err(|_| InvalidToken)?;
+
+        if device_authorization.expires_at <= Utc::now().naive_utc() {
+            return Err(AuthError::ExpiredToken);
+        }
+        if device_authorization.username.is_some() {
+            return Err(InvalidToken);
+        }
+
+        let user = self.get_potential_user(username, password, directory_login)?;
+        self.device_authorization_handler
+            .decide(&user_code, &user.username, !is_approved)?;
+        Ok(())
+    }
+
+    fn exchange_device_code(
+        &self,
+        client_id: &String,
+        device_code: &String,
+    ) -> AuthResult<(Token, RefreshToken)> {
+        let hashed_device_code = hash_secret(device_code);
+        let device_authorization = self
+            .device_authorization_handler
+            .get_by_device_code(&hashed_device_code)
+            .map_err(|_| InvalidToken)?;
+
+        if &device_authorization.client_id != client_id {
+            return Err(InvalidClientID);
+        }
+
+        let now = Utc::now().naive_utc();
+        if device_authorization.expires_at <= now {
+            self.device_authorization_handler
+                .delete(&hashed_device_code)?;
+            return Err(AuthError::ExpiredToken);
+        }
+
+        if let Some(last_polled_at) = device_authorization.last_polled_at {
+            let interval = chrono::Duration::seconds(device_authorization.poll_interval as i64);
+            if last_polled_at + interval > now {
+                self.device_authorization_handler.record_poll(
+                    &hashed_device_code,
+                    &now,
+                    device_authorization.poll_interval + SLOW_DOWN_INCREMENT,
+                )?;
+                return Err(AuthError::SlowDown);
+            }
+        }
+
+        let username = match device_authorization.username {
+            Some(username) => username,
+            None => {
+                self.device_authorization_handler.record_poll(
+                    &hashed_device_code,
+                    &now,
+                    device_authorization.poll_interval,
+                )?;
+                return Err(AuthError::AuthorizationPending);
+            }
+        };
+
+        self.device_authorization_handler
+            .delete(&hashed_device_code)?;
+        if device_authorization.is_denied {
+            return Err(AuthError::AccessDenied);
+        }
+
+        let token = self.generate_token(&username, Some(client_id), None)?;
+        let refresh_token = self.generate_refresh_token(&username)?;
+
+        Ok((token, refresh_token))
+    }
+
+    fn create_personal_access_token(
+        &self,
+        username: &String,
+        name: &String,
+        scopes: &Vec<String>,
+        lifetime: Option<u64>,
+    ) -> AuthResult<(PersonalAccessToken, Token)> {
+        if name.trim().is_empty()
+            || scopes.is_empty()
+            || scopes
+                .iter()
+                .any(|s| !PERSONAL_ACCESS_TOKEN_SCOPES.contains(&s.as_str()))
+        {
+            return Err(AuthError::InvalidScope);
+        }
+
+        let user = self.user_handler.get_by_username(username)?;
+        let lifetime = lifetime
+            .unwrap_or(self.personal_access_token_lifetime)
+            .min(self.personal_access_token_lifetime);
+        let expires_at = Utc::now().naive_utc() + chrono::Duration::seconds(lifetime as i64);
+
+        let token = generate_personal_access_token();
+        let personal_access_token =
+            self.personal_access_token_handler
+                .insert(&NewPersonalAccessToken {
+                    user_id: user.id,
+                    name,
+                    token_hash: &hash_secret(&token),
+                    scopes,
+                    expires_at: &expires_at,
+                })?;
+
+        Ok((personal_access_token, token))
+    }
+
+    fn get_personal_access_tokens(
+        &self,
+        username: &String,
+    ) -> AuthResult<Vec<PersonalAccessToken>> {
+        let user = self.user_handler.get_by_username(username)?;
+        Ok(self.personal_access_token_handler.get_by_user_id(user.id)?)
+    }
+
+    fn revoke_personal_access_token(&self, username: &String, id: i32) -> AuthResult<()> {
+        let user = self.user_handler.get_by_username(username)?;
+        self.personal_access_token_handler
+            .revoke(id, user.id, &Utc::now().naive_utc())?;
+        Ok(())
+    }
 }
 
 impl Auth {
-    fn get_potential_user(&self, username: &String, password: &String) -> AuthResult<User> {
+    fn inspect_personal_access_token(&self, token: &String) -> AuthResult<TokenPayload> {
+        let personal_access_token = self
+            .personal_access_token_handler
+            .get_by_token_hash(&hash_secret(token))
+            .map_err(|_| InvalidToken)?;
+
+        let now = Utc::now().naive_utc();
+        if personal_access_token.revoked_at.is_some() {
+            return Err(InvalidToken);
+        }
+        if !personal_access_token.is_usable(&now) {
+            return Err(AuthError::ExpiredToken);
+        }
+
+        let user = self.user_handler.get_by_id(personal_access_token.user_id)?;
+        if user.is_locked {
+            return Err(AuthError::Locked);
+        }
+        if !user.is_activated {
+            return Err(AuthError::NotActivated);
+        }
+
+        self.personal_access_token_handler
+            .touch(personal_access_token.id, &now)?;
+
+        Ok(TokenPayload {
+            salt: PERSONAL_ACCESS_TOKEN_PREFIX.to_owned() + &personal_access_token.id.to_string(),
+            username: user.username,
+            expiry_timestamp: personal_access_token.expires_at.timestamp_millis() as u128,
+            issued_timestamp: Some(personal_access_token.created_at.timestamp_millis() as u128),
+            client_id: None,
+            scope: Some(personal_access_token.scopes.join(" ")),
+            impersonator: None,
+        })
+    }
+
+    fn get_potential_user(
+        &self,
+        username: &String,
+        password: &String,
+        directory_login: &DirectoryLogin,
+    ) -> AuthResult<User> {
+        let subject = match directory_login {
+            DirectoryLogin::Verified(directory_user) => &directory_user.username,
+            DirectoryLogin::Rejected => username,
+            DirectoryLogin::Skipped => return self.get_local_user(username, password),
+        };
+        let linked = self.get_linked_directory_user(subject)?;
+        let local_exists = linked.is_none() && self.local_user_exists(subject)?;
+
+        match choose_login_check(directory_login, linked.is_some(), local_exists) {
+            LoginCheck::Directory(directory_user) => {
+                self.get_directory_user(directory_user, linked)
+            }
+            LoginCheck::DirectoryRejected => self.reject_directory_login(linked),
+            LoginCheck::Local => self.get_local_user(username, password),
+        }
+    }
+
+    fn get_local_user(&self, username: &String, password: &String) -> AuthResult<User> {
         let user = self.user_handler.get_by_username(username)?;
+        if user.is_locked {
+            return Err(AuthError::Locked);
+        }
+
         if verify(&user.password, password, &user.salt)? {
             if !user.is_activated {
                 Err(AuthError::NotActivated)
+            } else if user.must_reset_password {
+                Err(AuthError::PasswordResetRequired)
             } else {
+                if user.failed_login_attempts > 0 {
+                    self.user_handler.unlock(&user.username)?;
+                }
                 Ok(user)
             }
+        } else {
+            let user = self
+                .user_handler
+                .record_failed_login(&user.username, self.max_login_attempts)?;
+            if user.is_locked {
+                Err(AuthError::Locked)
+            } else {
+                Err(AuthError::WrongPassword)
+            }
+        }
+    }
+
+    /// Directory users are mirrored into the `user` table, so tokens and ownership keep working
+    /// with local usernames. The lockout and password reset flags of the mirror still apply.
+    fn get_directory_user(
+        &self,
+        directory_user: &DirectoryUser,
+        linked: Option<User>,
+    ) -> AuthResult<User> {
+        let user = match linked {
+            Some(user) => user,
+            None => self.provision_directory_user(directory_user)?,
+        };
+
+        if user.is_locked {
+            Err(AuthError::Locked)
+        } else if !user.is_activated {
+            Err(AuthError::NotActivated)
+        } else if user.must_reset_password {
+            Err(AuthError::PasswordResetRequired)
+        } else {
+            if user.failed_login_attempts > 0 {
+                self.user_handler.unlock(&user.username)?;
+            }
+            Ok(user)
+        }
+    }
+
+    /// Wrong directory passwords count towards the lockout of the mirrored account, like local
+    /// ones do.
+    fn reject_directory_login(&self, linked: Option<User>) -> AuthResult<User> {
+        let user = match linked {
+            Some(user) => user,
+            None => return Err(AuthError::WrongPassword),
+        };
+        if user.is_locked {
+            return Err(AuthError::Locked);
+        }
+
+        let user = self
+            .user_handler
+            .record_failed_login(&user.username, self.max_login_attempts)?;
+        if user.is_locked {
+            Err(AuthError::Locked)
         } else {
             Err(AuthError::WrongPassword)
         }
     }
 
-    fn generate_token(&self, username: &String) -> AuthResult<Token> {
-        let expiry_time = SystemTime::now()
+    fn local_user_exists(&self, username: &String) -> AuthResult<bool> {
+        match self.user_handler.get_by_username(username) {
+            Ok(_) => Ok(true),
+            Err(DbError::NotFound) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn get_linked_directory_user(&self, username: &String) -> AuthResult<Option<User>> {
+        match self
+            .federated_identity_handler
+            .get_by_subject(&DIRECTORY_PROVIDER.to_owned(), username)
+        {
+            Ok(linked) => Ok(Some(self.user_handler.get_by_id(linked.user_id)?)),
+            Err(DbError::NotFound) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Local accounts that weren't provisioned from the directory keep their username, logins
+    /// for them never get here. Refusing one that was registered in the meantime keeps it so.
+    fn provision_directory_user(&self, directory_user: &DirectoryUser) -> AuthResult<User> {
+        let email = directory_user
+            .email
+            .as_ref()
+            .ok_or(AuthError::MissingFederatedEmail)?;
+
+        match self.user_handler.get_by_username(&directory_user.username) {
+            Ok(_) => return Err(AuthError::UserAlreadyExist),
+            Err(DbError::NotFound) => (),
+            Err(e) => return Err(e.into()),
+        }
+
+        self.register(&directory_user.username, email, &generate_salt())?;
+        self.user_handler
+            .activate_by_username(&directory_user.username)?;
+        let user = self
+            .user_handler
+            .get_by_username(&directory_user.username)?;
+
+        self.federated_identity_handler
+            .insert(&NewFederatedIdentity {
+                provider: &DIRECTORY_PROVIDER.to_owned(),
+                subject: &directory_user.username,
+                user_id: user.id,
+                email,
+            })?;
+
+        Ok(user)
+    }
+
+    /// Finds the local account linked to an upstream identity. Unlinked identities are linked
+    /// to the activated account with the same email if the provider verified it, or get a fresh
+    /// account.
+    fn get_or_provision_federated_user(
+        &self,
+        identity: &FederatedIdentityClaims,
+    ) -> AuthResult<User> {
+        match self
+            .federated_identity_handler
+            .get_by_subject(&identity.provider, &identity.subject)
+        {
+            Ok(linked) => return Ok(self.user_handler.get_by_id(linked.user_id)?),
+            Err(DbError::NotFound) => (),
+            Err(e) => return Err(e.into()),
+        }
+
+        let email = identity
+            .email
+            .as_ref()
+            .ok_or(AuthError::MissingFederatedEmail)?;
+
+        let user = match self.user_handler.get_by_email(email) {
+            Ok(user) if can_link_by_email(&user, identity) => user,
+            Ok(_) => return Err(AuthError::UserAlreadyExist),
+            Err(DbError::NotFound) => self.provision_federated_user(identity, email)?,
+            Err(e) => return Err(e.into()),
+        };
+
+        self.federated_identity_handler
+            .insert(&NewFederatedIdentity {
+                provider: &identity.provider,
+                subject: &identity.subject,
+                user_id: user.id,
+                email,
+            })?;
+
+        Ok(user)
+    }
+
+    fn provision_federated_user(
+        &self,
+        identity: &FederatedIdentityClaims,
+        email: &String,
+    ) -> AuthResult<User> {
+        let base_username: String = identity
+            .preferred_username
+            .as_ref()
+            .unwrap_or(email)
+            .split('@')
+            .next()
+            .unwrap_or("")
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || "_-.".contains(*c))
+            .collect();
+
+        let mut username = base_username.to_owned();
+        while username.is_empty() || self.user_handler.get_by_username(&username).is_ok() {
+            let suffix: String = thread_rng().sample_iter(&Alphanumeric).take(4).collect();
+            username = format!("{}-{}", base_username, suffix);
+        }
+
+        // Federated accounts can only login through their provider until they reset the password
+        self.register(&username, email, &generate_salt())?;
+        self.user_handler.activate_by_username(&username)?;
+
+        Ok(self.user_handler.get_by_username(&username)?)
+    }
+
+    fn check_grant_type(&self, client_id: &String, grant_type: &str) -> AuthResult<()> {
+        let client_credential = match self.client_credential_handler.get_by_id(client_id) {
+            Err(diesel::NotFound) => return Err(InvalidClientID),
+            o => o,
+        }?;
+
+        if client_credential
+            .grant_types
+            .iter()
+            .any(|g| g == grant_type)
+        {
+            Ok(())
+        } else {
+            Err(AuthError::UnauthorizedGrantType)
+        }
+    }
+
+    fn generate_token(
+        &self,
+        username: &String,
+        client_id: Option<&String>,
+        impersonator: Option<&String>,
+    ) -> AuthResult<Token> {
+        let now = SystemTime::now();
+        let issued_time = now.duration_since(UNIX_EPOCH)?.as_millis();
+        let expiry_time = now
             .add(Duration::new(self.token_lifetime, 0))
             .duration_since(UNIX_EPOCH)?
             .as_millis();
@@ -257,6 +1111,10 @@ impl Auth {
             salt: TOKEN_PREFIX.to_string() + generate_salt().as_ref(),
             username: username.to_owned(),
             expiry_timestamp: expiry_time,
+            issued_timestamp: Some(issued_time),
+            client_id: client_id.cloned(),
+            scope: None,
+            impersonator: impersonator.cloned(),
         };
 
         let token_bytes = serde_json::to_vec(&token)?;
@@ -290,8 +1148,13 @@ impl Auth {
     }
 
     fn decrypt(&self, encrypted_token: &String) -> AuthResult<Vec<u8>> {
+        let (key_id, encrypted_token) = match encrypted_token.find(KEY_ID_SEPARATOR) {
+            Some(index) => (&encrypted_token[..index], &encrypted_token[index + 1..]),
+            None => (LEGACY_KEY_ID, encrypted_token.as_str()),
+        };
+
         let encrypted_token_bytes = base64::decode_config(encrypted_token, base64::URL_SAFE)?;
-        let crypter = self.new_crypter();
+        let crypter = new_crypter(&self.verification_key(key_id)?);
         let mut crypter = panic::AssertUnwindSafe(crypter);
         let token_bytes = match panic::catch_unwind(move || {
             crypter.decrypt_bytes_to_bytes(&encrypted_token_bytes)
@@ -306,17 +1169,53 @@ impl Auth {
     }
 
     fn encrypt(&self, token_bytes: &Vec<u8>) -> AuthResult<String> {
-        let mut crypter = self.new_crypter();
+        let (key_id, key) = self.signing_key()?;
+        let mut crypter = new_crypter(&key);
         let encrypted_token_bytes = crypter.encrypt_bytes_to_bytes(&token_bytes);
         let encrypted_token = base64::encode_config(&encrypted_token_bytes, base64::URL_SAFE);
-        Ok(encrypted_token)
+
+        if key_id == LEGACY_KEY_ID {
+            Ok(encrypted_token)
+        } else {
+            Ok(format!("{}{}{}", key_id, KEY_ID_SEPARATOR, encrypted_token))
+        }
     }
 
-    fn new_crypter(&self) -> MagicCrypt {
-        new_magic_crypt!(self.cypher_key.clone(), 256)
+    /// Returns the id and secret of the key new tokens are signed with: the configured
+    /// `signing_key_id` if any, otherwise the active key of the keyring.
+    fn signing_key(&self) -> AuthResult<(String, String)> {
+        let signing_key = match &self.signing_key_id {
+            Some(key_id) => self.signing_key_handler.get_by_id(key_id),
+            None => self.signing_key_handler.get_active(),
+        };
+
+        match signing_key {
+            Ok(key) if !key.is_expired(&Utc::now().naive_utc()) => Ok((key.id, key.secret)),
+            Ok(_) | Err(DbError::NotFound) if self.signing_key_id.is_some() => {
+                Err(AuthError::MissingSigningKey)
+            }
+            Ok(_) | Err(DbError::NotFound) => {
+                Ok((LEGACY_KEY_ID.to_owned(), self.cypher_key.clone()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn verification_key(&self, key_id: &str) -> AuthResult<String> {
+        match self.signing_key_handler.get_by_id(&key_id.to_owned()) {
+            Ok(key) if key.is_expired(&Utc::now().naive_utc()) => Err(InvalidToken),
+            Ok(key) => Ok(key.secret),
+            Err(DbError::NotFound) if key_id == LEGACY_KEY_ID => Ok(self.cypher_key.clone()),
+            Err(DbError::NotFound) => Err(InvalidToken),
+            Err(e) => Err(e.into()),
+        }
     }
 }
 
+fn new_crypter(key: &String) -> MagicCrypt {
+    new_magic_crypt!(key, 256)
+}
+
 fn verify(key: &String, password: &String, salt: &String) -> AuthResult<bool> {
     Ok(bcrypt::verify(password.to_owned() + salt, key)?)
 }
@@ -328,3 +1227,237 @@ fn generate_password(password: &String, salt: &String) -> AuthResult<String> {
 fn generate_salt() -> String {
     thread_rng().sample_iter(&Alphanumeric).take(30).collect()
 }
+
+fn generate_key_id() -> String {
+    thread_rng().sample_iter(&Alphanumeric).take(8).collect()
+}
+
+fn generate_key_secret() -> String {
+    thread_rng().sample_iter(&Alphanumeric).take(64).collect()
+}
+
+fn generate_client_id() -> String {
+    thread_rng().sample_iter(&Alphanumeric).take(24).collect()
+}
+
+fn generate_client_secret() -> ClientSecret {
+    thread_rng().sample_iter(&Alphanumeric).take(48).collect()
+}
+
+fn generate_personal_access_token() -> Token {
+    let secret: String = thread_rng().sample_iter(&Alphanumeric).take(40).collect();
+    PERSONAL_ACCESS_TOKEN_PREFIX.to_owned() + &secret
+}
+
+fn generate_device_code() -> String {
+    thread_rng().sample_iter(&Alphanumeric).take(48).collect()
+}
+
+fn generate_user_code() -> String {
+    let mut rng = thread_rng();
+    (0..USER_CODE_LENGTH)
+        .map(|_| USER_CODE_ALPHABET[rng.gen_range(0, USER_CODE_ALPHABET.len())] as char)
+        .collect()
+}
+
+/// Splits the user code in two halves, e.g. `WDJBMJHT` becomes `WDJB-MJHT`
+fn format_user_code(user_code: &String) -> String {
+    let (head, tail) = user_code.split_at(USER_CODE_LENGTH / 2);
+    format!("{}-{}", head, tail)
+}
+
+/// Users may type the code in lowercase or with the dash left out
+fn normalize_user_code(user_code: &String) -> String {
+    user_code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+pub fn hash_secret(secret: &String) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+pub fn verify_secret(secret: &String, hashed_secret: &String) -> bool {
+    hash_secret(secret)
+        .as_bytes()
+        .ct_eq(hashed_secret.as_bytes())
+        .into()
+}
+
+/// An existing account is only handed to an upstream identity when the provider verified the
+/// email and the account proved it owns the address too, by being activated.
+fn can_link_by_email(user: &User, identity: &FederatedIdentityClaims) -> bool {
+    identity.email_verified && user.is_activated
+}
+
+#[derive(Debug, PartialEq)]
+enum LoginCheck<'a> {
+    /// Sign in the linked account, or provision one for a new directory user
+    Directory(&'a DirectoryUser),
+    /// Count the wrong directory password against the linked account
+    DirectoryRejected,
+    Local,
+}
+
+/// The directory only speaks for usernames it is linked to or that are free. A local account
+/// with the same username is checked against its own password, whatever the directory says.
+fn choose_login_check(
+    directory_login: &DirectoryLogin,
+    is_linked: bool,
+    local_exists: bool,
+) -> LoginCheck {
+    match directory_login {
+        DirectoryLogin::Verified(directory_user) if is_linked || !local_exists => {
+            LoginCheck::Directory(directory_user)
+        }
+        DirectoryLogin::Rejected if is_linked => LoginCheck::DirectoryRejected,
+        _ => LoginCheck::Local,
+    }
+}
+
+/// Validates the metadata and returns the normalized redirect uris, in the same form
+/// `check_redirect_uri` compares them.
+fn validate_client_metadata(metadata: &ClientMetadata) -> AuthResult<Vec<String>> {
+    if metadata.client_name.trim().is_empty() {
+        return Err(InvalidClientMetadata("client_name is required".to_owned()));
+    }
+
+    if metadata.grant_types.is_empty()
+        || metadata
+            .grant_types
+            .iter()
+            .any(|g| !SUPPORTED_GRANT_TYPES.contains(&g.as_str()))
+    {
+        return Err(InvalidClientMetadata(format!(
+            "grant_types must be a subset of {:?}",
+            SUPPORTED_GRANT_TYPES
+        )));
+    }
+
+    if let Some(logo_uri) = &metadata.logo_uri {
+        match Url::parse(logo_uri) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => (),
+            _ => return Err(InvalidClientMetadata("invalid logo_uri".to_owned())),
+        }
+    }
+
+    if metadata.redirect_uris.is_empty()
+        && metadata
+            .grant_types
+            .iter()
+            .any(|g| g == GRANT_TYPE_AUTHORIZATION_CODE)
+    {
+        return Err(InvalidRedirectUri);
+    }
+
+    metadata
+        .redirect_uris
+        .iter()
+        .map(|uri| {
+            let url = Url::parse(uri).map_err(|_| InvalidRedirectUri)?;
+            if url.query().is_some() || url.fragment().is_some() {
+                return Err(InvalidRedirectUri);
+            }
+            Ok(url.into_string())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(is_activated: bool) -> User {
+        User {
+            id: 1,
+            username: "shizuka".to_owned(),
+            password: String::new(),
+            salt: String::new(),
+            email: "shizuka@example.com".to_owned(),
+            is_activated,
+            role: "user".to_owned(),
+            is_locked: false,
+            failed_login_attempts: 0,
+            must_reset_password: false,
+        }
+    }
+
+    fn identity(email_verified: bool) -> FederatedIdentityClaims {
+        FederatedIdentityClaims {
+            provider: "corporate".to_owned(),
+            subject: "upstream-42".to_owned(),
+            email: Some("shizuka@example.com".to_owned()),
+            email_verified,
+            preferred_username: None,
+        }
+    }
+
+    #[test]
+    fn links_verified_email_to_activated_account() {
+        assert!(can_link_by_email(&user(true), &identity(true)));
+    }
+
+    #[test]
+    fn refuses_unverified_email() {
+        assert!(!can_link_by_email(&user(true), &identity(false)));
+    }
+
+    #[test]
+    fn refuses_account_that_never_activated() {
+        assert!(!can_link_by_email(&user(false), &identity(true)));
+    }
+
+    fn verified() -> DirectoryLogin {
+        DirectoryLogin::Verified(DirectoryUser {
+            username: "shizuka".to_owned(),
+            email: Some("shizuka@example.com".to_owned()),
+        })
+    }
+
+    #[test]
+    fn signs_in_linked_directory_user() {
+        let login = verified();
+        let directory_user = match &login {
+            DirectoryLogin::Verified(directory_user) => directory_user,
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            choose_login_check(&login, true, false),
+            LoginCheck::Directory(directory_user)
+        );
+        assert_eq!(
+            choose_login_check(&login, false, false),
+            LoginCheck::Directory(directory_user)
+        );
+    }
+
+    #[test]
+    fn counts_rejected_password_of_linked_user() {
+        assert_eq!(
+            choose_login_check(&DirectoryLogin::Rejected, true, false),
+            LoginCheck::DirectoryRejected
+        );
+    }
+
+    #[test]
+    fn falls_back_to_local_account_with_same_username() {
+        assert_eq!(
+            choose_login_check(&verified(), false, true),
+            LoginCheck::Local
+        );
+        assert_eq!(
+            choose_login_check(&DirectoryLogin::Rejected, false, true),
+            LoginCheck::Local
+        );
+    }
+
+    #[test]
+    fn uses_local_accounts_without_directory() {
+        assert_eq!(
+            choose_login_check(&DirectoryLogin::Skipped, false, true),
+            LoginCheck::Local
+        );
+    }
+}
@@ -6,12 +6,43 @@ pub type AuthResult<T> = Result<T, AuthError>;
 pub type Token = String;
 pub type RefreshToken = String;
 pub type AuthCode = String;
+pub type ClientSecret = String;
+
+pub const GRANT_TYPE_AUTHORIZATION_CODE: &str = "authorization_code";
+pub const GRANT_TYPE_DEVICE_CODE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+pub const SUPPORTED_GRANT_TYPES: [&str; 2] =
+    [GRANT_TYPE_AUTHORIZATION_CODE, GRANT_TYPE_DEVICE_CODE];
+
+/// Personal access tokens are opaque and start with this prefix, unlike encrypted tokens
+pub const PERSONAL_ACCESS_TOKEN_PREFIX: &str = "dpat_";
+
+pub const SCOPE_URL_READ: &str = "url:read";
+pub const SCOPE_URL_WRITE: &str = "url:write";
+pub const PERSONAL_ACCESS_TOKEN_SCOPES: [&str; 2] = [SCOPE_URL_READ, SCOPE_URL_WRITE];
 
 #[derive(Deserialize, Serialize, Clone)]
 pub struct TokenPayload {
     pub salt: String,
     pub username: String,
     pub expiry_timestamp: u128,
+    #[serde(default)]
+    pub issued_timestamp: Option<u128>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonator: Option<String>,
+}
+
+impl TokenPayload {
+    /// Tokens without a scope act with the full rights of their user
+    pub fn has_scope(&self, scope: &str) -> bool {
+        match &self.scope {
+            Some(scopes) => scopes.split(' ').any(|s| s == scope),
+            None => true,
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize, Clone)]
@@ -28,3 +59,51 @@ pub struct ActivationCodePayload {
     pub username: String,
     pub expiry_timestamp: u128,
 }
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct PasswordResetCodePayload {
+    pub salt: String,
+    pub username: String,
+    pub password_salt: String,
+    pub expiry_timestamp: u128,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ClientMetadata {
+    pub client_name: String,
+    pub redirect_uris: Vec<String>,
+    #[serde(default)]
+    pub logo_uri: Option<String>,
+    #[serde(default = "default_grant_types")]
+    pub grant_types: Vec<String>,
+}
+
+fn default_grant_types() -> Vec<String> {
+    vec![GRANT_TYPE_AUTHORIZATION_CODE.to_owned()]
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct FederationStatePayload {
+    pub salt: String,
+    pub provider: String,
+    pub client_id: String,
+    pub redirect_uri: String,
+    pub expiry_timestamp: u128,
+}
+
+/// The identity an upstream provider vouched for after a federated login.
+pub struct FederatedIdentityClaims {
+    pub provider: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    pub preferred_username: Option<String>,
+}
+
+/// A pending device authorization, see RFC 8628 section 3.2
+pub struct DeviceAuthorizationGrant {
+    pub device_code: String,
+    pub user_code: String,
+    pub expires_in: u64,
+    pub interval: u64,
+}
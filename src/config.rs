@@ -1,13 +1,51 @@
 use std::fs;
+use std::net::IpAddr;
 
 use serde::Deserialize;
 
+use crate::auth::directory::DIRECTORY_PROVIDER;
+use crate::core::url_shortener::utils::is_valid_url_key;
+
 #[derive(Deserialize, Clone)]
 pub struct Config {
     pub database: DatabaseConfig,
     pub auth: AuthConfig,
     pub url: UrlConfig,
     pub gmail: GmailConfig,
+    pub identity_providers: Option<Vec<IdentityProviderConfig>>,
+    pub ldap: Option<LdapConfig>,
+    pub saml: Option<SamlConfig>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct SamlConfig {
+    pub entity_id: String,
+    pub certificate_path: String,
+    pub private_key_path: String,
+    pub assertion_lifetime: u64,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct LdapConfig {
+    pub url: String,
+    pub bind_dn: Option<String>,
+    pub bind_password: Option<String>,
+    pub user_base_dn: String,
+    pub user_filter: String,
+    pub username_attribute: String,
+    pub email_attribute: String,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct IdentityProviderConfig {
+    pub id: String,
+    pub display_name: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub userinfo_endpoint: String,
+    pub scope: String,
 }
 
 #[derive(Deserialize, Clone)]
@@ -31,17 +69,60 @@ pub struct AuthConfig {
     pub base_url: String,
     pub email_origin: String,
     pub cypher_key: String,
+    pub signing_key_id: Option<String>,
+    pub key_rotation_grace_period: u64,
     pub token_lifetime: u64,
     pub auth_code_lifetime: u64,
     pub activation_code_lifetime: u64,
+    pub password_reset_code_lifetime: u64,
+    pub device_code_lifetime: u64,
+    pub device_poll_interval: u64,
+    pub personal_access_token_lifetime: u64,
+    pub max_login_attempts: i32,
 }
 
 #[derive(Deserialize, Clone)]
 pub struct UrlConfig {
     pub client_secret: String,
+    pub short_url_base: String,
+    pub key_alphabet: String,
+    pub key_length: usize,
+    pub reject_private_targets: bool,
+    pub domain_blocklist_path: Option<String>,
+    pub ip_hash_salt: String,
+    pub trusted_proxies: Option<Vec<IpAddr>>,
+    pub unlock_cookie_secret: String,
+    pub unlock_cookie_lifetime: u64,
+    pub max_unlock_attempts: i32,
+    pub unlock_attempt_window: u64,
+    pub restore_window: u64,
+    pub geoip_database_path: Option<String>,
+    pub qr_logo_path: Option<String>,
 }
 
 pub fn get_config() -> Config {
     let config = fs::read("var/config.toml").expect("Error in reading config file");
-    toml::from_slice(&config).expect("Error in parsing config")
+    let config: Config = toml::from_slice(&config).expect("Error in parsing config");
+    validate(&config);
+    config
+}
+
+/// Refuses settings at startup that would otherwise only fail once a request hits them
+fn validate(config: &Config) {
+    // An alphabet that is itself a valid key only holds characters keys may contain
+    if !is_valid_url_key(&config.url.key_alphabet) {
+        panic!("url.key_alphabet must be made of letters, digits, '_', '-' and '.'");
+    }
+    if config.url.key_length == 0 {
+        panic!("url.key_length must be at least 1");
+    }
+
+    if let Some(providers) = &config.identity_providers {
+        if providers.iter().any(|p| p.id == DIRECTORY_PROVIDER) {
+            panic!(
+                "Identity provider id \"{}\" is reserved for the LDAP directory",
+                DIRECTORY_PROVIDER
+            );
+        }
+    }
 }
@@ -0,0 +1,34 @@
+use actix_web::web::Data;
+use actix_web::{HttpRequest, HttpResponse, Result};
+
+use crate::app_data::AppData;
+use crate::core::admin::users::UserSummary;
+use crate::core::admin::utils::{authenticate_admin, record};
+
+pub async fn handle_activate(data: Data<AppData>, req: HttpRequest) -> Result<HttpResponse> {
+    set_activated(data, req, true)
+}
+
+pub async fn handle_deactivate(data: Data<AppData>, req: HttpRequest) -> Result<HttpResponse> {
+    set_activated(data, req, false)
+}
+
+fn set_activated(
+    data: Data<AppData>,
+    req: HttpRequest,
+    is_activated: bool,
+) -> Result<HttpResponse> {
+    let admin = authenticate_admin(&data, &req)?;
+    let username = String::from(req.match_info().get("username").unwrap());
+
+    let user = data.user_handler.set_activated(&username, is_activated)?;
+
+    let action = if is_activated {
+        "activate"
+    } else {
+        "deactivate"
+    };
+    record(&data, &admin, action, &user.username, &String::new())?;
+
+    Ok(HttpResponse::Ok().json(UserSummary::from(user)))
+}
@@ -0,0 +1,51 @@
+use actix_web::web::{Data, Query};
+use actix_web::{HttpRequest, HttpResponse, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::app_data::AppData;
+use crate::core::admin::utils::authenticate_admin;
+use crate::database::handler::audit_log::AuditLog;
+
+#[derive(Deserialize)]
+pub struct GetAuditLogRequest {
+    target: Option<String>,
+    page: Option<i64>,
+    per_page: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct GetAuditLogResponse {
+    audit_logs: Vec<AuditLog>,
+    page: i64,
+    per_page: i64,
+    total: i64,
+}
+
+pub async fn handle(
+    request: Query<GetAuditLogRequest>,
+    data: Data<AppData>,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    authenticate_admin(&data, &req)?;
+
+    let page = request.page.unwrap_or(0);
+    let per_page = request.per_page.unwrap_or(10);
+
+    let offset = per_page * page;
+    let limit = per_page;
+
+    let audit_logs =
+        data.audit_log_handler
+            .get_by_target(request.target.as_ref(), offset, limit)?;
+
+    let total = data
+        .audit_log_handler
+        .count_by_target(request.target.as_ref())?;
+
+    Ok(HttpResponse::Ok().json(GetAuditLogResponse {
+        audit_logs,
+        page,
+        per_page,
+        total,
+    }))
+}
@@ -0,0 +1,127 @@
+use actix_web::web::{Data, Json, Query};
+use actix_web::{HttpRequest, HttpResponse, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::app_data::AppData;
+use crate::auth::model::{ClientMetadata, ClientSecret};
+use crate::core::admin::utils::{authenticate_admin, record};
+use crate::database::handler::client_credential::ClientCredential;
+use crate::database::handler::DbError;
+
+#[derive(Deserialize)]
+pub struct GetClientRequest {
+    page: Option<i64>,
+    per_page: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct GetClientResponse {
+    clients: Vec<ClientCredential>,
+    page: i64,
+    per_page: i64,
+    total: i64,
+}
+
+#[derive(Serialize)]
+pub struct ClientWithSecretResponse {
+    client: ClientCredential,
+    client_secret: ClientSecret,
+}
+
+pub async fn handle_list(
+    request: Query<GetClientRequest>,
+    data: Data<AppData>,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    authenticate_admin(&data, &req)?;
+
+    let page = request.page.unwrap_or(0);
+    let per_page = request.per_page.unwrap_or(10);
+
+    let offset = per_page * page;
+    let limit = per_page;
+
+    let clients = data.client_credential_handler.get_all(offset, limit)?;
+    let total = data.client_credential_handler.count()?;
+
+    Ok(HttpResponse::Ok().json(GetClientResponse {
+        clients,
+        page,
+        per_page,
+        total,
+    }))
+}
+
+pub async fn handle_one(data: Data<AppData>, req: HttpRequest) -> Result<HttpResponse> {
+    authenticate_admin(&data, &req)?;
+    let client_id = String::from(req.match_info().get("client_id").unwrap());
+
+    let client = data
+        .client_credential_handler
+        .get_by_id(&client_id)
+        .map_err(DbError::from)?;
+
+    Ok(HttpResponse::Ok().json(client))
+}
+
+pub async fn handle_create(
+    request: Json<ClientMetadata>,
+    data: Data<AppData>,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    let admin = authenticate_admin(&data, &req)?;
+
+    let (client, client_secret) = data
+        .auth_handler
+        .register_client(&admin.username, &request)?;
+    record(&data, &admin, "create_client", &client.id, &client.name)?;
+
+    Ok(HttpResponse::Ok().json(ClientWithSecretResponse {
+        client,
+        client_secret,
+    }))
+}
+
+pub async fn handle_update(
+    request: Json<ClientMetadata>,
+    data: Data<AppData>,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    let admin = authenticate_admin(&data, &req)?;
+    let client_id = String::from(req.match_info().get("client_id").unwrap());
+
+    let client = data.auth_handler.update_client(&client_id, &request)?;
+    record(&data, &admin, "update_client", &client.id, &client.name)?;
+
+    Ok(HttpResponse::Ok().json(client))
+}
+
+pub async fn handle_rotate_secret(data: Data<AppData>, req: HttpRequest) -> Result<HttpResponse> {
+    let admin = authenticate_admin(&data, &req)?;
+    let client_id = String::from(req.match_info().get("client_id").unwrap());
+
+    let (client, client_secret) = data.auth_handler.rotate_client_secret(&client_id)?;
+    record(
+        &data,
+        &admin,
+        "rotate_client_secret",
+        &client.id,
+        &String::new(),
+    )?;
+
+    Ok(HttpResponse::Ok().json(ClientWithSecretResponse {
+        client,
+        client_secret,
+    }))
+}
+
+pub async fn handle_delete(data: Data<AppData>, req: HttpRequest) -> Result<HttpResponse> {
+    let admin = authenticate_admin(&data, &req)?;
+    let client_id = String::from(req.match_info().get("client_id").unwrap());
+
+    data.client_credential_handler
+        .delete_at_least_one(&client_id)?;
+    record(&data, &admin, "delete_client", &client_id, &String::new())?;
+
+    Ok(HttpResponse::Ok().finish())
+}
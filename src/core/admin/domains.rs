@@ -0,0 +1,85 @@
+use actix_web::web::{Data, Query};
+use actix_web::{HttpRequest, HttpResponse, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::app_data::AppData;
+use crate::core::admin::utils::{authenticate_admin, record};
+use crate::database::handler::domain::Domain;
+
+#[derive(Deserialize)]
+pub struct ListPendingRequest {
+    page: Option<i64>,
+    per_page: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct ListPendingResponse {
+    domains: Vec<Domain>,
+    page: i64,
+    per_page: i64,
+    total: i64,
+}
+
+fn get_domain_id(req: &HttpRequest) -> Result<i32> {
+    req.match_info()
+        .get("domain_id")
+        .unwrap()
+        .parse::<i32>()
+        .map_err(actix_web::error::ErrorBadRequest)
+}
+
+/// Registrations waiting for an admin to check the registrant controls the hostname
+pub async fn handle_list_pending(
+    request: Query<ListPendingRequest>,
+    data: Data<AppData>,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    authenticate_admin(&data, &req)?;
+
+    let page = request.page.unwrap_or(0);
+    let per_page = request.per_page.unwrap_or(10);
+
+    let domains = data.domain_handler.get_pending(per_page * page, per_page)?;
+    let total = data.domain_handler.count_pending()?;
+
+    Ok(HttpResponse::Ok().json(ListPendingResponse {
+        domains,
+        page,
+        per_page,
+        total,
+    }))
+}
+
+pub async fn handle_approve(data: Data<AppData>, req: HttpRequest) -> Result<HttpResponse> {
+    let admin = authenticate_admin(&data, &req)?;
+    let domain_id = get_domain_id(&req)?;
+
+    let domain = data.domain_handler.approve(domain_id, &admin.username)?;
+    data.domain_cache.invalidate();
+    record(
+        &data,
+        &admin,
+        "approve_domain",
+        &domain.hostname,
+        &domain.username,
+    )?;
+
+    Ok(HttpResponse::Ok().json(domain))
+}
+
+/// Drops a registration, which frees the hostname for whoever actually controls it
+pub async fn handle_reject(data: Data<AppData>, req: HttpRequest) -> Result<HttpResponse> {
+    let admin = authenticate_admin(&data, &req)?;
+    let domain_id = get_domain_id(&req)?;
+
+    let domain = data.domain_handler.delete_pending(domain_id)?;
+    record(
+        &data,
+        &admin,
+        "reject_domain",
+        &domain.hostname,
+        &domain.username,
+    )?;
+
+    Ok(HttpResponse::Ok().finish())
+}
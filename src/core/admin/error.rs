@@ -0,0 +1,30 @@
+use std::fmt;
+
+use crate::database::handler::user::ROLES;
+
+#[derive(Debug)]
+pub enum AdminError {
+    Forbidden,
+    InvalidRole,
+    CannotImpersonateAdmin,
+}
+
+impl fmt::Display for AdminError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdminError::Forbidden => write!(f, "Admin role required"),
+            AdminError::InvalidRole => write!(f, "Role can only be one of {:?}", ROLES),
+            AdminError::CannotImpersonateAdmin => write!(f, "Admins can't be impersonated"),
+        }
+    }
+}
+
+impl From<AdminError> for actix_web::Error {
+    fn from(e: AdminError) -> Self {
+        match e {
+            AdminError::Forbidden => actix_web::error::ErrorForbidden(e),
+            AdminError::InvalidRole => actix_web::error::ErrorBadRequest(e),
+            AdminError::CannotImpersonateAdmin => actix_web::error::ErrorBadRequest(e),
+        }
+    }
+}
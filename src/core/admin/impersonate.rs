@@ -0,0 +1,45 @@
+use actix_web::web::{Data, Json};
+use actix_web::{HttpRequest, HttpResponse, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::app_data::AppData;
+use crate::auth::model::Token;
+use crate::core::admin::error::AdminError;
+use crate::core::admin::utils::{authenticate_admin, record};
+
+#[derive(Deserialize)]
+pub struct ImpersonateRequest {
+    reason: String,
+}
+
+#[derive(Serialize)]
+pub struct ImpersonateResponse {
+    access_token: Token,
+}
+
+pub async fn handle(
+    request: Json<ImpersonateRequest>,
+    data: Data<AppData>,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    let admin = authenticate_admin(&data, &req)?;
+    let username = String::from(req.match_info().get("username").unwrap());
+
+    let user = data.user_handler.get_by_username(&username)?;
+    if user.is_admin() {
+        return Err(AdminError::CannotImpersonateAdmin.into());
+    }
+
+    let access_token = data
+        .auth_handler
+        .impersonate(&admin.username, &user.username)?;
+    record(
+        &data,
+        &admin,
+        "impersonate",
+        &user.username,
+        &request.reason,
+    )?;
+
+    Ok(HttpResponse::Ok().json(ImpersonateResponse { access_token }))
+}
@@ -0,0 +1,42 @@
+use actix_web::web::{Data, Json};
+use actix_web::{HttpRequest, HttpResponse, Result};
+use serde::Deserialize;
+
+use crate::app_data::AppData;
+use crate::core::admin::utils::{authenticate_admin, record};
+
+#[derive(Deserialize)]
+pub struct RotateKeyRequest {
+    grace_period: Option<u64>,
+}
+
+pub async fn handle_list(data: Data<AppData>, req: HttpRequest) -> Result<HttpResponse> {
+    authenticate_admin(&data, &req)?;
+
+    let keys = data.signing_key_handler.get_all()?;
+
+    Ok(HttpResponse::Ok().json(keys))
+}
+
+pub async fn handle_rotate(
+    request: Json<RotateKeyRequest>,
+    data: Data<AppData>,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    let admin = authenticate_admin(&data, &req)?;
+
+    let grace_period = request
+        .grace_period
+        .unwrap_or(data.config.auth.key_rotation_grace_period);
+
+    let key = data.auth_handler.rotate_signing_key(grace_period)?;
+    record(
+        &data,
+        &admin,
+        "rotate_signing_key",
+        &key.id,
+        &format!("grace_period={}", grace_period),
+    )?;
+
+    Ok(HttpResponse::Ok().json(key))
+}
@@ -0,0 +1,85 @@
+use actix_web::dev::HttpServiceFactory;
+use actix_web::web;
+
+mod utils;
+
+mod activation;
+mod audit_log;
+mod clients;
+mod domains;
+mod impersonate;
+mod keys;
+mod reset_password;
+mod role;
+mod saml;
+mod unlock;
+mod urls;
+mod users;
+
+mod error;
+
+pub fn service(prefix: &str) -> impl HttpServiceFactory {
+    web::scope(prefix)
+        .route("/users", web::get().to(users::handle_search))
+        .route("/users/{username}", web::get().to(users::handle_one))
+        .route(
+            "/users/{username}/activate",
+            web::post().to(activation::handle_activate),
+        )
+        .route(
+            "/users/{username}/deactivate",
+            web::post().to(activation::handle_deactivate),
+        )
+        .route("/users/{username}/unlock", web::post().to(unlock::handle))
+        .route("/users/{username}/role", web::post().to(role::handle))
+        .route(
+            "/users/{username}/reset-password",
+            web::post().to(reset_password::handle),
+        )
+        .route(
+            "/users/{username}/impersonate",
+            web::post().to(impersonate::handle),
+        )
+        .route("/clients", web::get().to(clients::handle_list))
+        .route("/clients", web::post().to(clients::handle_create))
+        .route("/clients/{client_id}", web::get().to(clients::handle_one))
+        .route(
+            "/clients/{client_id}",
+            web::put().to(clients::handle_update),
+        )
+        .route(
+            "/clients/{client_id}",
+            web::delete().to(clients::handle_delete),
+        )
+        .route(
+            "/clients/{client_id}/rotate-secret",
+            web::post().to(clients::handle_rotate_secret),
+        )
+        .route("/keys", web::get().to(keys::handle_list))
+        .route("/keys/rotate", web::post().to(keys::handle_rotate))
+        .route("/saml-service-providers", web::get().to(saml::handle_list))
+        .route(
+            "/saml-service-providers",
+            web::post().to(saml::handle_create),
+        )
+        .route(
+            "/saml-service-providers",
+            web::delete().to(saml::handle_delete),
+        )
+        .route("/urls/disabled", web::get().to(urls::handle_list_disabled))
+        .route("/urls/{key}/disable", web::post().to(urls::handle_disable))
+        .route("/urls/{key}/enable", web::post().to(urls::handle_enable))
+        .route(
+            "/domains/pending",
+            web::get().to(domains::handle_list_pending),
+        )
+        .route(
+            "/domains/{domain_id}/approve",
+            web::post().to(domains::handle_approve),
+        )
+        .route(
+            "/domains/{domain_id}/reject",
+            web::post().to(domains::handle_reject),
+        )
+        .route("/audit-logs", web::get().to(audit_log::handle))
+}
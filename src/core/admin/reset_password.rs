@@ -0,0 +1,32 @@
+use actix_web::web::Data;
+use actix_web::{HttpRequest, HttpResponse, Result};
+
+use crate::app_data::AppData;
+use crate::core::admin::utils::{authenticate_admin, record};
+use crate::core::sso::utils::{get_password_reset_url, send_password_reset_mail};
+
+pub async fn handle(data: Data<AppData>, req: HttpRequest) -> Result<HttpResponse> {
+    let admin = authenticate_admin(&data, &req)?;
+    let username = String::from(req.match_info().get("username").unwrap());
+
+    let (email, reset_code) = data.auth_handler.force_password_reset(&username)?;
+    let reset_url = get_password_reset_url(&data.config.auth.base_url, &reset_code);
+
+    let mailer = data.as_ref().mailer()?;
+    actix_rt::spawn(send_password_reset_mail(
+        mailer,
+        data.config.auth.email_origin.to_owned(),
+        email,
+        reset_url,
+    ));
+
+    record(
+        &data,
+        &admin,
+        "force_password_reset",
+        &username,
+        &String::new(),
+    )?;
+
+    Ok(HttpResponse::Ok().finish())
+}
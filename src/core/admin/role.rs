@@ -0,0 +1,32 @@
+use actix_web::web::{Data, Json};
+use actix_web::{HttpRequest, HttpResponse, Result};
+use serde::Deserialize;
+
+use crate::app_data::AppData;
+use crate::core::admin::error::AdminError;
+use crate::core::admin::users::UserSummary;
+use crate::core::admin::utils::{authenticate_admin, record};
+use crate::database::handler::user::ROLES;
+
+#[derive(Deserialize)]
+pub struct SetRoleRequest {
+    role: String,
+}
+
+pub async fn handle(
+    request: Json<SetRoleRequest>,
+    data: Data<AppData>,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    let admin = authenticate_admin(&data, &req)?;
+    let username = String::from(req.match_info().get("username").unwrap());
+
+    if !ROLES.contains(&request.role.as_str()) {
+        return Err(AdminError::InvalidRole.into());
+    }
+
+    let user = data.user_handler.set_role(&username, &request.role)?;
+    record(&data, &admin, "set_role", &user.username, &request.role)?;
+
+    Ok(HttpResponse::Ok().json(UserSummary::from(user)))
+}
@@ -0,0 +1,59 @@
+use actix_web::web::{Data, Json};
+use actix_web::{HttpRequest, HttpResponse, Result};
+use serde::Deserialize;
+
+use crate::app_data::AppData;
+use crate::core::admin::utils::{authenticate_admin, record};
+use crate::database::handler::saml_service_provider::NewSamlServiceProvider;
+
+#[derive(Deserialize)]
+pub struct DeleteServiceProviderRequest {
+    entity_id: String,
+}
+
+pub async fn handle_list(data: Data<AppData>, req: HttpRequest) -> Result<HttpResponse> {
+    authenticate_admin(&data, &req)?;
+
+    let service_providers = data.saml_service_provider_handler.get_all()?;
+
+    Ok(HttpResponse::Ok().json(service_providers))
+}
+
+pub async fn handle_create(
+    request: Json<NewSamlServiceProvider>,
+    data: Data<AppData>,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    let admin = authenticate_admin(&data, &req)?;
+
+    data.saml_service_provider_handler.insert(&request)?;
+    record(
+        &data,
+        &admin,
+        "create_saml_service_provider",
+        &request.entity_id,
+        &format!("acs_url={}", request.acs_url),
+    )?;
+
+    Ok(HttpResponse::Created().finish())
+}
+
+pub async fn handle_delete(
+    request: Json<DeleteServiceProviderRequest>,
+    data: Data<AppData>,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    let admin = authenticate_admin(&data, &req)?;
+
+    data.saml_service_provider_handler
+        .delete_at_least_one(&request.entity_id)?;
+    record(
+        &data,
+        &admin,
+        "delete_saml_service_provider",
+        &request.entity_id,
+        &String::new(),
+    )?;
+
+    Ok(HttpResponse::Ok().finish())
+}
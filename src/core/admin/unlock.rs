@@ -0,0 +1,16 @@
+use actix_web::web::Data;
+use actix_web::{HttpRequest, HttpResponse, Result};
+
+use crate::app_data::AppData;
+use crate::core::admin::users::UserSummary;
+use crate::core::admin::utils::{authenticate_admin, record};
+
+pub async fn handle(data: Data<AppData>, req: HttpRequest) -> Result<HttpResponse> {
+    let admin = authenticate_admin(&data, &req)?;
+    let username = String::from(req.match_info().get("username").unwrap());
+
+    let user = data.user_handler.unlock(&username)?;
+    record(&data, &admin, "unlock", &user.username, &String::new())?;
+
+    Ok(HttpResponse::Ok().json(UserSummary::from(user)))
+}
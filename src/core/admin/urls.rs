@@ -0,0 +1,85 @@
+use actix_web::web::{Data, Json, Query};
+use actix_web::{HttpRequest, HttpResponse, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::app_data::AppData;
+use crate::core::admin::utils::{authenticate_admin, record};
+use crate::database::handler::url::Url;
+
+#[derive(Deserialize)]
+pub struct ListDisabledRequest {
+    page: Option<i64>,
+    per_page: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct ListDisabledResponse {
+    urls: Vec<Url>,
+    page: i64,
+    per_page: i64,
+    total: i64,
+}
+
+#[derive(Deserialize)]
+pub struct UrlDomainRequest {
+    domain_id: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct DisableUrlRequest {
+    reason: String,
+}
+
+pub async fn handle_list_disabled(
+    request: Query<ListDisabledRequest>,
+    data: Data<AppData>,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    authenticate_admin(&data, &req)?;
+
+    let page = request.page.unwrap_or(0);
+    let per_page = request.per_page.unwrap_or(10);
+
+    let urls = data.url_handler.get_disabled(per_page * page, per_page)?;
+    let total = data.url_handler.count_disabled()?;
+
+    Ok(HttpResponse::Ok().json(ListDisabledResponse {
+        urls,
+        page,
+        per_page,
+        total,
+    }))
+}
+
+pub async fn handle_disable(
+    domain: Query<UrlDomainRequest>,
+    request: Json<DisableUrlRequest>,
+    data: Data<AppData>,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    let admin = authenticate_admin(&data, &req)?;
+    let key = String::from(req.match_info().get("key").unwrap());
+
+    let url = data
+        .url_handler
+        .set_disabled(domain.domain_id, &key, true, Some(&request.reason))?;
+    record(&data, &admin, "disable_url", &url.key, &request.reason)?;
+
+    Ok(HttpResponse::Ok().json(url))
+}
+
+pub async fn handle_enable(
+    domain: Query<UrlDomainRequest>,
+    data: Data<AppData>,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    let admin = authenticate_admin(&data, &req)?;
+    let key = String::from(req.match_info().get("key").unwrap());
+
+    let url = data
+        .url_handler
+        .set_disabled(domain.domain_id, &key, false, None)?;
+    record(&data, &admin, "enable_url", &url.key, &String::new())?;
+
+    Ok(HttpResponse::Ok().json(url))
+}
@@ -0,0 +1,89 @@
+use actix_web::web::{Data, Query};
+use actix_web::{HttpRequest, HttpResponse, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::app_data::AppData;
+use crate::core::admin::utils::authenticate_admin;
+use crate::database::handler::user::User;
+
+#[derive(Serialize)]
+pub struct UserSummary {
+    id: i32,
+    username: String,
+    email: String,
+    role: String,
+    is_activated: bool,
+    is_locked: bool,
+    failed_login_attempts: i32,
+    must_reset_password: bool,
+}
+
+impl From<User> for UserSummary {
+    fn from(user: User) -> Self {
+        UserSummary {
+            id: user.id,
+            username: user.username,
+            email: user.email,
+            role: user.role,
+            is_activated: user.is_activated,
+            is_locked: user.is_locked,
+            failed_login_attempts: user.failed_login_attempts,
+            must_reset_password: user.must_reset_password,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SearchUserRequest {
+    q: Option<String>,
+    page: Option<i64>,
+    per_page: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct SearchUserResponse {
+    users: Vec<UserSummary>,
+    page: i64,
+    per_page: i64,
+    total: i64,
+}
+
+pub async fn handle_search(
+    request: Query<SearchUserRequest>,
+    data: Data<AppData>,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    authenticate_admin(&data, &req)?;
+
+    let query = request.q.to_owned().unwrap_or_default();
+    let page = request.page.unwrap_or(0);
+    let per_page = request.per_page.unwrap_or(10);
+
+    let offset = per_page * page;
+    let limit = per_page;
+
+    let users = data
+        .user_handler
+        .search(&query, offset, limit)?
+        .into_iter()
+        .map(UserSummary::from)
+        .collect();
+
+    let total = data.user_handler.count_search(&query)?;
+
+    Ok(HttpResponse::Ok().json(SearchUserResponse {
+        users,
+        page,
+        per_page,
+        total,
+    }))
+}
+
+pub async fn handle_one(data: Data<AppData>, req: HttpRequest) -> Result<HttpResponse> {
+    authenticate_admin(&data, &req)?;
+    let username = String::from(req.match_info().get("username").unwrap());
+
+    let user = data.user_handler.get_by_username(&username)?;
+
+    Ok(HttpResponse::Ok().json(UserSummary::from(user)))
+}
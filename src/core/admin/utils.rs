@@ -0,0 +1,49 @@
+use actix_web::web::Data;
+use actix_web::HttpRequest;
+
+use crate::app_data::AppData;
+use crate::auth::AuthError;
+use crate::core::admin::error::AdminError;
+use crate::core::url_shortener::utils::authenticate;
+use crate::database::handler::audit_log::NewAuditLog;
+use crate::database::handler::user::User;
+use crate::database::handler::DbError;
+
+pub fn authenticate_admin(data: &Data<AppData>, req: &HttpRequest) -> actix_web::Result<User> {
+    let token = authenticate(data, req)?;
+
+    // Impersonation and scoped tokens never carry the admin's own privileges
+    if token.impersonator.is_some() || token.scope.is_some() {
+        return Err(AdminError::Forbidden.into());
+    }
+
+    let user = data
+        .user_handler
+        .get_by_username(&token.username)
+        .map_err(|e| match e {
+            DbError::NotFound => actix_web::error::ErrorUnauthorized(AuthError::InvalidToken),
+            e => e.into(),
+        })?;
+
+    if !user.is_admin() {
+        return Err(AdminError::Forbidden.into());
+    }
+
+    Ok(user)
+}
+
+pub fn record(
+    data: &Data<AppData>,
+    actor: &User,
+    action: &str,
+    target: &String,
+    detail: &String,
+) -> actix_web::Result<()> {
+    data.audit_log_handler.insert(&NewAuditLog {
+        actor: &actor.username,
+        action,
+        target,
+        detail,
+    })?;
+    Ok(())
+}
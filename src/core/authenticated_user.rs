@@ -0,0 +1,148 @@
+use std::fmt;
+
+use actix_web::dev::Payload;
+use actix_web::http::{header, StatusCode};
+use actix_web::web::Data;
+use actix_web::{FromRequest, HttpRequest, HttpResponse, ResponseError};
+use futures::future::{ready, Ready};
+
+use crate::app_data::AppData;
+use crate::auth::model::TokenPayload;
+use crate::auth::AuthError;
+
+const REALM: &str = "doraemon";
+
+/// The caller of a request, extracted from a `Bearer` token in the `Authorization` header.
+///
+/// Handlers in any `core` module can take it as an argument instead of authenticating by
+/// hand; failures are answered with RFC 6750 `WWW-Authenticate` challenges.
+pub struct AuthenticatedUser {
+    pub token: TokenPayload,
+}
+
+impl AuthenticatedUser {
+    pub fn username(&self) -> &String {
+        &self.token.username
+    }
+
+    pub fn require_scope(&self, scope: &'static str) -> Result<(), BearerError> {
+        if self.token.has_scope(scope) {
+            Ok(())
+        } else {
+            Err(BearerError::InsufficientScope(scope))
+        }
+    }
+
+    pub fn authenticate(data: &AppData, req: &HttpRequest) -> actix_web::Result<Self> {
+        let token = get_bearer_token(req)?;
+
+        match data.auth_handler.inspect(&token) {
+            Ok(token) => Ok(AuthenticatedUser { token }),
+            Err(e @ AuthError::InvalidToken)
+            | Err(e @ AuthError::ExpiredToken)
+            | Err(e @ AuthError::NotFound)
+            | Err(e @ AuthError::Locked)
+            | Err(e @ AuthError::NotActivated)
+            | Err(e @ AuthError::JSONError(_)) => Err(BearerError::InvalidToken(e).into()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(match req.app_data::<Data<AppData>>() {
+            Some(data) => AuthenticatedUser::authenticate(data, req),
+            None => Err(actix_web::error::ErrorInternalServerError(
+                "App data is not configured",
+            )),
+        })
+    }
+}
+
+/// Reads the token from `Authorization: Bearer <token>`, see RFC 6750 section 2.1. A bare
+/// token without a scheme is still accepted for clients written before the scheme was parsed.
+fn get_bearer_token(req: &HttpRequest) -> Result<String, BearerError> {
+    let value = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .ok_or(BearerError::MissingToken)?
+        .to_str()
+        .map_err(|_| BearerError::InvalidRequest)?
+        .trim();
+
+    let mut parts = value.splitn(2, ' ');
+    let token = match (parts.next(), parts.next()) {
+        (Some(scheme), Some(token)) if scheme.eq_ignore_ascii_case("Bearer") => token.trim(),
+        (Some(token), None) if !token.is_empty() => token,
+        _ => return Err(BearerError::InvalidRequest),
+    };
+
+    if token.is_empty() || token.contains(' ') {
+        return Err(BearerError::InvalidRequest);
+    }
+
+    Ok(token.to_owned())
+}
+
+#[derive(Debug)]
+pub enum BearerError {
+    MissingToken,
+    InvalidRequest,
+    InvalidToken(AuthError),
+    InsufficientScope(&'static str),
+}
+
+impl BearerError {
+    fn challenge(&self) -> String {
+        match self {
+            BearerError::MissingToken => format!("Bearer realm=\"{}\"", REALM),
+            BearerError::InvalidRequest => format!(
+                "Bearer realm=\"{}\", error=\"invalid_request\", error_description=\"{}\"",
+                REALM, self
+            ),
+            BearerError::InvalidToken(_) => format!(
+                "Bearer realm=\"{}\", error=\"invalid_token\", error_description=\"{}\"",
+                REALM, self
+            ),
+            BearerError::InsufficientScope(scope) => format!(
+                "Bearer realm=\"{}\", error=\"insufficient_scope\", scope=\"{}\"",
+                REALM, scope
+            ),
+        }
+    }
+}
+
+impl fmt::Display for BearerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BearerError::MissingToken => write!(f, "Missing bearer token"),
+            BearerError::InvalidRequest => write!(f, "Malformed Authorization header"),
+            BearerError::InvalidToken(e) => write!(f, "{}", e),
+            BearerError::InsufficientScope(scope) => {
+                write!(f, "Token doesn't have the {} scope", scope)
+            }
+        }
+    }
+}
+
+impl ResponseError for BearerError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            BearerError::MissingToken => StatusCode::UNAUTHORIZED,
+            BearerError::InvalidRequest => StatusCode::BAD_REQUEST,
+            BearerError::InvalidToken(_) => StatusCode::UNAUTHORIZED,
+            BearerError::InsufficientScope(_) => StatusCode::FORBIDDEN,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code())
+            .header(header::WWW_AUTHENTICATE, self.challenge())
+            .body(self.to_string())
+    }
+}
@@ -1,3 +1,5 @@
+pub mod admin;
+pub mod authenticated_user;
 pub mod greeter;
 pub mod resizer;
 pub mod sso;
@@ -1,10 +1,10 @@
 use actix_web::web::Data;
-use actix_web::{error, http, web, HttpResponse, Result};
+use actix_web::{web, HttpResponse, Result};
 use serde::{Deserialize, Serialize};
-use url::Url;
 
 use crate::app_data::AppData;
 use crate::auth::model::AuthCode;
+use crate::core::sso::utils::redirect_with_auth_code;
 
 #[derive(Deserialize, Clone)]
 pub struct UserPayload {
@@ -23,22 +23,19 @@ pub async fn handle_login(
     data: Data<AppData>,
     req: web::Form<UserPayload>,
 ) -> Result<HttpResponse> {
+    let directory_login = data
+        .auth_handler
+        .check_directory(&req.username, &req.password)
+        .await;
     let auth_code = data.auth_handler.get_authorization_code(
         &req.username,
         &req.password,
+        &directory_login,
         &req.client_id,
         &req.redirect_uri,
     )?;
 
-    let redirect_uri = match Url::parse_with_params(&req.redirect_uri, &[("auth_code", auth_code)])
-    {
-        Ok(url) => Ok(url.into_string()),
-        Err(e) => Err(error::ErrorBadRequest(e)),
-    }?;
-
-    Ok(HttpResponse::Found()
-        .header(http::header::LOCATION, redirect_uri)
-        .finish())
+    redirect_with_auth_code(&req.redirect_uri, auth_code)
 }
 
 #[derive(Deserialize, Serialize)]
@@ -51,8 +48,13 @@ pub async fn handle_form(
     query: web::Query<LoginFormParam>,
     data: Data<AppData>,
 ) -> Result<HttpResponse> {
-    let template = data
-        .templater
-        .login_page(&query.client_id, &query.redirect_uri)?;
+    let identity_providers = match &data.config.identity_providers {
+        Some(providers) => providers.as_slice(),
+        None => &[],
+    };
+
+    let template =
+        data.templater
+            .login_page(&query.client_id, &query.redirect_uri, identity_providers)?;
     Ok(HttpResponse::Ok().body(template))
 }
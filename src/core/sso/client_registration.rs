@@ -0,0 +1,77 @@
+use actix_web::web::{Data, Json};
+use actix_web::{HttpRequest, HttpResponse, Result};
+use serde::Serialize;
+
+use crate::app_data::AppData;
+use crate::auth::model::{ClientMetadata, ClientSecret};
+use crate::auth::AuthError;
+use crate::core::sso::error::SsoError;
+use crate::core::url_shortener::utils::authenticate;
+
+/// Client information response, see RFC 7591 section 3.2.1
+#[derive(Serialize)]
+pub struct ClientInformationResponse {
+    client_id: String,
+    client_secret: ClientSecret,
+    client_id_issued_at: i64,
+    client_secret_expires_at: i64,
+    client_name: String,
+    redirect_uris: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    logo_uri: Option<String>,
+    grant_types: Vec<String>,
+    token_endpoint_auth_method: &'static str,
+}
+
+/// Client registration error response, see RFC 7591 section 3.2.2
+#[derive(Serialize)]
+pub struct ClientRegistrationError {
+    error: &'static str,
+    error_description: String,
+}
+
+pub async fn handle(
+    request: Json<ClientMetadata>,
+    data: Data<AppData>,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    let token = authenticate(&data, &req)?;
+
+    let user = data.user_handler.get_by_username(&token.username)?;
+    if token.impersonator.is_some() || token.scope.is_some() || !user.can_register_client() {
+        return Err(SsoError::ClientRegistrationNotAllowed.into());
+    }
+
+    let (client, client_secret) = match data.auth_handler.register_client(&user.username, &request)
+    {
+        Err(AuthError::InvalidRedirectUri) => {
+            return Ok(registration_error(
+                "invalid_redirect_uri",
+                AuthError::InvalidRedirectUri,
+            ))
+        }
+        Err(e @ AuthError::InvalidClientMetadata(_)) => {
+            return Ok(registration_error("invalid_client_metadata", e))
+        }
+        r => r,
+    }?;
+
+    Ok(HttpResponse::Created().json(ClientInformationResponse {
+        client_id: client.id,
+        client_secret,
+        client_id_issued_at: client.created_at.timestamp(),
+        client_secret_expires_at: 0,
+        client_name: client.name,
+        redirect_uris: client.redirect_uris,
+        logo_uri: client.logo_uri,
+        grant_types: client.grant_types,
+        token_endpoint_auth_method: "client_secret_post",
+    }))
+}
+
+fn registration_error(error: &'static str, e: AuthError) -> HttpResponse {
+    HttpResponse::BadRequest().json(ClientRegistrationError {
+        error,
+        error_description: e.to_string(),
+    })
+}
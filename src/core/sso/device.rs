@@ -0,0 +1,185 @@
+use actix_web::dev::RequestHead;
+use actix_web::web::Data;
+use actix_web::{http, web, HttpResponse, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::app_data::AppData;
+use crate::auth::model::{RefreshToken, Token, GRANT_TYPE_DEVICE_CODE};
+use crate::auth::AuthError;
+
+/// Device authorization response, see RFC 8628 section 3.2
+#[derive(Serialize)]
+pub struct DeviceAuthorizationResponse {
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    verification_uri_complete: String,
+    expires_in: u64,
+    interval: u64,
+}
+
+#[derive(Serialize)]
+pub struct TokenResponse {
+    access_token: Token,
+    refresh_token: RefreshToken,
+    token_type: &'static str,
+}
+
+/// Token error response, see RFC 6749 section 5.2 and RFC 8628 section 3.5
+#[derive(Serialize)]
+pub struct TokenError {
+    error: &'static str,
+    error_description: String,
+}
+
+#[derive(Deserialize)]
+pub struct DeviceAuthorizationRequest {
+    client_id: String,
+}
+
+#[derive(Deserialize)]
+pub struct DeviceTokenRequest {
+    grant_type: String,
+    device_code: String,
+    client_id: String,
+}
+
+#[derive(Deserialize)]
+pub struct VerificationParam {
+    user_code: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct VerificationPayload {
+    user_code: String,
+    username: String,
+    password: String,
+    action: String,
+}
+
+/// The device grant is polled with form-encoded bodies, unlike the JSON authorization code
+/// exchange served on the same path.
+pub fn is_form_request(head: &RequestHead) -> bool {
+    head.headers
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .map(|content_type| content_type.starts_with("application/x-www-form-urlencoded"))
+        .unwrap_or(false)
+}
+
+fn get_verification_uri(base_url: &String) -> String {
+    base_url.to_owned() + "/device"
+}
+
+pub async fn handle_code(
+    form: web::Form<DeviceAuthorizationRequest>,
+    data: Data<AppData>,
+) -> Result<HttpResponse> {
+    let grant = match data
+        .auth_handler
+        .request_device_authorization(&form.client_id)
+    {
+        Ok(grant) => grant,
+        Err(e @ AuthError::InvalidClientID) => return Ok(token_error("invalid_client", e)),
+        Err(e @ AuthError::UnauthorizedGrantType) => {
+            return Ok(token_error("unauthorized_client", e))
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let verification_uri = get_verification_uri(&data.config.auth.base_url);
+    Ok(HttpResponse::Ok()
+        .header(http::header::CACHE_CONTROL, "no-store")
+        .json(DeviceAuthorizationResponse {
+            device_code: grant.device_code,
+            verification_uri_complete: format!(
+                "{}?user_code={}",
+                verification_uri, grant.user_code
+            ),
+            user_code: grant.user_code,
+            verification_uri,
+            expires_in: grant.expires_in,
+            interval: grant.interval,
+        }))
+}
+
+pub async fn handle_token(
+    form: web::Form<DeviceTokenRequest>,
+    data: Data<AppData>,
+) -> Result<HttpResponse> {
+    if form.grant_type != GRANT_TYPE_DEVICE_CODE {
+        return Ok(HttpResponse::BadRequest()
+            .json(serde_json::json!({ "error": "unsupported_grant_type" })));
+    }
+
+    let (access_token, refresh_token) = match data
+        .auth_handler
+        .exchange_device_code(&form.client_id, &form.device_code)
+    {
+        Ok(tokens) => tokens,
+        Err(e @ AuthError::AuthorizationPending) => {
+            return Ok(token_error("authorization_pending", e))
+        }
+        Err(e @ AuthError::SlowDown) => return Ok(token_error("slow_down", e)),
+        Err(e @ AuthError::AccessDenied) => return Ok(token_error("access_denied", e)),
+        Err(e @ AuthError::ExpiredToken) => return Ok(token_error("expired_token", e)),
+        Err(e @ AuthError::InvalidClientID) => return Ok(token_error("invalid_client", e)),
+        Err(e @ AuthError::InvalidToken) => return Ok(token_error("invalid_grant", e)),
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(HttpResponse::Ok()
+        .header(http::header::CACHE_CONTROL, "no-store")
+        .json(TokenResponse {
+            access_token,
+            refresh_token,
+            token_type: "Bearer",
+        }))
+}
+
+pub async fn handle_form(
+    query: web::Query<VerificationParam>,
+    data: Data<AppData>,
+) -> Result<HttpResponse> {
+    let user_code = query.user_code.to_owned().unwrap_or_default();
+    let template = data
+        .templater
+        .device_verification_page(&user_code, &String::new())?;
+    Ok(HttpResponse::Ok().body(template))
+}
+
+pub async fn handle_verify(
+    form: web::Form<VerificationPayload>,
+    data: Data<AppData>,
+) -> Result<HttpResponse> {
+    let is_approved = form.action == "approve";
+    let directory_login = data
+        .auth_handler
+        .check_directory(&form.username, &form.password)
+        .await;
+    let message = match data.auth_handler.decide_device_authorization(
+        &form.user_code,
+        &form.username,
+        &form.password,
+        &directory_login,
+        is_approved,
+    ) {
+        Ok(()) if is_approved => "Device approved, you can return to your device".to_owned(),
+        Ok(()) => "Device access denied".to_owned(),
+        Err(e) => e.to_string(),
+    };
+
+    let template = data
+        .templater
+        .device_verification_page(&form.user_code, &message)?;
+    Ok(HttpResponse::Ok().body(template))
+}
+
+fn token_error(error: &'static str, e: AuthError) -> HttpResponse {
+    HttpResponse::BadRequest()
+        .header(http::header::CACHE_CONTROL, "no-store")
+        .json(TokenError {
+            error,
+            error_description: e.to_string(),
+        })
+}
@@ -3,12 +3,32 @@ use std::fmt;
 #[derive(Debug)]
 pub enum SsoError {
     CookieNotFound,
+    ClientRegistrationNotAllowed,
+    UnknownIdentityProvider,
+    InvalidFederationState,
+    UpstreamError(String),
+    SamlNotConfigured,
+    InvalidSamlRequest(String),
+    UnknownServiceProvider,
+    SamlError(String),
 }
 
 impl fmt::Display for SsoError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             SsoError::CookieNotFound => write!(f, "Bad boi!"),
+            SsoError::ClientRegistrationNotAllowed => {
+                write!(f, "Only developers can register clients")
+            }
+            SsoError::UnknownIdentityProvider => write!(f, "Unknown identity provider"),
+            SsoError::InvalidFederationState => write!(f, "Invalid federation state"),
+            SsoError::UpstreamError(e) => write!(f, "Identity provider error: {}", e),
+            SsoError::SamlNotConfigured => write!(f, "SAML is not enabled"),
+            SsoError::InvalidSamlRequest(e) => write!(f, "Invalid SAML request: {}", e),
+            SsoError::UnknownServiceProvider => {
+                write!(f, "Unknown service provider or assertion consumer service")
+            }
+            SsoError::SamlError(e) => write!(f, "SAML error: {}", e),
         }
     }
 }
@@ -17,6 +37,14 @@ impl From<SsoError> for actix_web::Error {
     fn from(e: SsoError) -> Self {
         match e {
             SsoError::CookieNotFound => actix_web::error::ErrorBadRequest(e),
+            SsoError::ClientRegistrationNotAllowed => actix_web::error::ErrorForbidden(e),
+            SsoError::UnknownIdentityProvider => actix_web::error::ErrorNotFound(e),
+            SsoError::InvalidFederationState => actix_web::error::ErrorBadRequest(e),
+            SsoError::UpstreamError(_) => actix_web::error::ErrorBadGateway(e),
+            SsoError::SamlNotConfigured => actix_web::error::ErrorNotFound(e),
+            SsoError::InvalidSamlRequest(_) => actix_web::error::ErrorBadRequest(e),
+            SsoError::UnknownServiceProvider => actix_web::error::ErrorBadRequest(e),
+            SsoError::SamlError(_) => actix_web::error::ErrorInternalServerError(e),
         }
     }
 }
@@ -0,0 +1,335 @@
+use actix_web::client::Client;
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::web::Data;
+use actix_web::{http, web, HttpMessage, HttpRequest, HttpResponse, Result};
+use serde::Deserialize;
+use url::Url;
+
+use crate::app_data::AppData;
+use crate::auth::model::FederatedIdentityClaims;
+use crate::config::IdentityProviderConfig;
+use crate::core::sso::error::SsoError;
+use crate::core::sso::utils::redirect_with_auth_code;
+
+const FEDERATION_COOKIE: &str = "federation-state";
+
+#[derive(Deserialize)]
+pub struct FederationLoginParam {
+    client_id: String,
+    redirect_uri: String,
+}
+
+#[derive(Deserialize)]
+pub struct FederationCallbackParam {
+    code: Option<String>,
+    state: String,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct UpstreamTokenResponse {
+    access_token: String,
+}
+
+#[derive(Deserialize)]
+struct UpstreamUserInfo {
+    sub: String,
+    email: Option<String>,
+    #[serde(default)]
+    email_verified: bool,
+    preferred_username: Option<String>,
+}
+
+pub async fn handle_login(
+    query: web::Query<FederationLoginParam>,
+    data: Data<AppData>,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    let provider = get_provider(&data, &req)?;
+
+    let (state, nonce) = data.auth_handler.generate_federation_state(
+        &provider.id,
+        &query.client_id,
+        &query.redirect_uri,
+    )?;
+
+    let callback_url = get_callback_url(&data.config.auth.base_url, &provider);
+    let authorization_url = Url::parse_with_params(
+        &provider.authorization_endpoint,
+        &[
+            ("response_type", "code"),
+            ("client_id", provider.client_id.as_str()),
+            ("redirect_uri", callback_url.as_str()),
+            ("scope", provider.scope.as_str()),
+            ("state", state.as_str()),
+        ],
+    )
+    .map_err(|e| SsoError::UpstreamError(e.to_string()))?;
+
+    // Binds the state to this browser, so a victim can't be logged in with an attacker's code
+    let cookie = Cookie::build(FEDERATION_COOKIE, nonce)
+        .same_site(SameSite::Lax)
+        .http_only(true)
+        .path("/")
+        .finish();
+
+    Ok(HttpResponse::Found()
+        .cookie(cookie)
+        .header(http::header::LOCATION, authorization_url.into_string())
+        .finish())
+}
+
+pub async fn handle_callback(
+    query: web::Query<FederationCallbackParam>,
+    data: Data<AppData>,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    let provider = get_provider(&data, &req)?;
+
+    let state = data.auth_handler.verify_federation_state(&query.state)?;
+    let nonce = req
+        .cookie(FEDERATION_COOKIE)
+        .ok_or(SsoError::CookieNotFound)?;
+    if state.provider != provider.id || nonce.value() != state.salt {
+        return Err(SsoError::InvalidFederationState.into());
+    }
+
+    if let Some(error) = &query.error {
+        return Err(SsoError::UpstreamError(error.to_owned()).into());
+    }
+    let code = query
+        .code
+        .as_ref()
+        .ok_or(SsoError::UpstreamError("missing code".to_owned()))?;
+
+    let callback_url = get_callback_url(&data.config.auth.base_url, &provider);
+    let identity = fetch_identity(&provider, code, &callback_url).await?;
+
+    let auth_code = data
+        .auth_handler
+        .get_federated_authorization_code(&identity, &state.client_id)?;
+
+    let mut removal = Cookie::named(FEDERATION_COOKIE);
+    removal.set_path("/");
+    removal.make_removal();
+
+    let mut response = redirect_with_auth_code(&state.redirect_uri, auth_code)?;
+    response.add_cookie(&removal)?;
+    Ok(response)
+}
+
+fn get_provider(data: &Data<AppData>, req: &HttpRequest) -> Result<IdentityProviderConfig> {
+    let provider_id = req.match_info().get("provider").unwrap();
+
+    data.config
+        .identity_providers
+        .as_ref()
+        .and_then(|providers| providers.iter().find(|p| p.id == provider_id))
+        .cloned()
+        .ok_or(SsoError::UnknownIdentityProvider.into())
+}
+
+fn get_callback_url(base_url: &String, provider: &IdentityProviderConfig) -> String {
+    format!("{}/federated/{}/callback", base_url, provider.id)
+}
+
+/// Exchanges the upstream authorization code and reads the user's claims from the
+/// provider's userinfo endpoint.
+async fn fetch_identity(
+    provider: &IdentityProviderConfig,
+    code: &String,
+    callback_url: &String,
+) -> Result<FederatedIdentityClaims, SsoError> {
+    let client = Client::default();
+
+    let mut response = client
+        .post(&provider.token_endpoint)
+        .header(http::header::ACCEPT, "application/json")
+        .send_form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code.as_str()),
+            ("redirect_uri", callback_url.as_str()),
+            ("client_id", provider.client_id.as_str()),
+            ("client_secret", provider.client_secret.as_str()),
+        ])
+        .await
+        .map_err(|e| SsoError::UpstreamError(e.to_string()))?;
+    if !response.status().is_success() {
+        return Err(SsoError::UpstreamError(format!(
+            "token endpoint answered {}",
+            response.status()
+        )));
+    }
+    let token: UpstreamTokenResponse = response
+        .json()
+        .await
+        .map_err(|e| SsoError::UpstreamError(e.to_string()))?;
+
+    let mut response = client
+        .get(&provider.userinfo_endpoint)
+        .bearer_auth(&token.access_token)
+        .header(http::header::ACCEPT, "application/json")
+        .send()
+        .await
+        .map_err(|e| SsoError::UpstreamError(e.to_string()))?;
+    if !response.status().is_success() {
+        return Err(SsoError::UpstreamError(format!(
+            "userinfo endpoint answered {}",
+            response.status()
+        )));
+    }
+    let user_info: UpstreamUserInfo = response
+        .json()
+        .await
+        .map_err(|e| SsoError::UpstreamError(e.to_string()))?;
+
+    Ok(FederatedIdentityClaims {
+        provider: provider.id.to_owned(),
+        subject: user_info.sub,
+        email: user_info.email,
+        email_verified: user_info.email_verified,
+        preferred_username: user_info.preferred_username,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use actix_web::{test, App};
+    use serde_json::json;
+
+    use super::*;
+
+    const CALLBACK_URL: &str = "http://localhost:8000/federated/corporate/callback";
+
+    /// A mock IdP that hands out one access token per known code, `unverified-code` belongs to
+    /// a user whose email the IdP doesn't vouch for.
+    fn start_mock_idp() -> test::TestServer {
+        test::start(|| {
+            App::new()
+                .route("/token", web::post().to(mock_token))
+                .route("/userinfo", web::get().to(mock_userinfo))
+        })
+    }
+
+    async fn mock_token(form: web::Form<HashMap<String, String>>) -> HttpResponse {
+        let field = |name: &str| form.get(name).map(String::as_str);
+        if field("grant_type") != Some("authorization_code")
+            || field("client_secret") != Some("secret")
+            || field("redirect_uri") != Some(CALLBACK_URL)
+        {
+            return HttpResponse::BadRequest().json(json!({ "error": "invalid_request" }));
+        }
+
+        let access_token = match field("code") {
+            Some("verified-code") => "verified-token",
+            Some("unverified-code") => "unverified-token",
+            _ => return HttpResponse::BadRequest().json(json!({ "error": "invalid_grant" })),
+        };
+        HttpResponse::Ok().json(json!({ "access_token": access_token, "token_type": "Bearer" }))
+    }
+
+    async fn mock_userinfo(req: HttpRequest) -> HttpResponse {
+        let authorization = req
+            .headers()
+            .get(http::header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok());
+
+        match authorization {
+            Some("Bearer verified-token") => HttpResponse::Ok().json(json!({
+                "sub": "upstream-42",
+                "email": "shizuka@example.com",
+                "email_verified": true,
+                "preferred_username": "shizuka",
+            })),
+            Some("Bearer unverified-token") => HttpResponse::Ok().json(json!({
+                "sub": "upstream-43",
+                "email": "shizuka@example.com",
+            })),
+            _ => HttpResponse::Unauthorized().finish(),
+        }
+    }
+
+    fn provider(idp: &test::TestServer) -> IdentityProviderConfig {
+        IdentityProviderConfig {
+            id: "corporate".to_owned(),
+            display_name: "Corporate SSO".to_owned(),
+            client_id: "doraemon".to_owned(),
+            client_secret: "secret".to_owned(),
+            authorization_endpoint: idp.url("/authorize"),
+            token_endpoint: idp.url("/token"),
+            userinfo_endpoint: idp.url("/userinfo"),
+            scope: "openid email profile".to_owned(),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn fetches_verified_identity() {
+        let idp = start_mock_idp();
+
+        let identity = fetch_identity(
+            &provider(&idp),
+            &"verified-code".to_owned(),
+            &CALLBACK_URL.to_owned(),
+        )
+        .await
+        .unwrap_or_else(|e| panic!("{}", e));
+
+        assert_eq!(identity.provider, "corporate");
+        assert_eq!(identity.subject, "upstream-42");
+        assert_eq!(identity.email, Some("shizuka@example.com".to_owned()));
+        assert!(identity.email_verified);
+        assert_eq!(identity.preferred_username, Some("shizuka".to_owned()));
+    }
+
+    #[actix_rt::test]
+    async fn treats_missing_email_verified_as_unverified() {
+        let idp = start_mock_idp();
+
+        let identity = fetch_identity(
+            &provider(&idp),
+            &"unverified-code".to_owned(),
+            &CALLBACK_URL.to_owned(),
+        )
+        .await
+        .unwrap_or_else(|e| panic!("{}", e));
+
+        assert_eq!(identity.subject, "upstream-43");
+        assert!(!identity.email_verified);
+    }
+
+    #[actix_rt::test]
+    async fn rejects_code_refused_by_provider() {
+        let idp = start_mock_idp();
+
+        let result = fetch_identity(
+            &provider(&idp),
+            &"stolen-code".to_owned(),
+            &CALLBACK_URL.to_owned(),
+        )
+        .await;
+
+        match result {
+            Err(SsoError::UpstreamError(_)) => (),
+            _ => panic!("expected UpstreamError"),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn rejects_mismatched_callback_url() {
+        let idp = start_mock_idp();
+
+        let result = fetch_identity(
+            &provider(&idp),
+            &"verified-code".to_owned(),
+            &"http://attacker.example/callback".to_owned(),
+        )
+        .await;
+
+        match result {
+            Err(SsoError::UpstreamError(_)) => (),
+            _ => panic!("expected UpstreamError"),
+        }
+    }
+}
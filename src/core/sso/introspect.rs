@@ -0,0 +1,88 @@
+use actix_web::web::Data;
+use actix_web::{http, web, HttpRequest, HttpResponse, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::app_data::AppData;
+use crate::auth::model::{Token, TokenPayload};
+use crate::core::sso::utils::get_basic_credentials;
+
+#[derive(Deserialize, Clone)]
+pub struct IntrospectionRequest {
+    token: Token,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+}
+
+/// Introspection response, see RFC 7662 section 2.2
+#[derive(Serialize, Default)]
+pub struct IntrospectionResponse {
+    active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sub: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scope: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token_type: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exp: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    iat: Option<u64>,
+}
+
+impl From<TokenPayload> for IntrospectionResponse {
+    fn from(token: TokenPayload) -> Self {
+        IntrospectionResponse {
+            active: true,
+            sub: Some(token.username.to_owned()),
+            username: Some(token.username),
+            client_id: token.client_id,
+            scope: token.scope,
+            token_type: Some("Bearer"),
+            exp: Some((token.expiry_timestamp / 1000) as u64),
+            iat: token.issued_timestamp.map(|iat| (iat / 1000) as u64),
+        }
+    }
+}
+
+pub async fn handle(
+    form: web::Form<IntrospectionRequest>,
+    data: Data<AppData>,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    let (client_id, client_secret) = match get_basic_credentials(&req) {
+        Some(credentials) => credentials,
+        None => match (&form.client_id, &form.client_secret) {
+            (Some(id), Some(secret)) => (id.to_owned(), secret.to_owned()),
+            _ => return Ok(unauthorized_client()),
+        },
+    };
+
+    if data
+        .auth_handler
+        .authenticate_client(&client_id, &client_secret)
+        .is_err()
+    {
+        return Ok(unauthorized_client());
+    }
+
+    // Refresh tokens aren't introspectable, so `token_type_hint` never changes the lookup and
+    // is left to serde to skip, unknown hints included (RFC 7662 section 2.1)
+    let response = match data.auth_handler.inspect(&form.token) {
+        Ok(token) => IntrospectionResponse::from(token),
+        Err(_) => IntrospectionResponse::default(),
+    };
+
+    Ok(HttpResponse::Ok()
+        .header(http::header::CACHE_CONTROL, "no-store")
+        .json(response))
+}
+
+fn unauthorized_client() -> HttpResponse {
+    HttpResponse::Unauthorized()
+        .header(http::header::WWW_AUTHENTICATE, "Basic realm=\"doraemon\"")
+        .json(serde_json::json!({ "error": "invalid_client" }))
+}
@@ -18,9 +18,13 @@ pub struct TokenResponse {
 }
 
 pub async fn handle(item: web::Json<UserPayload>, data: Data<AppData>) -> Result<HttpResponse> {
-    let (token, refresh_token) = data
+    let directory_login = data
         .auth_handler
-        .get_token(&item.username, &item.password)?;
+        .check_directory(&item.username, &item.password)
+        .await;
+    let (token, refresh_token) =
+        data.auth_handler
+            .get_token(&item.username, &item.password, &directory_login)?;
     Ok(HttpResponse::Ok().json(TokenResponse {
         access_token: token,
         refresh_token,
@@ -1,15 +1,22 @@
 use actix_web::dev::HttpServiceFactory;
-use actix_web::web;
+use actix_web::{guard, web};
 
 mod activate;
 mod authorize;
+mod client_registration;
+mod device;
+mod federation;
 mod inspect;
+mod introspect;
 mod login;
+mod personal_access_token;
 mod register;
+mod reset_password;
+mod saml;
 mod token;
 
 mod error;
-mod utils;
+pub mod utils;
 
 pub fn service(prefix: &str) -> impl HttpServiceFactory {
     web::scope(prefix)
@@ -18,8 +25,48 @@ pub fn service(prefix: &str) -> impl HttpServiceFactory {
         .route("/activate", web::post().to(activate::handle_resend))
         .route("/authorize", web::post().to(authorize::handle_login))
         .route("/authorize", web::get().to(authorize::handle_form))
+        .route(
+            "/token",
+            web::post()
+                .guard(guard::fn_guard(device::is_form_request))
+                .to(device::handle_token),
+        )
         .route("/token", web::post().to(token::handle))
+        .route("/device/code", web::post().to(device::handle_code))
+        .route("/device", web::get().to(device::handle_form))
+        .route("/device", web::post().to(device::handle_verify))
         .route("/register", web::post().to(register::handle_register))
         .route("/register", web::get().to(register::handle_form))
         .route("/inspect", web::post().to(inspect::handle))
+        .route("/introspect", web::post().to(introspect::handle))
+        .route(
+            "/reset-password",
+            web::get().to(reset_password::handle_form),
+        )
+        .route(
+            "/reset-password",
+            web::post().to(reset_password::handle_reset),
+        )
+        .route("/clients", web::post().to(client_registration::handle))
+        .route("/tokens", web::get().to(personal_access_token::handle_list))
+        .route(
+            "/tokens",
+            web::post().to(personal_access_token::handle_create),
+        )
+        .route(
+            "/tokens/{id}",
+            web::delete().to(personal_access_token::handle_revoke),
+        )
+        .route(
+            "/federated/{provider}",
+            web::get().to(federation::handle_login),
+        )
+        .route(
+            "/federated/{provider}/callback",
+            web::get().to(federation::handle_callback),
+        )
+        .route("/saml/metadata", web::get().to(saml::metadata::handle))
+        .route("/saml/sso", web::get().to(saml::sso::handle_redirect))
+        .route("/saml/sso", web::post().to(saml::sso::handle_post))
+        .route("/saml/login", web::post().to(saml::sso::handle_login))
 }
@@ -0,0 +1,81 @@
+use actix_web::web::{Data, Json};
+use actix_web::{HttpRequest, HttpResponse, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::app_data::AppData;
+use crate::auth::model::{Token, TokenPayload};
+use crate::auth::AuthError;
+use crate::core::url_shortener::utils::authenticate;
+use crate::database::handler::personal_access_token::PersonalAccessToken;
+
+#[derive(Deserialize)]
+pub struct CreatePersonalAccessTokenRequest {
+    name: String,
+    scopes: Vec<String>,
+    expires_in: Option<u64>,
+}
+
+/// The plain token is only ever returned here, it is stored hashed
+#[derive(Serialize)]
+pub struct CreatePersonalAccessTokenResponse {
+    token: Token,
+    #[serde(flatten)]
+    personal_access_token: PersonalAccessToken,
+}
+
+/// Personal access tokens can only be managed with a full user session, never with another
+/// personal access token or an impersonation token.
+fn authenticate_owner(data: &Data<AppData>, req: &HttpRequest) -> Result<TokenPayload> {
+    let token = authenticate(data, req)?;
+    if token.scope.is_some() || token.impersonator.is_some() {
+        return Err(AuthError::InsufficientScope.into());
+    }
+    Ok(token)
+}
+
+pub async fn handle_list(data: Data<AppData>, req: HttpRequest) -> Result<HttpResponse> {
+    let token = authenticate_owner(&data, &req)?;
+
+    let personal_access_tokens = data
+        .auth_handler
+        .get_personal_access_tokens(&token.username)?;
+
+    Ok(HttpResponse::Ok().json(personal_access_tokens))
+}
+
+pub async fn handle_create(
+    request: Json<CreatePersonalAccessTokenRequest>,
+    data: Data<AppData>,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    let token = authenticate_owner(&data, &req)?;
+
+    let (personal_access_token, token) = data.auth_handler.create_personal_access_token(
+        &token.username,
+        &request.name,
+        &request.scopes,
+        request.expires_in,
+    )?;
+
+    Ok(
+        HttpResponse::Created().json(CreatePersonalAccessTokenResponse {
+            token,
+            personal_access_token,
+        }),
+    )
+}
+
+pub async fn handle_revoke(data: Data<AppData>, req: HttpRequest) -> Result<HttpResponse> {
+    let token = authenticate_owner(&data, &req)?;
+    let id = req
+        .match_info()
+        .get("id")
+        .unwrap()
+        .parse::<i32>()
+        .map_err(actix_web::error::ErrorBadRequest)?;
+
+    data.auth_handler
+        .revoke_personal_access_token(&token.username, id)?;
+
+    Ok(HttpResponse::Ok().finish())
+}
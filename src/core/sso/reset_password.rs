@@ -0,0 +1,45 @@
+use actix_web::web::Data;
+use actix_web::{web, HttpResponse, Result};
+use serde::Deserialize;
+
+use crate::app_data::AppData;
+
+#[derive(Deserialize, Clone)]
+pub struct ResetCodePayload {
+    code: String,
+}
+
+pub async fn handle_form(
+    data: Data<AppData>,
+    req: web::Query<ResetCodePayload>,
+) -> Result<HttpResponse> {
+    display_reset_password_form(data, &req.code, None)
+}
+
+#[derive(Deserialize, Clone)]
+pub struct ResetPasswordPayload {
+    code: String,
+    password: String,
+}
+
+pub async fn handle_reset(
+    data: Data<AppData>,
+    req: web::Form<ResetPasswordPayload>,
+) -> Result<HttpResponse> {
+    match data.auth_handler.reset_password(&req.code, &req.password) {
+        Err(e) => display_reset_password_form(data, &req.code, Some(e.to_string())),
+        Ok(_) => Ok(HttpResponse::Ok().body("Password changed! You can now login")),
+    }
+}
+
+fn display_reset_password_form(
+    data: Data<AppData>,
+    code: &String,
+    message: Option<String>,
+) -> Result<HttpResponse> {
+    let view = data
+        .as_ref()
+        .templater
+        .reset_password_page(code, &message.unwrap_or("".to_owned()))?;
+    Ok(HttpResponse::Ok().body(view))
+}
@@ -0,0 +1,47 @@
+use actix_web::web::Data;
+use actix_web::{HttpResponse, Result};
+
+use crate::app_data::AppData;
+use crate::core::sso::error::SsoError;
+use crate::core::sso::saml::request::{BINDING_POST, BINDING_REDIRECT};
+use crate::core::sso::saml::xml::{escape_attribute, load_certificate, DSIG_NS, METADATA_NS};
+
+const NAME_ID_FORMAT_UNSPECIFIED: &str = "urn:oasis:names:tc:SAML:1.1:nameid-format:unspecified";
+
+pub async fn handle(data: Data<AppData>) -> Result<HttpResponse> {
+    let config = data
+        .config
+        .saml
+        .as_ref()
+        .ok_or(SsoError::SamlNotConfigured)?;
+
+    let sso_url = escape_attribute(&(data.config.auth.base_url.to_owned() + "/saml/sso"));
+    let metadata = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+         <md:EntityDescriptor xmlns:md=\"{}\" entityID=\"{}\">\
+         <md:IDPSSODescriptor WantAuthnRequestsSigned=\"false\" \
+         protocolSupportEnumeration=\"urn:oasis:names:tc:SAML:2.0:protocol\">\
+         <md:KeyDescriptor use=\"signing\">\
+         <ds:KeyInfo xmlns:ds=\"{}\"><ds:X509Data><ds:X509Certificate>{}</ds:X509Certificate>\
+         </ds:X509Data></ds:KeyInfo>\
+         </md:KeyDescriptor>\
+         <md:NameIDFormat>{}</md:NameIDFormat>\
+         <md:SingleSignOnService Binding=\"{}\" Location=\"{}\"/>\
+         <md:SingleSignOnService Binding=\"{}\" Location=\"{}\"/>\
+         </md:IDPSSODescriptor>\
+         </md:EntityDescriptor>",
+        METADATA_NS,
+        escape_attribute(&config.entity_id),
+        DSIG_NS,
+        load_certificate(config)?,
+        NAME_ID_FORMAT_UNSPECIFIED,
+        BINDING_REDIRECT,
+        sso_url,
+        BINDING_POST,
+        sso_url
+    );
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/samlmetadata+xml")
+        .body(metadata))
+}
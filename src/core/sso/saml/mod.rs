@@ -0,0 +1,6 @@
+pub mod metadata;
+pub mod sso;
+
+mod request;
+mod response;
+mod xml;
@@ -0,0 +1,64 @@
+use std::io::Read;
+
+use flate2::read::DeflateDecoder;
+
+use crate::core::sso::error::SsoError;
+use crate::core::sso::saml::xml::{SAMLP_NS, SAML_NS};
+
+pub const BINDING_REDIRECT: &str = "urn:oasis:names:tc:SAML:2.0:bindings:HTTP-Redirect";
+pub const BINDING_POST: &str = "urn:oasis:names:tc:SAML:2.0:bindings:HTTP-POST";
+
+pub struct AuthnRequest {
+    pub id: String,
+    pub issuer: String,
+    pub acs_url: Option<String>,
+}
+
+fn invalid_request(reason: impl ToString) -> SsoError {
+    SsoError::InvalidSamlRequest(reason.to_string())
+}
+
+/// Decodes a `SAMLRequest` parameter. The HTTP-Redirect binding deflates the message before
+/// encoding it, the HTTP-POST binding only base64 encodes it.
+pub fn parse(saml_request: &String, binding: &str) -> Result<AuthnRequest, SsoError> {
+    let compact: String = saml_request
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    let bytes = base64::decode(&compact).map_err(invalid_request)?;
+
+    let xml = match binding {
+        BINDING_REDIRECT => {
+            let mut xml = String::new();
+            DeflateDecoder::new(&bytes[..])
+                .read_to_string(&mut xml)
+                .map_err(invalid_request)?;
+            xml
+        }
+        BINDING_POST => String::from_utf8(bytes).map_err(invalid_request)?,
+        _ => return Err(invalid_request("unsupported binding")),
+    };
+
+    let document = roxmltree::Document::parse(&xml).map_err(invalid_request)?;
+    let root = document.root_element();
+    if root.tag_name().name() != "AuthnRequest" || root.tag_name().namespace() != Some(SAMLP_NS) {
+        return Err(invalid_request("expected an AuthnRequest"));
+    }
+
+    let issuer = root
+        .children()
+        .find(|node| node.has_tag_name((SAML_NS, "Issuer")))
+        .and_then(|node| node.text())
+        .ok_or(invalid_request("missing Issuer"))?;
+
+    Ok(AuthnRequest {
+        id: root
+            .attribute("ID")
+            .ok_or(invalid_request("missing ID"))?
+            .to_owned(),
+        issuer: issuer.trim().to_owned(),
+        acs_url: root
+            .attribute("AssertionConsumerServiceURL")
+            .map(|url| url.to_owned()),
+    })
+}
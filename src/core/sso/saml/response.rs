@@ -0,0 +1,244 @@
+use std::ops::Add;
+
+use chrono::{Duration, Utc};
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+
+use crate::config::SamlConfig;
+use crate::core::sso::error::SsoError;
+use crate::core::sso::saml::request::AuthnRequest;
+use crate::core::sso::saml::xml::{escape_attribute, escape_text, sign, SAMLP_NS, SAML_NS};
+use crate::database::handler::user::User;
+
+const NAME_ID_FORMAT_UNSPECIFIED: &str = "urn:oasis:names:tc:SAML:1.1:nameid-format:unspecified";
+const BEARER_CONFIRMATION: &str = "urn:oasis:names:tc:SAML:2.0:cm:bearer";
+const PASSWORD_PROTECTED_TRANSPORT: &str =
+    "urn:oasis:names:tc:SAML:2.0:ac:classes:PasswordProtectedTransport";
+const STATUS_SUCCESS: &str = "urn:oasis:names:tc:SAML:2.0:status:Success";
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%SZ";
+
+fn generate_id() -> String {
+    let id: String = thread_rng().sample_iter(&Alphanumeric).take(40).collect();
+    format!("_{}", id)
+}
+
+fn attribute(name: &str, value: &String) -> String {
+    format!(
+        "<saml:Attribute Name=\"{}\"><saml:AttributeValue>{}</saml:AttributeValue></saml:Attribute>",
+        name,
+        escape_text(value)
+    )
+}
+
+/// Builds a `samlp:Response` carrying a signed assertion for `user`. Elements are written
+/// directly in canonical form (sorted attributes, explicit end tags) so the assertion digest
+/// matches what service providers compute after exclusive canonicalization.
+pub fn build(
+    config: &SamlConfig,
+    request: &AuthnRequest,
+    acs_url: &String,
+    user: &User,
+) -> Result<String, SsoError> {
+    let now = Utc::now();
+    let issue_instant = now.format(TIMESTAMP_FORMAT).to_string();
+    let not_on_or_after = now
+        .add(Duration::seconds(config.assertion_lifetime as i64))
+        .format(TIMESTAMP_FORMAT)
+        .to_string();
+    let assertion_id = generate_id();
+
+    let issuer = format!(
+        "<saml:Issuer>{}</saml:Issuer>",
+        escape_text(&config.entity_id)
+    );
+    let assertion_start = format!(
+        "<saml:Assertion xmlns:saml=\"{}\" ID=\"{}\" IssueInstant=\"{}\" Version=\"2.0\">",
+        SAML_NS, assertion_id, issue_instant
+    );
+    let assertion_body = format!(
+        "<saml:Subject>\
+         <saml:NameID Format=\"{}\">{}</saml:NameID>\
+         <saml:SubjectConfirmation Method=\"{}\">\
+         <saml:SubjectConfirmationData InResponseTo=\"{}\" NotOnOrAfter=\"{}\" Recipient=\"{}\">\
+         </saml:SubjectConfirmationData>\
+         </saml:SubjectConfirmation>\
+         </saml:Subject>\
+         <saml:Conditions NotBefore=\"{}\" NotOnOrAfter=\"{}\">\
+         <saml:AudienceRestriction><saml:Audience>{}</saml:Audience></saml:AudienceRestriction>\
+         </saml:Conditions>\
+         <saml:AuthnStatement AuthnInstant=\"{}\" SessionIndex=\"{}\">\
+         <saml:AuthnContext><saml:AuthnContextClassRef>{}</saml:AuthnContextClassRef></saml:AuthnContext>\
+         </saml:AuthnStatement>\
+         <saml:AttributeStatement>{}{}</saml:AttributeStatement>\
+         </saml:Assertion>",
+        NAME_ID_FORMAT_UNSPECIFIED,
+        escape_text(&user.username),
+        BEARER_CONFIRMATION,
+        escape_attribute(&request.id),
+        not_on_or_after,
+        escape_attribute(acs_url),
+        issue_instant,
+        not_on_or_after,
+        escape_text(&request.issuer),
+        issue_instant,
+        assertion_id,
+        PASSWORD_PROTECTED_TRANSPORT,
+        attribute("username", &user.username),
+        attribute("email", &user.email)
+    );
+
+    let unsigned_assertion = format!("{}{}{}", assertion_start, issuer, assertion_body);
+    let signature = sign(config, &unsigned_assertion, &assertion_id)?;
+    let assertion = format!(
+        "{}{}{}{}",
+        assertion_start, issuer, signature, assertion_body
+    );
+
+    Ok(format!(
+        "<samlp:Response xmlns:saml=\"{}\" xmlns:samlp=\"{}\" Destination=\"{}\" ID=\"{}\" \
+         InResponseTo=\"{}\" IssueInstant=\"{}\" Version=\"2.0\">\
+         {}\
+         <samlp:Status><samlp:StatusCode Value=\"{}\"></samlp:StatusCode></samlp:Status>\
+         {}\
+         </samlp:Response>",
+        SAML_NS,
+        SAMLP_NS,
+        escape_attribute(acs_url),
+        generate_id(),
+        escape_attribute(&request.id),
+        issue_instant,
+        issuer,
+        STATUS_SUCCESS,
+        assertion
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use openssl::asn1::Asn1Time;
+    use openssl::hash::MessageDigest;
+    use openssl::pkey::PKey;
+    use openssl::rsa::Rsa;
+    use openssl::sha::sha256;
+    use openssl::sign::Verifier;
+    use openssl::x509::{X509NameBuilder, X509};
+
+    use super::*;
+
+    /// Writes a fresh key and self-signed certificate, named after the test so they don't clash
+    fn config(name: &str) -> SamlConfig {
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let mut subject = X509NameBuilder::new().unwrap();
+        subject
+            .append_entry_by_text("CN", "idp.example.com")
+            .unwrap();
+        let subject = subject.build();
+        let mut certificate = X509::builder().unwrap();
+        certificate.set_version(2).unwrap();
+        certificate.set_subject_name(&subject).unwrap();
+        certificate.set_issuer_name(&subject).unwrap();
+        certificate.set_pubkey(&key).unwrap();
+        certificate
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        certificate
+            .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        certificate.sign(&key, MessageDigest::sha256()).unwrap();
+
+        let dir = env::temp_dir();
+        let certificate_path = dir.join(format!("saml-{}-{}.crt", name, std::process::id()));
+        let private_key_path = dir.join(format!("saml-{}-{}.key", name, std::process::id()));
+        fs::write(&certificate_path, certificate.build().to_pem().unwrap()).unwrap();
+        fs::write(&private_key_path, key.private_key_to_pem_pkcs8().unwrap()).unwrap();
+
+        SamlConfig {
+            entity_id: "https://idp.example.com/saml".to_owned(),
+            certificate_path: certificate_path.to_string_lossy().into_owned(),
+            private_key_path: private_key_path.to_string_lossy().into_owned(),
+            assertion_lifetime: 300,
+        }
+    }
+
+    fn user(username: &str) -> User {
+        User {
+            id: 1,
+            username: username.to_owned(),
+            password: String::new(),
+            salt: String::new(),
+            email: "shizuka@example.com".to_owned(),
+            is_activated: true,
+            role: "user".to_owned(),
+            is_locked: false,
+            failed_login_attempts: 0,
+            must_reset_password: false,
+        }
+    }
+
+    fn request() -> AuthnRequest {
+        AuthnRequest {
+            id: "_request\"1".to_owned(),
+            issuer: "https://sp.example.com".to_owned(),
+            acs_url: None,
+        }
+    }
+
+    /// The part of `document` from `start` up to and including `end`
+    fn element<'a>(document: &'a str, start: &str, end: &str) -> &'a str {
+        let from = document.find(start).unwrap();
+        let to = from + document[from..].find(end).unwrap() + end.len();
+        &document[from..to]
+    }
+
+    fn text<'a>(document: &'a str, tag: &str) -> &'a str {
+        let open = format!("<{}>", tag);
+        let from = document.find(&open).unwrap() + open.len();
+        let to = from + document[from..].find(&format!("</{}>", tag)).unwrap();
+        &document[from..to]
+    }
+
+    /// Checks the assertion the way a service provider does: the digest over the assertion
+    /// without its enveloped signature, then the signature over `SignedInfo`
+    fn verify(config: &SamlConfig, response: &str) {
+        let assertion = element(response, "<saml:Assertion ", "</saml:Assertion>");
+        let signature = element(assertion, "<ds:Signature ", "</ds:Signature>");
+        let unsigned_assertion = assertion.replace(signature, "");
+        assert_eq!(
+            text(signature, "ds:DigestValue"),
+            base64::encode(&sha256(unsigned_assertion.as_bytes()))
+        );
+
+        let certificate = X509::from_pem(&fs::read(&config.certificate_path).unwrap()).unwrap();
+        let key = certificate.public_key().unwrap();
+        let signed_info = element(signature, "<ds:SignedInfo ", "</ds:SignedInfo>");
+        let signature_value = base64::decode(text(signature, "ds:SignatureValue")).unwrap();
+        let mut verifier = Verifier::new(MessageDigest::sha256(), &key).unwrap();
+        verifier.update(signed_info.as_bytes()).unwrap();
+        assert!(verifier.verify(&signature_value).unwrap());
+    }
+
+    #[test]
+    fn signs_assertion() {
+        let config = config("signs-assertion");
+        let acs_url = "https://sp.example.com/acs".to_owned();
+        let response = build(&config, &request(), &acs_url, &user("shizuka")).unwrap();
+
+        verify(&config, &response);
+    }
+
+    #[test]
+    fn escapes_text_and_attributes_in_canonical_form() {
+        let config = config("escapes");
+        let acs_url = "https://sp.example.com/acs?a=1&b=2".to_owned();
+        let response = build(&config, &request(), &acs_url, &user("a<b>\"c\"&d")).unwrap();
+
+        verify(&config, &response);
+        assert!(response.contains(">a&lt;b&gt;\"c\"&amp;d</saml:NameID>"));
+        assert!(response.contains("InResponseTo=\"_request&quot;1\""));
+        assert!(response.contains("Recipient=\"https://sp.example.com/acs?a=1&amp;b=2\""));
+    }
+}
@@ -0,0 +1,116 @@
+use actix_web::web::Data;
+use actix_web::{web, HttpResponse, Result};
+use serde::Deserialize;
+
+use crate::app_data::AppData;
+use crate::core::sso::error::SsoError;
+use crate::core::sso::saml::request::{self, AuthnRequest, BINDING_POST, BINDING_REDIRECT};
+use crate::core::sso::saml::response;
+
+#[derive(Deserialize)]
+pub struct SamlRequestParam {
+    #[serde(rename = "SAMLRequest")]
+    saml_request: String,
+    #[serde(rename = "RelayState")]
+    relay_state: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SamlLoginPayload {
+    username: String,
+    password: String,
+    saml_request: String,
+    binding: String,
+    relay_state: Option<String>,
+}
+
+/// Looks up the issuing service provider and returns the assertion consumer service to post
+/// the response to. A requested ACS URL must match the registered one.
+fn resolve_acs_url(data: &AppData, request: &AuthnRequest) -> Result<String, SsoError> {
+    let service_provider = data
+        .saml_service_provider_handler
+        .get_by_entity_id(&request.issuer)
+        .map_err(|_| SsoError::UnknownServiceProvider)?;
+
+    match &request.acs_url {
+        Some(acs_url) if acs_url != &service_provider.acs_url => {
+            Err(SsoError::UnknownServiceProvider)
+        }
+        _ => Ok(service_provider.acs_url),
+    }
+}
+
+fn login_form(data: &AppData, param: &SamlRequestParam, binding: &str) -> Result<HttpResponse> {
+    data.config
+        .saml
+        .as_ref()
+        .ok_or(SsoError::SamlNotConfigured)?;
+
+    let request = request::parse(&param.saml_request, binding)?;
+    resolve_acs_url(data, &request)?;
+
+    let template = data.templater.saml_login_page(
+        &param.saml_request,
+        &binding.to_owned(),
+        param.relay_state.as_ref(),
+        &String::new(),
+    )?;
+    Ok(HttpResponse::Ok().body(template))
+}
+
+pub async fn handle_redirect(
+    query: web::Query<SamlRequestParam>,
+    data: Data<AppData>,
+) -> Result<HttpResponse> {
+    login_form(&data, &query, BINDING_REDIRECT)
+}
+
+pub async fn handle_post(
+    form: web::Form<SamlRequestParam>,
+    data: Data<AppData>,
+) -> Result<HttpResponse> {
+    login_form(&data, &form, BINDING_POST)
+}
+
+pub async fn handle_login(
+    form: web::Form<SamlLoginPayload>,
+    data: Data<AppData>,
+) -> Result<HttpResponse> {
+    let config = data
+        .config
+        .saml
+        .as_ref()
+        .ok_or(SsoError::SamlNotConfigured)?;
+
+    let request = request::parse(&form.saml_request, form.binding.as_str())?;
+    let acs_url = resolve_acs_url(&data, &request)?;
+
+    let directory_login = data
+        .auth_handler
+        .check_directory(&form.username, &form.password)
+        .await;
+    let user =
+        match data
+            .auth_handler
+            .authenticate(&form.username, &form.password, &directory_login)
+        {
+            Ok(user) => user,
+            Err(e) => {
+                let template = data.templater.saml_login_page(
+                    &form.saml_request,
+                    &form.binding,
+                    form.relay_state.as_ref(),
+                    &e.to_string(),
+                )?;
+                return Ok(HttpResponse::Unauthorized().body(template));
+            }
+        };
+
+    let saml_response = response::build(config, &request, &acs_url, &user)?;
+    let template = data.templater.saml_post_page(
+        &acs_url,
+        &base64::encode(saml_response.as_bytes()),
+        form.relay_state.as_ref(),
+    )?;
+    Ok(HttpResponse::Ok().body(template))
+}
@@ -0,0 +1,114 @@
+use std::fs;
+
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sha::sha256;
+use openssl::sign::Signer;
+use openssl::x509::X509;
+
+use crate::config::SamlConfig;
+use crate::core::sso::error::SsoError;
+
+pub const SAML_NS: &str = "urn:oasis:names:tc:SAML:2.0:assertion";
+pub const SAMLP_NS: &str = "urn:oasis:names:tc:SAML:2.0:protocol";
+pub const METADATA_NS: &str = "urn:oasis:names:tc:SAML:2.0:metadata";
+pub const DSIG_NS: &str = "http://www.w3.org/2000/09/xmldsig#";
+
+const EXC_C14N: &str = "http://www.w3.org/2001/10/xml-exc-c14n#";
+const ENVELOPED_SIGNATURE: &str = "http://www.w3.org/2000/09/xmldsig#enveloped-signature";
+const RSA_SHA256: &str = "http://www.w3.org/2001/04/xmldsig-more#rsa-sha256";
+const SHA256: &str = "http://www.w3.org/2001/04/xmlenc#sha256";
+
+/// Escapes element text the way exclusive canonicalization writes it, so the documents built
+/// here are already in canonical form and can be digested as-is.
+pub fn escape_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '\r' => escaped.push_str("&#xD;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escapes a double-quoted attribute value the way exclusive canonicalization writes it,
+/// which leaves `>` alone but escapes tabs and line breaks.
+pub fn escape_attribute(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\t' => escaped.push_str("&#x9;"),
+            '\n' => escaped.push_str("&#xA;"),
+            '\r' => escaped.push_str("&#xD;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn to_saml_error(e: impl std::fmt::Display) -> SsoError {
+    SsoError::SamlError(e.to_string())
+}
+
+/// Base64 of the DER encoded signing certificate, as embedded in `ds:X509Certificate`.
+pub fn load_certificate(config: &SamlConfig) -> Result<String, SsoError> {
+    let pem = fs::read(&config.certificate_path).map_err(to_saml_error)?;
+    let der = X509::from_pem(&pem)
+        .and_then(|certificate| certificate.to_der())
+        .map_err(to_saml_error)?;
+    Ok(base64::encode(&der))
+}
+
+/// Builds the enveloped `ds:Signature` for the canonical element `element` with the given
+/// `ID`. The signature has to be inserted right after the element's `Issuer`.
+pub fn sign(config: &SamlConfig, element: &str, id: &str) -> Result<String, SsoError> {
+    let digest = base64::encode(&sha256(element.as_bytes()));
+
+    let signed_info = format!(
+        "<ds:SignedInfo xmlns:ds=\"{}\">\
+         <ds:CanonicalizationMethod Algorithm=\"{}\"></ds:CanonicalizationMethod>\
+         <ds:SignatureMethod Algorithm=\"{}\"></ds:SignatureMethod>\
+         <ds:Reference URI=\"#{}\">\
+         <ds:Transforms>\
+         <ds:Transform Algorithm=\"{}\"></ds:Transform>\
+         <ds:Transform Algorithm=\"{}\"></ds:Transform>\
+         </ds:Transforms>\
+         <ds:DigestMethod Algorithm=\"{}\"></ds:DigestMethod>\
+         <ds:DigestValue>{}</ds:DigestValue>\
+         </ds:Reference>\
+         </ds:SignedInfo>",
+        DSIG_NS,
+        EXC_C14N,
+        RSA_SHA256,
+        escape_attribute(id),
+        ENVELOPED_SIGNATURE,
+        EXC_C14N,
+        SHA256,
+        digest
+    );
+
+    let pem = fs::read(&config.private_key_path).map_err(to_saml_error)?;
+    let key = PKey::private_key_from_pem(&pem).map_err(to_saml_error)?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &key).map_err(to_saml_error)?;
+    signer
+        .update(signed_info.as_bytes())
+        .map_err(to_saml_error)?;
+    let signature = signer.sign_to_vec().map_err(to_saml_error)?;
+
+    Ok(format!(
+        "<ds:Signature xmlns:ds=\"{}\">{}<ds:SignatureValue>{}</ds:SignatureValue>\
+         <ds:KeyInfo><ds:X509Data><ds:X509Certificate>{}</ds:X509Certificate></ds:X509Data>\
+         </ds:KeyInfo></ds:Signature>",
+        DSIG_NS,
+        signed_info,
+        base64::encode(&signature),
+        load_certificate(config)?
+    ))
+}
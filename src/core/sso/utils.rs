@@ -1,27 +1,65 @@
+use actix_web::{error, http, HttpRequest, HttpResponse};
 use lettre::{SmtpTransport, Transport};
 use lettre_email::EmailBuilder;
+use url::Url;
+
+use crate::auth::model::AuthCode;
 
 pub async fn send_activation_mail(
-    mut mailer: SmtpTransport,
+    mailer: SmtpTransport,
     origin: String,
     email: String,
     activation_url: String,
+) {
+    send_mail(
+        mailer,
+        origin,
+        email,
+        "Activation code",
+        format!("Here is your activation code!\n{}", activation_url),
+    )
+}
+
+pub async fn send_password_reset_mail(
+    mailer: SmtpTransport,
+    origin: String,
+    email: String,
+    reset_url: String,
+) {
+    send_mail(
+        mailer,
+        origin,
+        email,
+        "Password reset",
+        format!(
+            "A password reset has been requested for your account.\n{}",
+            reset_url
+        ),
+    )
+}
+
+fn send_mail(
+    mut mailer: SmtpTransport,
+    origin: String,
+    email: String,
+    subject: &str,
+    text: String,
 ) {
     EmailBuilder::new()
         .to(email)
         .from(origin)
-        .subject("Activation code")
-        .text(format!("Here is your activation code!\n{}", activation_url))
+        .subject(subject)
+        .text(text)
         .build()
         .map(Some)
         .unwrap_or_else(|err| {
-            println!("Could not build activation email: {:?}", err);
+            println!("Could not build {} email: {:?}", subject, err);
             None
         })
         .map(|email| mailer.send(email.into()))
         .and_then(|result| result.err())
         .and_then(|err| {
-            println!("Could not send activation email: {:?}", err);
+            println!("Could not send {} email: {:?}", subject, err);
             Some(())
         });
 }
@@ -29,3 +67,38 @@ pub async fn send_activation_mail(
 pub fn get_activation_url(base_url: &String, activation_code: &String) -> String {
     base_url.to_owned() + "/activate?code=" + activation_code.as_str()
 }
+
+pub fn get_password_reset_url(base_url: &String, reset_code: &String) -> String {
+    base_url.to_owned() + "/reset-password?code=" + reset_code.as_str()
+}
+
+/// Reads `client_id:client_secret` from an HTTP Basic `Authorization` header.
+pub fn get_basic_credentials(req: &HttpRequest) -> Option<(String, String)> {
+    let header = req.headers().get("Authorization")?.to_str().ok()?;
+    if !header.starts_with("Basic ") {
+        return None;
+    }
+
+    let decoded = base64::decode(header["Basic ".len()..].trim()).ok()?;
+    let decoded = String::from_utf8(decoded).ok()?;
+    let mut credentials = decoded.splitn(2, ':');
+
+    Some((
+        credentials.next()?.to_owned(),
+        credentials.next()?.to_owned(),
+    ))
+}
+
+pub fn redirect_with_auth_code(
+    redirect_uri: &String,
+    auth_code: AuthCode,
+) -> actix_web::Result<HttpResponse> {
+    let redirect_uri = match Url::parse_with_params(redirect_uri, &[("auth_code", auth_code)]) {
+        Ok(url) => Ok(url.into_string()),
+        Err(e) => Err(error::ErrorBadRequest(e)),
+    }?;
+
+    Ok(HttpResponse::Found()
+        .header(http::header::LOCATION, redirect_uri)
+        .finish())
+}
@@ -0,0 +1,140 @@
+use std::net::{IpAddr, SocketAddr};
+use std::rc::Rc;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+use actix_web::{http, HttpRequest};
+use maxminddb::{geoip2, Reader};
+use sha2::{Digest, Sha256};
+
+use crate::app_data::AppData;
+use crate::config::{Config, UrlConfig};
+use crate::database::establish_connection;
+use crate::database::handler::url_click::{NewUrlClick, UrlClickHandler, UrlClickPostgresHandler};
+
+/// What a redirect knows about its visitor, captured before the response is sent
+pub struct Click {
+    pub url_id: i32,
+    pub referrer: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip: Option<IpAddr>,
+    /// The redirect rule that picked the target, if any
+    pub rule_id: Option<i32>,
+}
+
+impl Click {
+    pub fn from_request(config: &UrlConfig, url_id: i32, req: &HttpRequest) -> Click {
+        let header = |name: http::header::HeaderName| {
+            req.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_owned())
+        };
+
+        Click {
+            url_id,
+            referrer: header(http::header::REFERER),
+            user_agent: header(http::header::USER_AGENT),
+            ip: visitor_ip(config, req),
+            rule_id: None,
+        }
+    }
+}
+
+/// The socket peer, unless it is a trusted proxy: then the right-most `X-Forwarded-For` entry
+/// that isn't one of the proxies. Anyone else can put whatever they like in that header.
+pub fn visitor_ip(config: &UrlConfig, req: &HttpRequest) -> Option<IpAddr> {
+    let peer = req.peer_addr()?.ip();
+    let proxies = match &config.trusted_proxies {
+        Some(proxies) if proxies.contains(&peer) => proxies,
+        _ => return Some(peer),
+    };
+
+    let forwarded = req
+        .headers()
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let client = forwarded
+        .rsplit(',')
+        .filter_map(|entry| parse_ip(entry.trim()))
+        .find(|ip| !proxies.contains(ip));
+
+    client.or(Some(peer))
+}
+
+/// `remote` is either a bare address or a socket address
+fn parse_ip(remote: &str) -> Option<IpAddr> {
+    remote
+        .parse::<IpAddr>()
+        .ok()
+        .or_else(|| remote.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+}
+
+fn hash_ip(salt: &String, ip: Option<IpAddr>) -> String {
+    let ip = ip.map(|ip| ip.to_string()).unwrap_or_default();
+    format!("{:x}", Sha256::digest((salt.to_owned() + &ip).as_bytes()))
+}
+
+/// The salted hash clicks are stored with, for telling visitors apart without keeping their IP
+pub fn visitor_hash(data: &AppData, req: &HttpRequest) -> String {
+    let ip = visitor_ip(&data.config.url, req);
+    hash_ip(&data.config.url.ip_hash_salt, ip)
+}
+
+pub fn lookup_country(data: &AppData, ip: Option<IpAddr>) -> Option<String> {
+    find_country(data.geoip.as_ref()?, ip?)
+}
+
+fn find_country(reader: &Reader<Vec<u8>>, ip: IpAddr) -> Option<String> {
+    let country: geoip2::Country = reader.lookup(ip).ok()?;
+    country.country?.iso_code
+}
+
+/// Hands the click to the writer thread, the redirect doesn't wait for it
+pub fn record_click(data: &AppData, click: Click) {
+    let url_id = click.url_id;
+    if data.click_sender.send(click).is_err() {
+        println!("Could not record click on {}: click writer is gone", url_id);
+    }
+}
+
+/// Starts the thread that writes clicks with its own connection, so neither the GeoIP lookup
+/// nor the insert runs on a worker. It stops once every sender is dropped.
+pub fn spawn_click_writer(config: &Config) -> Sender<Click> {
+    let (sender, receiver) = mpsc::channel::<Click>();
+    let config = config.clone();
+
+    thread::spawn(move || {
+        let url_click_handler =
+            UrlClickPostgresHandler::new(Rc::new(establish_connection(&config)));
+        let geoip = config
+            .url
+            .geoip_database_path
+            .as_ref()
+            .map(|path| Reader::open_readfile(path).expect("Invalid GeoIP database"));
+
+        for click in receiver {
+            let country = match (&geoip, click.ip) {
+                (Some(reader), Some(ip)) => find_country(reader, ip),
+                _ => None,
+            };
+            let ip_hash = hash_ip(&config.url.ip_hash_salt, click.ip);
+
+            let result = url_click_handler.insert(&NewUrlClick {
+                url_id: click.url_id,
+                referrer: click.referrer.as_ref(),
+                user_agent: click.user_agent.as_ref(),
+                country: country.as_ref(),
+                ip_hash: &ip_hash,
+                rule_id: click.rule_id,
+            });
+
+            if let Err(e) = result {
+                println!("Could not record click on {}: {}", click.url_id, e);
+            }
+        }
+    });
+
+    sender
+}
@@ -0,0 +1,107 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::time::{Duration, Instant, SystemTime};
+
+/// How long a lookup trusts the loaded list before checking the file again
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Domains that links may not point at, read from a file with one domain per line. Blank lines
+/// and lines starting with `#` are skipped.
+///
+/// The file's modification time is checked at most every `CHECK_INTERVAL` and the list is
+/// re-read when it changed, so operators can edit it without restarting the workers.
+pub struct DomainBlocklist {
+    path: String,
+    state: RefCell<BlocklistState>,
+}
+
+struct BlocklistState {
+    modified: Option<SystemTime>,
+    domains: HashSet<String>,
+    checked_at: Option<Instant>,
+    /// The last read failure, so it is only logged when it starts or changes
+    error: Option<String>,
+}
+
+impl DomainBlocklist {
+    pub fn new(path: String) -> DomainBlocklist {
+        let blocklist = DomainBlocklist {
+            path,
+            state: RefCell::new(BlocklistState {
+                modified: None,
+                domains: HashSet::new(),
+                checked_at: None,
+                error: None,
+            }),
+        };
+        blocklist.reload_if_changed();
+        blocklist
+    }
+
+    /// Whether `host` or any of its parent domains is listed.
+    pub fn is_blocked(&self, host: &str) -> bool {
+        self.reload_if_changed();
+
+        let host = host.trim_end_matches('.').to_lowercase();
+        let state = self.state.borrow();
+        let mut domain = host.as_str();
+        loop {
+            if state.domains.contains(domain) {
+                return true;
+            }
+            match domain.find('.') {
+                Some(index) => domain = &domain[index + 1..],
+                None => return false,
+            }
+        }
+    }
+
+    fn reload_if_changed(&self) {
+        let now = Instant::now();
+        match self.state.borrow().checked_at {
+            Some(checked_at) if now.duration_since(checked_at) < CHECK_INTERVAL => return,
+            _ => (),
+        }
+
+        // Keep serving the previous list if the new one can't be read
+        let result = self.read_if_changed();
+
+        let mut state = self.state.borrow_mut();
+        state.checked_at = Some(now);
+        match result {
+            Ok(()) => {
+                if state.error.take().is_some() {
+                    println!("Domain blocklist {} can be read again", self.path);
+                }
+            }
+            Err(e) => {
+                let e = e.to_string();
+                if state.error.as_ref() != Some(&e) {
+                    println!("Can't read domain blocklist {}: {}", self.path, e);
+                    state.error = Some(e);
+                }
+            }
+        }
+    }
+
+    fn read_if_changed(&self) -> io::Result<()> {
+        let modified = fs::metadata(&self.path)?.modified()?;
+        if self.state.borrow().modified == Some(modified) {
+            return Ok(());
+        }
+
+        let domains = fs::read_to_string(&self.path)?
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| line.trim_end_matches('.').to_lowercase())
+            .collect();
+
+        let mut state = self.state.borrow_mut();
+        state.modified = Some(modified);
+        state.domains = domains;
+        Ok(())
+    }
+}
@@ -0,0 +1,291 @@
+use actix_web::web::{Data, Payload};
+use actix_web::{http, HttpRequest, HttpResponse, Result};
+use chrono::NaiveDateTime;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+
+use crate::app_data::AppData;
+use crate::auth::model::SCOPE_URL_WRITE;
+use crate::core::authenticated_user::AuthenticatedUser;
+use crate::core::url_shortener::error::UrlShortenerError;
+use crate::core::url_shortener::export::CSV_TAG_SEPARATOR;
+use crate::core::url_shortener::utils::{
+    generate_url_key, is_valid_url_key, normalize_tags, validate_limits, validate_target,
+    MAX_KEY_ATTEMPTS,
+};
+use crate::database::handler::url::{NewUrl, DEFAULT_REDIRECT_TYPE, REDIRECT_TYPES};
+use crate::database::handler::DbError;
+
+const MAX_IMPORT_BYTES: usize = 4 * 1024 * 1024;
+const MAX_IMPORT_ROWS: usize = 5000;
+
+const STATUS_CREATED: &str = "created";
+const STATUS_DUPLICATE: &str = "duplicate";
+const STATUS_INVALID: &str = "invalid";
+
+/// One link to import, the same fields `create::handle` accepts except the password. CSV
+/// imports use these as column names.
+#[derive(Deserialize)]
+pub struct ImportRow {
+    key: Option<String>,
+    target: String,
+    organization_id: Option<i32>,
+    domain_id: Option<i32>,
+    expires_at: Option<NaiveDateTime>,
+    activates_at: Option<NaiveDateTime>,
+    max_clicks: Option<i32>,
+    redirect_type: Option<i32>,
+    always_preview: Option<bool>,
+    description: Option<String>,
+    /// A JSON array, or in CSV one column with the tags separated like exports write them
+    tags: Option<Vec<String>>,
+}
+
+#[derive(Serialize)]
+pub struct ImportResult {
+    /// Position of the row in the request, starting at 0
+    row: usize,
+    key: String,
+    status: &'static str,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ImportResponse {
+    created: usize,
+    results: Vec<ImportResult>,
+}
+
+/// Creates many links in one transaction. The body is a JSON array of rows, or CSV with a
+/// header line when sent as `text/csv`. Rows that fail validation or hit an existing key are
+/// reported and skipped, the rest are created. Generated keys that collide are drawn again in
+/// a follow-up transaction, like `create::handle` does.
+pub async fn handle_import(
+    mut body: Payload,
+    data: Data<AppData>,
+    user: AuthenticatedUser,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    user.require_scope(SCOPE_URL_WRITE)?;
+
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk?;
+        if bytes.len() + chunk.len() > MAX_IMPORT_BYTES {
+            return Err(
+                UrlShortenerError::InvalidImport("Import can be at most 4MB".to_owned()).into(),
+            );
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+
+    let rows = if is_csv(&req) {
+        parse_csv(&bytes)?
+    } else {
+        serde_json::from_slice::<Vec<ImportRow>>(&bytes)
+            .map_err(|e| UrlShortenerError::InvalidImport(e.to_string()))?
+    };
+    if rows.len() > MAX_IMPORT_ROWS {
+        return Err(UrlShortenerError::InvalidImport(format!(
+            "Import can have at most {} rows",
+            MAX_IMPORT_ROWS
+        ))
+        .into());
+    }
+
+    let config = &data.config.url;
+    let mut keys: Vec<String> = rows
+        .iter()
+        .map(|row| match &row.key {
+            Some(key) => key.to_owned(),
+            None => generate_url_key(&config.key_alphabet, config.key_length),
+        })
+        .collect();
+
+    let mut results = Vec::with_capacity(rows.len());
+    let mut pending_rows = Vec::new();
+    for (index, (row, key)) in rows.iter().zip(keys.iter()).enumerate() {
+        match validate_row(&data, user.username(), row, key)? {
+            Some(error) => results.push(ImportResult {
+                row: index,
+                key: key.to_owned(),
+                status: STATUS_INVALID,
+                error: Some(error),
+            }),
+            None => pending_rows.push(index),
+        }
+    }
+
+    let mut created = 0;
+    let mut attempts = 0;
+    while !pending_rows.is_empty() {
+        attempts += 1;
+        let inserted = {
+            let new_urls: Vec<NewUrl> = pending_rows
+                .iter()
+                .map(|&index| new_url(&rows[index], &keys[index], user.username()))
+                .collect();
+            data.url_handler.insert_many(&new_urls)?
+        };
+
+        let mut colliding_rows = Vec::new();
+        for (index, result) in pending_rows.into_iter().zip(inserted.into_iter()) {
+            let (status, error) = match result {
+                Ok(url) => {
+                    if let Some(tags) = &rows[index].tags {
+                        data.url_handler.set_tags(url.id, &normalize_tags(tags)?)?;
+                    }
+                    created += 1;
+                    (STATUS_CREATED, None)
+                }
+                Err(DbError::DuplicateKey) if rows[index].key.is_some() => (STATUS_DUPLICATE, None),
+                Err(DbError::DuplicateKey) if attempts < MAX_KEY_ATTEMPTS => {
+                    colliding_rows.push(index);
+                    continue;
+                }
+                Err(DbError::DuplicateKey) => (
+                    STATUS_INVALID,
+                    Some(UrlShortenerError::KeyGenerationFailed.to_string()),
+                ),
+                Err(e) => (STATUS_INVALID, Some(e.to_string())),
+            };
+            results.push(ImportResult {
+                row: index,
+                key: keys[index].to_owned(),
+                status,
+                error,
+            });
+        }
+
+        for &index in &colliding_rows {
+            keys[index] = generate_url_key(&config.key_alphabet, config.key_length);
+        }
+        pending_rows = colliding_rows;
+    }
+    results.sort_by_key(|result| result.row);
+
+    Ok(HttpResponse::Ok().json(ImportResponse { created, results }))
+}
+
+fn new_url<'a>(row: &'a ImportRow, key: &'a String, username: &'a String) -> NewUrl<'a> {
+    NewUrl {
+        key,
+        target: &row.target,
+        username,
+        organization_id: row.organization_id,
+        expires_at: row.expires_at.as_ref(),
+        activates_at: row.activates_at.as_ref(),
+        max_clicks: row.max_clicks,
+        redirect_type: row.redirect_type.unwrap_or(DEFAULT_REDIRECT_TYPE),
+        always_preview: row.always_preview.unwrap_or(false),
+        password_hash: None,
+        description: row.description.as_ref(),
+        domain_id: row.domain_id,
+    }
+}
+
+fn is_csv(req: &HttpRequest) -> bool {
+    req.headers()
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.starts_with("text/csv"))
+        .unwrap_or(false)
+}
+
+/// The tags column is split by hand, the other columns map onto `ImportRow` by name.
+fn parse_csv(bytes: &[u8]) -> Result<Vec<ImportRow>, UrlShortenerError> {
+    let invalid = |e: csv::Error| UrlShortenerError::InvalidImport(e.to_string());
+    let mut reader = csv::Reader::from_reader(bytes);
+    let headers = reader.headers().map_err(invalid)?.clone();
+    let tags_column = headers.iter().position(|header| header == "tags");
+    let without_tags = |record: &csv::StringRecord| -> csv::StringRecord {
+        record
+            .iter()
+            .enumerate()
+            .filter(|&(column, _)| Some(column) != tags_column)
+            .map(|(_, field)| field)
+            .collect()
+    };
+    let field_headers = without_tags(&headers);
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(invalid)?;
+        let mut row: ImportRow = without_tags(&record)
+            .deserialize(Some(&field_headers))
+            .map_err(invalid)?;
+        row.tags = tags_column
+            .and_then(|column| record.get(column))
+            .filter(|tags| !tags.is_empty())
+            .map(|tags| tags.split(CSV_TAG_SEPARATOR).map(String::from).collect());
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// The reason a row can't be imported, if any. Only database failures are returned as errors.
+fn validate_row(
+    data: &AppData,
+    username: &String,
+    row: &ImportRow,
+    key: &String,
+) -> Result<Option<String>> {
+    if !is_valid_url_key(key) {
+        return Ok(Some(
+            "Key can only have alphanumeric and \"_-.\"".to_owned(),
+        ));
+    }
+
+    let redirect_type = row.redirect_type.unwrap_or(DEFAULT_REDIRECT_TYPE);
+    let validation = validate_target(data, &row.target)
+        .and_then(|_| {
+            validate_limits(
+                row.expires_at.as_ref(),
+                row.activates_at.as_ref(),
+                row.max_clicks,
+            )
+        })
+        .and_then(|_| {
+            if REDIRECT_TYPES.contains(&redirect_type) {
+                Ok(())
+            } else {
+                Err(UrlShortenerError::InvalidRedirectType)
+            }
+        })
+        .and_then(|_| match &row.tags {
+            Some(tags) => normalize_tags(tags).map(|_| ()),
+            None => Ok(()),
+        });
+    if let Err(e) = validation {
+        return Ok(Some(e.to_string()));
+    }
+
+    if let Some(organization_id) = row.organization_id {
+        match data
+            .organization_handler
+            .get_member(organization_id, username)
+        {
+            Ok(member) if member.can_write() => {}
+            Ok(_) => {
+                return Ok(Some(
+                    UrlShortenerError::InsufficientOrganizationRole.to_string(),
+                ))
+            }
+            Err(DbError::NotFound) => return Ok(Some(UrlShortenerError::NotAMember.to_string())),
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    if let Some(domain_id) = row.domain_id {
+        match data
+            .domain_handler
+            .get_by_id_and_username(domain_id, username)
+        {
+            Ok(_) => {}
+            Err(DbError::NotFound) => return Ok(Some("Unknown domain".to_owned())),
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(None)
+}
@@ -1,28 +1,132 @@
 use actix_web::web::{Data, Json};
-use actix_web::{HttpRequest, HttpResponse, Result};
-use serde::Deserialize;
+use actix_web::{HttpResponse, Result};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
 
 use crate::app_data::AppData;
-use crate::core::url_shortener::utils::{authenticate, is_valid_url_key};
+use crate::auth::model::SCOPE_URL_WRITE;
+use crate::core::authenticated_user::AuthenticatedUser;
+use crate::core::url_shortener::error::UrlShortenerError;
+use crate::core::url_shortener::unlock::hash_password;
+use crate::core::url_shortener::utils::{
+    generate_url_key, get_membership, get_short_url, is_valid_url_key, normalize_tags,
+    validate_limits, validate_target, MAX_KEY_ATTEMPTS,
+};
+use crate::database::handler::url::{NewUrl, TaggedUrl, DEFAULT_REDIRECT_TYPE, REDIRECT_TYPES};
+use crate::database::handler::DbError;
 
 #[derive(Deserialize)]
 pub struct CreateUrlRequest {
-    key: String,
+    key: Option<String>,
     target: String,
+    organization_id: Option<i32>,
+    domain_id: Option<i32>,
+    expires_at: Option<NaiveDateTime>,
+    activates_at: Option<NaiveDateTime>,
+    max_clicks: Option<i32>,
+    redirect_type: Option<i32>,
+    always_preview: Option<bool>,
+    password: Option<String>,
+    description: Option<String>,
+    tags: Option<Vec<String>>,
+}
+
+#[derive(Serialize)]
+pub struct CreateUrlResponse {
+    url: TaggedUrl,
+    short_url: String,
 }
 
 pub async fn handle(
     request: Json<CreateUrlRequest>,
     data: Data<AppData>,
-    req: HttpRequest,
+    user: AuthenticatedUser,
 ) -> Result<HttpResponse> {
-    let token = authenticate(&data, &req)?;
+    user.require_scope(SCOPE_URL_WRITE)?;
+
+    if let Some(key) = &request.key {
+        if !is_valid_url_key(key) {
+            return Ok(
+                HttpResponse::BadRequest().body("Key can only have alphanumeric and \"_-.\"")
+            );
+        }
+    }
+
+    validate_target(&data, &request.target)?;
+    validate_limits(
+        request.expires_at.as_ref(),
+        request.activates_at.as_ref(),
+        request.max_clicks,
+    )?;
+
+    let redirect_type = request.redirect_type.unwrap_or(DEFAULT_REDIRECT_TYPE);
+    if !REDIRECT_TYPES.contains(&redirect_type) {
+        return Err(UrlShortenerError::InvalidRedirectType.into());
+    }
 
-    if !is_valid_url_key(&request.key) {
-        return Ok(HttpResponse::BadRequest().body("Key can only have alphanumeric and \"_-.\""));
+    if let Some(organization_id) = request.organization_id {
+        if !get_membership(&data, organization_id, user.username())?.can_write() {
+            return Err(UrlShortenerError::InsufficientOrganizationRole.into());
+        }
     }
 
-    data.url_handler
-        .insert(&request.key, &request.target, &token.username)?;
-    Ok(HttpResponse::Ok().finish())
+    if let Some(domain_id) = request.domain_id {
+        data.domain_handler
+            .get_by_id_and_username(domain_id, user.username())?;
+    }
+
+    let tags = normalize_tags(
+        request
+            .tags
+            .as_ref()
+            .map(|tags| tags.as_slice())
+            .unwrap_or(&[]),
+    )?;
+
+    let password_hash = match &request.password {
+        Some(password) if !password.is_empty() => Some(hash_password(password)?),
+        _ => None,
+    };
+
+    let insert = |key: &String| {
+        data.url_handler.insert(&NewUrl {
+            key,
+            target: &request.target,
+            username: user.username(),
+            organization_id: request.organization_id,
+            expires_at: request.expires_at.as_ref(),
+            activates_at: request.activates_at.as_ref(),
+            max_clicks: request.max_clicks,
+            redirect_type,
+            always_preview: request.always_preview.unwrap_or(false),
+            password_hash: password_hash.as_ref(),
+            description: request.description.as_ref(),
+            domain_id: request.domain_id,
+        })
+    };
+
+    let url = match &request.key {
+        Some(key) => insert(key)?,
+        None => {
+            let config = &data.config.url;
+            let mut attempts = 0;
+            loop {
+                let key = generate_url_key(&config.key_alphabet, config.key_length);
+                match insert(&key) {
+                    Err(DbError::DuplicateKey) if attempts + 1 < MAX_KEY_ATTEMPTS => attempts += 1,
+                    Err(DbError::DuplicateKey) => {
+                        return Err(UrlShortenerError::KeyGenerationFailed.into())
+                    }
+                    result => break result?,
+                }
+            }
+        }
+    };
+
+    data.url_handler.set_tags(url.id, &tags)?;
+
+    Ok(HttpResponse::Created().json(CreateUrlResponse {
+        short_url: get_short_url(&data, &url)?,
+        url: TaggedUrl { url, tags },
+    }))
 }
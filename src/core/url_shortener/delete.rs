@@ -1,24 +1,26 @@
 use actix_web::web::{Data, Json};
-use actix_web::{HttpRequest, HttpResponse, Result};
+use actix_web::{HttpResponse, Result};
 use serde::Deserialize;
 
 use crate::app_data::AppData;
-use crate::core::url_shortener::utils::authenticate;
+use crate::auth::model::SCOPE_URL_WRITE;
+use crate::core::authenticated_user::AuthenticatedUser;
 
 #[derive(Deserialize)]
 pub struct DeleteUrlRequest {
+    domain_id: Option<i32>,
     key: String,
 }
 
 pub async fn handle(
     request: Json<DeleteUrlRequest>,
     data: Data<AppData>,
-    req: HttpRequest,
+    user: AuthenticatedUser,
 ) -> Result<HttpResponse> {
-    let token = authenticate(&data, &req)?;
+    user.require_scope(SCOPE_URL_WRITE)?;
 
     data.url_handler
-        .delete_at_least_one(&request.key, &token.username)?;
+        .delete_at_least_one(request.domain_id, &request.key, user.username())?;
 
     Ok(HttpResponse::Ok().finish())
 }
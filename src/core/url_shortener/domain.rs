@@ -0,0 +1,154 @@
+use actix_web::web::{Data, Json};
+use actix_web::{http, HttpRequest, HttpResponse, Result};
+use serde::Deserialize;
+
+use crate::app_data::AppData;
+use crate::auth::model::{SCOPE_URL_READ, SCOPE_URL_WRITE};
+use crate::core::authenticated_user::AuthenticatedUser;
+use crate::core::url_shortener::error::UrlShortenerError;
+use crate::core::url_shortener::utils::{
+    get_membership, normalize_hostname, request_hostname, restore_deadline, validate_target,
+};
+use crate::database::handler::domain::{Domain, NewDomain};
+use crate::database::handler::DbError;
+
+#[derive(Deserialize)]
+pub struct CreateDomainRequest {
+    hostname: String,
+    organization_id: Option<i32>,
+    not_found_url: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateDomainRequest {
+    not_found_url: Option<String>,
+}
+
+fn get_domain_id(req: &HttpRequest) -> Result<i32> {
+    req.match_info()
+        .get("domain_id")
+        .unwrap()
+        .parse::<i32>()
+        .map_err(actix_web::error::ErrorBadRequest)
+}
+
+pub async fn handle_list(data: Data<AppData>, user: AuthenticatedUser) -> Result<HttpResponse> {
+    user.require_scope(SCOPE_URL_READ)?;
+
+    let domains = data.domain_handler.get_by_username(user.username())?;
+
+    Ok(HttpResponse::Ok().json(domains))
+}
+
+/// Registers a hostname whose DNS already points at this server. Owned by the user, or by an
+/// organization where they may edit links. Its links are only served once an admin approved it.
+pub async fn handle_create(
+    request: Json<CreateDomainRequest>,
+    data: Data<AppData>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse> {
+    user.require_scope(SCOPE_URL_WRITE)?;
+
+    let hostname = normalize_hostname(&data.config, &request.hostname)?;
+    if let Some(not_found_url) = &request.not_found_url {
+        validate_target(&data, not_found_url)?;
+    }
+
+    if let Some(organization_id) = request.organization_id {
+        if !get_membership(&data, organization_id, user.username())?.can_write() {
+            return Err(UrlShortenerError::InsufficientOrganizationRole.into());
+        }
+    }
+
+    let domain = data.domain_handler.insert(&NewDomain {
+        hostname: &hostname,
+        username: user.username(),
+        organization_id: request.organization_id,
+        not_found_url: request.not_found_url.as_ref(),
+    })?;
+
+    Ok(HttpResponse::Created().json(domain))
+}
+
+pub async fn handle_update(
+    request: Json<UpdateDomainRequest>,
+    data: Data<AppData>,
+    user: AuthenticatedUser,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    user.require_scope(SCOPE_URL_WRITE)?;
+    let domain_id = get_domain_id(&req)?;
+
+    if let Some(not_found_url) = &request.not_found_url {
+        validate_target(&data, not_found_url)?;
+    }
+
+    let domain = data.domain_handler.set_not_found_url(
+        domain_id,
+        user.username(),
+        request.not_found_url.as_ref(),
+    )?;
+
+    Ok(HttpResponse::Ok().json(domain))
+}
+
+/// Only empty domains can be deleted, links still in their restore window count too
+pub async fn handle_delete(
+    data: Data<AppData>,
+    user: AuthenticatedUser,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    user.require_scope(SCOPE_URL_WRITE)?;
+    let domain_id = get_domain_id(&req)?;
+
+    let domain = data
+        .domain_handler
+        .get_by_id_and_username(domain_id, user.username())?;
+    if data
+        .url_handler
+        .count_by_domain(domain.id, &restore_deadline(&data))?
+        > 0
+    {
+        return Err(UrlShortenerError::DomainInUse.into());
+    }
+
+    data.domain_handler
+        .delete_at_least_one(domain.id, user.username())?;
+    data.domain_cache.invalidate();
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// The approved domain a request came in on
+pub fn request_domain(data: &AppData, req: &HttpRequest) -> Result<Option<Domain>> {
+    let hostname = match request_hostname(req.headers()) {
+        Some(hostname) => hostname,
+        None => return Ok(None),
+    };
+    match data.domain_handler.get_approved_by_hostname(&hostname) {
+        Ok(domain) => Ok(Some(domain)),
+        Err(DbError::NotFound) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Sends visitors to the domain's own fallback page when it has one
+pub fn not_found(data: &AppData, domain: Option<&Domain>) -> Result<HttpResponse> {
+    if let Some(not_found_url) = domain.and_then(|domain| domain.not_found_url.as_ref()) {
+        return Ok(HttpResponse::Found()
+            .header(http::header::LOCATION, not_found_url.as_str())
+            .header(http::header::CACHE_CONTROL, "no-store")
+            .finish());
+    }
+
+    let template = data
+        .templater
+        .url_unavailable_page(&"This link doesn't exist".to_owned())?;
+    Ok(HttpResponse::NotFound().body(template))
+}
+
+/// Anything on a custom domain that isn't a key, such as its root
+pub async fn handle_not_found(data: Data<AppData>, req: HttpRequest) -> Result<HttpResponse> {
+    let domain = request_domain(&data, &req)?;
+    not_found(&data, domain.as_ref())
+}
@@ -0,0 +1,59 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use crate::database::handler::domain::DomainHandler;
+
+/// How long the hostnames are trusted before they are loaded again. Approvals made on other
+/// workers and deletions show up within this.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The approved custom domains, kept in memory so routing a request doesn't need a query.
+pub struct DomainCache {
+    domain_handler: Rc<dyn DomainHandler>,
+    state: RefCell<DomainCacheState>,
+}
+
+struct DomainCacheState {
+    hostnames: HashSet<String>,
+    loaded_at: Option<Instant>,
+}
+
+impl DomainCache {
+    pub fn new(domain_handler: Rc<dyn DomainHandler>) -> DomainCache {
+        DomainCache {
+            domain_handler,
+            state: RefCell::new(DomainCacheState {
+                hostnames: HashSet::new(),
+                loaded_at: None,
+            }),
+        }
+    }
+
+    pub fn contains(&self, hostname: &str) -> bool {
+        self.reload_if_stale();
+        self.state.borrow().hostnames.contains(hostname)
+    }
+
+    /// Makes the next lookup load the hostnames again, after this worker changed them
+    pub fn invalidate(&self) {
+        self.state.borrow_mut().loaded_at = None;
+    }
+
+    fn reload_if_stale(&self) {
+        let now = Instant::now();
+        match self.state.borrow().loaded_at {
+            Some(loaded_at) if now.duration_since(loaded_at) < REFRESH_INTERVAL => return,
+            _ => (),
+        }
+
+        // Keep serving the previous hostnames if the database can't be reached
+        let mut state = self.state.borrow_mut();
+        state.loaded_at = Some(now);
+        match self.domain_handler.get_approved_hostnames() {
+            Ok(hostnames) => state.hostnames = hostnames.into_iter().collect(),
+            Err(e) => println!("Can't load custom domains: {}", e),
+        }
+    }
+}
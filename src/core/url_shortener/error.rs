@@ -0,0 +1,110 @@
+use std::fmt;
+
+use crate::database::handler::organization::ORGANIZATION_ROLES;
+use crate::database::handler::url::REDIRECT_TYPES;
+use crate::database::handler::url_rule::RULE_KINDS;
+
+#[derive(Debug)]
+pub enum UrlShortenerError {
+    NotAMember,
+    InsufficientOrganizationRole,
+    InvalidOrganizationRole,
+    LastOwner,
+    NotLinkOwner,
+    InvalidLimits(&'static str),
+    KeyGenerationFailed,
+    InvalidRedirectType,
+    InvalidTarget(&'static str),
+    BlockedTarget,
+    PasswordHashFailed,
+    InvalidQrParameter(&'static str),
+    QrGenerationFailed,
+    InvalidImport(String),
+    InvalidExportFormat,
+    ExportFailed,
+    InvalidSearch(&'static str),
+    InvalidTags(&'static str),
+    InvalidDomain(&'static str),
+    DomainInUse,
+    InvalidRuleKind,
+    InvalidRule(&'static str),
+}
+
+impl fmt::Display for UrlShortenerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UrlShortenerError::NotAMember => write!(f, "Not a member of this organization"),
+            UrlShortenerError::InsufficientOrganizationRole => {
+                write!(f, "Your organization role doesn't allow this")
+            }
+            UrlShortenerError::InvalidOrganizationRole => {
+                write!(f, "Role can only be one of {:?}", ORGANIZATION_ROLES)
+            }
+            UrlShortenerError::LastOwner => {
+                write!(f, "An organization needs at least one owner")
+            }
+            UrlShortenerError::NotLinkOwner => write!(
+                f,
+                "Only the owner of a personal link can move it to an organization"
+            ),
+            UrlShortenerError::InvalidLimits(e) => write!(f, "{}", e),
+            UrlShortenerError::KeyGenerationFailed => {
+                write!(f, "Couldn't generate a free key, please try again")
+            }
+            UrlShortenerError::InvalidRedirectType => {
+                write!(f, "Redirect type can only be one of {:?}", REDIRECT_TYPES)
+            }
+            UrlShortenerError::InvalidTarget(e) => write!(f, "{}", e),
+            UrlShortenerError::BlockedTarget => write!(f, "Target domain is not allowed"),
+            UrlShortenerError::PasswordHashFailed => {
+                write!(f, "Couldn't process the link password")
+            }
+            UrlShortenerError::InvalidQrParameter(e) => write!(f, "{}", e),
+            UrlShortenerError::QrGenerationFailed => write!(f, "Couldn't generate the QR code"),
+            UrlShortenerError::InvalidImport(e) => write!(f, "Invalid import: {}", e),
+            UrlShortenerError::InvalidExportFormat => {
+                write!(f, "Export format can only be csv or json")
+            }
+            UrlShortenerError::ExportFailed => write!(f, "Couldn't export links"),
+            UrlShortenerError::InvalidSearch(e) => write!(f, "{}", e),
+            UrlShortenerError::InvalidTags(e) => write!(f, "{}", e),
+            UrlShortenerError::InvalidDomain(e) => write!(f, "{}", e),
+            UrlShortenerError::DomainInUse => {
+                write!(f, "Delete the links on this domain first")
+            }
+            UrlShortenerError::InvalidRuleKind => {
+                write!(f, "Rule kind can only be one of {:?}", RULE_KINDS)
+            }
+            UrlShortenerError::InvalidRule(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<UrlShortenerError> for actix_web::Error {
+    fn from(e: UrlShortenerError) -> Self {
+        match e {
+            UrlShortenerError::NotAMember => actix_web::error::ErrorForbidden(e),
+            UrlShortenerError::InsufficientOrganizationRole => actix_web::error::ErrorForbidden(e),
+            UrlShortenerError::InvalidOrganizationRole => actix_web::error::ErrorBadRequest(e),
+            UrlShortenerError::LastOwner => actix_web::error::ErrorBadRequest(e),
+            UrlShortenerError::NotLinkOwner => actix_web::error::ErrorForbidden(e),
+            UrlShortenerError::InvalidLimits(_) => actix_web::error::ErrorBadRequest(e),
+            UrlShortenerError::KeyGenerationFailed => actix_web::error::ErrorServiceUnavailable(e),
+            UrlShortenerError::InvalidRedirectType => actix_web::error::ErrorBadRequest(e),
+            UrlShortenerError::InvalidTarget(_) => actix_web::error::ErrorBadRequest(e),
+            UrlShortenerError::BlockedTarget => actix_web::error::ErrorBadRequest(e),
+            UrlShortenerError::PasswordHashFailed => actix_web::error::ErrorInternalServerError(e),
+            UrlShortenerError::InvalidQrParameter(_) => actix_web::error::ErrorBadRequest(e),
+            UrlShortenerError::QrGenerationFailed => actix_web::error::ErrorInternalServerError(e),
+            UrlShortenerError::InvalidImport(_) => actix_web::error::ErrorBadRequest(e),
+            UrlShortenerError::InvalidExportFormat => actix_web::error::ErrorBadRequest(e),
+            UrlShortenerError::ExportFailed => actix_web::error::ErrorInternalServerError(e),
+            UrlShortenerError::InvalidSearch(_) => actix_web::error::ErrorBadRequest(e),
+            UrlShortenerError::InvalidTags(_) => actix_web::error::ErrorBadRequest(e),
+            UrlShortenerError::InvalidDomain(_) => actix_web::error::ErrorBadRequest(e),
+            UrlShortenerError::DomainInUse => actix_web::error::ErrorConflict(e),
+            UrlShortenerError::InvalidRuleKind => actix_web::error::ErrorBadRequest(e),
+            UrlShortenerError::InvalidRule(_) => actix_web::error::ErrorBadRequest(e),
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use actix_web::web::{Bytes, Data, Query};
+use actix_web::{http, HttpResponse, Result};
+use chrono::NaiveDateTime;
+use futures::stream;
+use serde::{Deserialize, Serialize};
+
+use crate::app_data::AppData;
+use crate::auth::model::SCOPE_URL_READ;
+use crate::core::authenticated_user::AuthenticatedUser;
+use crate::core::url_shortener::error::UrlShortenerError;
+use crate::core::url_shortener::utils::with_tags;
+use crate::database::handler::url::TaggedUrl;
+
+const EXPORT_BATCH_SIZE: i64 = 500;
+
+const FORMAT_CSV: &str = "csv";
+const FORMAT_JSON: &str = "json";
+/// `ExportRow`'s fields in order, written up front so an export without links still has them
+const CSV_COLUMNS: [&str; 14] = [
+    "key",
+    "target",
+    "organization_id",
+    "domain_id",
+    "expires_at",
+    "activates_at",
+    "max_clicks",
+    "redirect_type",
+    "always_preview",
+    "click_count",
+    "is_disabled",
+    "description",
+    "created_at",
+    "tags",
+];
+/// Tags share one CSV column, `bulk::handle_import` splits them again
+pub const CSV_TAG_SEPARATOR: &str = ",";
+
+#[derive(Deserialize)]
+pub struct ExportRequest {
+    format: Option<String>,
+    domain_id: Option<i32>,
+}
+
+/// A CSV line, with the columns `bulk::handle_import` reads first so exports can be
+/// imported again
+#[derive(Serialize)]
+struct ExportRow<'a> {
+    key: &'a String,
+    target: &'a String,
+    organization_id: Option<i32>,
+    domain_id: Option<i32>,
+    expires_at: Option<&'a NaiveDateTime>,
+    activates_at: Option<&'a NaiveDateTime>,
+    max_clicks: Option<i32>,
+    redirect_type: i32,
+    always_preview: bool,
+    click_count: i32,
+    is_disabled: bool,
+    description: Option<&'a String>,
+    created_at: &'a NaiveDateTime,
+    tags: String,
+}
+
+impl<'a> From<&'a TaggedUrl> for ExportRow<'a> {
+    fn from(tagged_url: &'a TaggedUrl) -> Self {
+        let url = &tagged_url.url;
+        ExportRow {
+            key: &url.key,
+            target: &url.target,
+            organization_id: url.organization_id,
+            domain_id: url.domain_id,
+            expires_at: url.expires_at.as_ref(),
+            activates_at: url.activates_at.as_ref(),
+            max_clicks: url.max_clicks,
+            redirect_type: url.redirect_type,
+            always_preview: url.always_preview,
+            click_count: url.click_count,
+            is_disabled: url.is_disabled,
+            description: url.description.as_ref(),
+            created_at: &url.created_at,
+            tags: tagged_url.tags.join(CSV_TAG_SEPARATOR),
+        }
+    }
+}
+
+enum ExportState {
+    Start,
+    After(String),
+    Done,
+}
+
+/// Every link on the domain the user can read, streamed in batches ordered by key instead of being
+/// collected into one response.
+pub async fn handle(
+    request: Query<ExportRequest>,
+    data: Data<AppData>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse> {
+    user.require_scope(SCOPE_URL_READ)?;
+
+    let format = match request.format.as_ref().map(|format| format.as_str()) {
+        None | Some(FORMAT_CSV) => FORMAT_CSV,
+        Some(FORMAT_JSON) => FORMAT_JSON,
+        Some(_) => return Err(UrlShortenerError::InvalidExportFormat.into()),
+    };
+    let username = user.username().to_owned();
+    let domain_id = request.domain_id;
+
+    let batches = stream::unfold(ExportState::Start, move |state| {
+        let data = data.clone();
+        let username = username.clone();
+        async move {
+            let after_key = match &state {
+                ExportState::Start => None,
+                ExportState::After(key) => Some(key),
+                ExportState::Done => return None,
+            };
+            let is_first = after_key.is_none();
+
+            let urls = match data.url_handler.get_by_username_after(
+                &username,
+                domain_id,
+                after_key,
+                EXPORT_BATCH_SIZE,
+            ) {
+                Ok(urls) => urls,
+                Err(e) => return Some((Err(actix_web::Error::from(e)), ExportState::Done)),
+            };
+
+            let next = match urls.last() {
+                Some(url) if urls.len() as i64 == EXPORT_BATCH_SIZE => {
+                    ExportState::After(url.key.to_owned())
+                }
+                _ => ExportState::Done,
+            };
+            let is_last = match next {
+                ExportState::Done => true,
+                _ => false,
+            };
+
+            let urls = match with_tags(&data, urls) {
+                Ok(urls) => urls,
+                Err(e) => return Some((Err(e), ExportState::Done)),
+            };
+
+            let chunk = if format == FORMAT_CSV {
+                encode_csv(&urls, is_first)
+            } else {
+                encode_json(&urls, is_first, is_last)
+            };
+            Some((chunk.map(Bytes::from), next))
+        }
+    });
+
+    let content_type = if format == FORMAT_CSV {
+        "text/csv"
+    } else {
+        "application/json"
+    };
+    Ok(HttpResponse::Ok()
+        .content_type(content_type)
+        .header(
+            http::header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"urls.{}\"", format),
+        )
+        .streaming(batches))
+}
+
+fn encode_csv(urls: &[TaggedUrl], with_header: bool) -> Result<Vec<u8>> {
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(Vec::new());
+    if with_header {
+        writer
+            .write_record(&CSV_COLUMNS)
+            .map_err(|_| UrlShortenerError::ExportFailed)?;
+    }
+    for url in urls {
+        writer
+            .serialize(ExportRow::from(url))
+            .map_err(|_| UrlShortenerError::ExportFailed)?;
+    }
+    Ok(writer
+        .into_inner()
+        .map_err(|_| UrlShortenerError::ExportFailed)?)
+}
+
+/// One JSON array split across batches, so the opening and closing brackets go with the
+/// first and last ones.
+fn encode_json(urls: &[TaggedUrl], is_first: bool, is_last: bool) -> Result<Vec<u8>> {
+    let mut chunk = Vec::new();
+    if is_first {
+        chunk.push(b'[');
+    }
+    for (index, url) in urls.iter().enumerate() {
+        if !is_first || index > 0 {
+            chunk.push(b',');
+        }
+        serde_json::to_writer(&mut chunk, url).map_err(|_| UrlShortenerError::ExportFailed)?;
+    }
+    if is_last {
+        chunk.push(b']');
+    }
+    Ok(chunk)
+}
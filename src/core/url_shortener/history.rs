@@ -0,0 +1,81 @@
+use actix_web::web::{Data, Json, Query};
+use actix_web::{HttpRequest, HttpResponse, Result};
+use serde::Deserialize;
+
+use crate::app_data::AppData;
+use crate::auth::model::{SCOPE_URL_READ, SCOPE_URL_WRITE};
+use crate::core::authenticated_user::AuthenticatedUser;
+use crate::core::url_shortener::utils::{restore_deadline, with_tags};
+
+#[derive(Deserialize)]
+pub struct HistoryRequest {
+    domain_id: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct RollbackRequest {
+    domain_id: Option<i32>,
+    revision_id: i32,
+}
+
+pub async fn handle_revisions(
+    request: Query<HistoryRequest>,
+    data: Data<AppData>,
+    user: AuthenticatedUser,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    user.require_scope(SCOPE_URL_READ)?;
+    let key = String::from(req.match_info().get("key").unwrap());
+
+    let url = data
+        .url_handler
+        .get_by_key_and_username(request.domain_id, &key, user.username())?;
+    let revisions = data.url_revision_handler.get_by_url_id(url.id)?;
+
+    Ok(HttpResponse::Ok().json(revisions))
+}
+
+/// Puts the link back the way it was at `revision_id`, which is itself recorded as a new
+/// revision so the rollback can be undone too.
+pub async fn handle_rollback(
+    request: Json<RollbackRequest>,
+    data: Data<AppData>,
+    user: AuthenticatedUser,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    user.require_scope(SCOPE_URL_WRITE)?;
+    let key = String::from(req.match_info().get("key").unwrap());
+
+    let url = data
+        .url_handler
+        .get_by_key_and_username(request.domain_id, &key, user.username())?;
+    let revision = data
+        .url_revision_handler
+        .get_by_id(url.id, request.revision_id)?;
+    let url = data
+        .url_handler
+        .rollback(url.id, user.username(), &revision)?;
+    let url = with_tags(&data, vec![url])?.remove(0);
+
+    Ok(HttpResponse::Ok().json(url))
+}
+
+pub async fn handle_restore(
+    request: Query<HistoryRequest>,
+    data: Data<AppData>,
+    user: AuthenticatedUser,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    user.require_scope(SCOPE_URL_WRITE)?;
+    let key = String::from(req.match_info().get("key").unwrap());
+
+    let url = data.url_handler.restore(
+        request.domain_id,
+        &key,
+        user.username(),
+        &restore_deadline(&data),
+    )?;
+    let url = with_tags(&data, vec![url])?.remove(0);
+
+    Ok(HttpResponse::Ok().json(url))
+}
@@ -1,23 +1,110 @@
 use actix_web::dev::HttpServiceFactory;
-use actix_web::web;
+use actix_web::{guard, web};
 
-mod utils;
+use crate::app_data::AppData;
+use crate::core::url_shortener::utils::{is_server_host, request_hostname};
 
+pub mod analytics;
+pub mod blocklist;
+pub mod domain_cache;
+pub mod utils;
+
+mod bulk;
 mod create;
 mod delete;
+mod domain;
+mod export;
+mod history;
+mod qr;
 mod read;
+mod rules;
+mod stats;
 mod update;
 
 mod exchange;
+mod organization;
 mod redirect_by_key;
+mod unlock;
+
+mod error;
 
 pub fn service(prefix: &str) -> impl HttpServiceFactory {
     web::scope(prefix)
         .route("/redirect/{key}", web::get().to(redirect_by_key::handle))
+        .route("/redirect/{key}", web::post().to(unlock::handle))
         .route("/exchange", web::post().to(exchange::handle))
         .route("/", web::post().to(create::handle))
         .route("/", web::get().to(read::handle))
         .route("/", web::delete().to(delete::handle))
         .route("/", web::patch().to(update::handle))
+        .route("/bulk", web::post().to(bulk::handle_import))
+        .route("/export", web::get().to(export::handle))
         .route("/spec/{key}", web::get().to(read::handle_one))
+        .route("/spec/{key}/stats", web::get().to(stats::handle))
+        .route("/spec/{key}/qr", web::get().to(qr::handle))
+        .route("/spec/{key}/rules", web::get().to(rules::handle_list))
+        .route("/spec/{key}/rules", web::put().to(rules::handle_set))
+        .route(
+            "/spec/{key}/revisions",
+            web::get().to(history::handle_revisions),
+        )
+        .route(
+            "/spec/{key}/rollback",
+            web::post().to(history::handle_rollback),
+        )
+        .route(
+            "/spec/{key}/restore",
+            web::post().to(history::handle_restore),
+        )
+        .route("/domains", web::get().to(domain::handle_list))
+        .route("/domains", web::post().to(domain::handle_create))
+        .route(
+            "/domains/{domain_id}",
+            web::patch().to(domain::handle_update),
+        )
+        .route(
+            "/domains/{domain_id}",
+            web::delete().to(domain::handle_delete),
+        )
+        .route("/organizations", web::get().to(organization::handle_list))
+        .route(
+            "/organizations",
+            web::post().to(organization::handle_create),
+        )
+        .route(
+            "/organizations/{organization_id}/urls",
+            web::get().to(organization::handle_urls),
+        )
+        .route(
+            "/organizations/{organization_id}/members",
+            web::get().to(organization::handle_members),
+        )
+        .route(
+            "/organizations/{organization_id}/members",
+            web::put().to(organization::handle_set_member),
+        )
+        .route(
+            "/organizations/{organization_id}/members",
+            web::delete().to(organization::handle_remove_member),
+        )
+}
+
+/// Serves links at the root of approved custom domains. Has to be registered before the other
+/// services, it only takes requests whose `Host` is one of those domains.
+pub fn domain_service(data: &AppData) -> impl HttpServiceFactory {
+    let config = data.config.clone();
+    let domain_cache = data.domain_cache.clone();
+
+    web::scope("")
+        .guard(guard::fn_guard(move |head| {
+            match request_hostname(&head.headers) {
+                Some(hostname) => {
+                    !is_server_host(&config, &hostname) && domain_cache.contains(&hostname)
+                }
+                None => false,
+            }
+        }))
+        .route("/{key}", web::get().to(redirect_by_key::handle_on_domain))
+        .route("/{key}", web::post().to(unlock::handle_on_domain))
+        .default_service(web::to(domain::handle_not_found))
 }
@@ -0,0 +1,180 @@
+use actix_web::web::{Data, Json, Query};
+use actix_web::{HttpRequest, HttpResponse, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::app_data::AppData;
+use crate::auth::model::{SCOPE_URL_READ, SCOPE_URL_WRITE};
+use crate::core::authenticated_user::AuthenticatedUser;
+use crate::core::url_shortener::error::UrlShortenerError;
+use crate::core::url_shortener::utils::{get_membership, is_valid_url_key, with_tags};
+use crate::database::handler::organization::{ORGANIZATION_ROLES, ORGANIZATION_ROLE_OWNER};
+use crate::database::handler::url::TaggedUrl;
+
+#[derive(Deserialize)]
+pub struct CreateOrganizationRequest {
+    slug: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+pub struct SetMemberRequest {
+    username: String,
+    role: String,
+}
+
+#[derive(Deserialize)]
+pub struct RemoveMemberRequest {
+    username: String,
+}
+
+#[derive(Deserialize)]
+pub struct GetOrganizationUrlRequest {
+    page: Option<i64>,
+    per_page: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct GetOrganizationUrlResponse {
+    urls: Vec<TaggedUrl>,
+    page: i64,
+    per_page: i64,
+    total: i64,
+}
+
+fn get_organization_id(req: &HttpRequest) -> Result<i32> {
+    req.match_info()
+        .get("organization_id")
+        .unwrap()
+        .parse::<i32>()
+        .map_err(actix_web::error::ErrorBadRequest)
+}
+
+pub async fn handle_list(data: Data<AppData>, user: AuthenticatedUser) -> Result<HttpResponse> {
+    user.require_scope(SCOPE_URL_READ)?;
+
+    let organizations = data.organization_handler.get_by_username(user.username())?;
+
+    Ok(HttpResponse::Ok().json(organizations))
+}
+
+pub async fn handle_create(
+    request: Json<CreateOrganizationRequest>,
+    data: Data<AppData>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse> {
+    user.require_scope(SCOPE_URL_WRITE)?;
+
+    if !is_valid_url_key(&request.slug) {
+        return Ok(HttpResponse::BadRequest().body("Slug can only have alphanumeric and \"_-.\""));
+    }
+
+    let organization =
+        data.organization_handler
+            .create(&request.slug, &request.name, user.username())?;
+
+    Ok(HttpResponse::Created().json(organization))
+}
+
+pub async fn handle_urls(
+    request: Query<GetOrganizationUrlRequest>,
+    data: Data<AppData>,
+    user: AuthenticatedUser,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    user.require_scope(SCOPE_URL_READ)?;
+    let organization_id = get_organization_id(&req)?;
+    get_membership(&data, organization_id, user.username())?;
+
+    let page = request.page.unwrap_or(0);
+    let per_page = request.per_page.unwrap_or(10);
+
+    let urls = data
+        .url_handler
+        .get_by_organization(organization_id, per_page * page, per_page)?;
+    let urls = with_tags(&data, urls)?;
+    let total = data.url_handler.count_by_organization(organization_id)?;
+
+    Ok(HttpResponse::Ok().json(GetOrganizationUrlResponse {
+        urls,
+        page,
+        per_page,
+        total,
+    }))
+}
+
+pub async fn handle_members(
+    data: Data<AppData>,
+    user: AuthenticatedUser,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    user.require_scope(SCOPE_URL_READ)?;
+    let organization_id = get_organization_id(&req)?;
+    get_membership(&data, organization_id, user.username())?;
+
+    let members = data.organization_handler.get_members(organization_id)?;
+
+    Ok(HttpResponse::Ok().json(members))
+}
+
+pub async fn handle_set_member(
+    request: Json<SetMemberRequest>,
+    data: Data<AppData>,
+    user: AuthenticatedUser,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    user.require_scope(SCOPE_URL_WRITE)?;
+    let organization_id = get_organization_id(&req)?;
+    if !get_membership(&data, organization_id, user.username())?.is_owner() {
+        return Err(UrlShortenerError::InsufficientOrganizationRole.into());
+    }
+
+    if !ORGANIZATION_ROLES.contains(&request.role.as_str()) {
+        return Err(UrlShortenerError::InvalidOrganizationRole.into());
+    }
+
+    let member = data.user_handler.get_by_username(&request.username)?;
+
+    // Demoting the only owner would leave nobody able to manage the members
+    if request.role != ORGANIZATION_ROLE_OWNER {
+        if let Ok(current) = data
+            .organization_handler
+            .get_member(organization_id, &member.username)
+        {
+            if current.is_owner() && data.organization_handler.count_owners(organization_id)? <= 1 {
+                return Err(UrlShortenerError::LastOwner.into());
+            }
+        }
+    }
+
+    let member =
+        data.organization_handler
+            .set_member(organization_id, &member.username, &request.role)?;
+
+    Ok(HttpResponse::Ok().json(member))
+}
+
+pub async fn handle_remove_member(
+    request: Json<RemoveMemberRequest>,
+    data: Data<AppData>,
+    user: AuthenticatedUser,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    user.require_scope(SCOPE_URL_WRITE)?;
+    let organization_id = get_organization_id(&req)?;
+
+    // Members may always leave, only owners may remove others
+    let membership = get_membership(&data, organization_id, user.username())?;
+    if &request.username != user.username() && !membership.is_owner() {
+        return Err(UrlShortenerError::InsufficientOrganizationRole.into());
+    }
+
+    let removed = get_membership(&data, organization_id, &request.username)?;
+    if removed.is_owner() && data.organization_handler.count_owners(organization_id)? <= 1 {
+        return Err(UrlShortenerError::LastOwner.into());
+    }
+
+    data.organization_handler
+        .remove_member_at_least_one(organization_id, &removed.username)?;
+
+    Ok(HttpResponse::Ok().finish())
+}
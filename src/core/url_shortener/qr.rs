@@ -0,0 +1,225 @@
+use actix_web::web::{Data, Query};
+use actix_web::{http, HttpRequest, HttpResponse, Result};
+use image::imageops::FilterType;
+use image::{DynamicImage, GrayImage, ImageOutputFormat, Luma};
+use qrcode::{Color, EcLevel, QrCode};
+use serde::Deserialize;
+
+use crate::app_data::AppData;
+use crate::auth::model::SCOPE_URL_READ;
+use crate::core::authenticated_user::AuthenticatedUser;
+use crate::core::url_shortener::error::UrlShortenerError;
+use crate::core::url_shortener::utils::get_short_url;
+
+const DEFAULT_SIZE: u32 = 256;
+const MIN_SIZE: u32 = 64;
+const MAX_SIZE: u32 = 2048;
+/// The QR spec asks for a quiet zone of four modules
+const DEFAULT_MARGIN: u32 = 4;
+const MAX_MARGIN: u32 = 16;
+/// The logo covers a fifth of the code's width, which high error correction can recover from
+const LOGO_RATIO: u32 = 5;
+
+#[derive(Deserialize)]
+pub struct QrRequest {
+    domain_id: Option<i32>,
+    format: Option<String>,
+    size: Option<u32>,
+    margin: Option<u32>,
+    ec: Option<String>,
+    logo: Option<bool>,
+}
+
+/// The short link as a QR code. `size` is the edge of the image in pixels, rounded down so
+/// every module gets the same whole number of pixels, and `margin` is counted in modules.
+pub async fn handle(
+    request: Query<QrRequest>,
+    data: Data<AppData>,
+    user: AuthenticatedUser,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    user.require_scope(SCOPE_URL_READ)?;
+    let key = String::from(req.match_info().get("key").unwrap());
+
+    let url = data
+        .url_handler
+        .get_by_key_and_username(request.domain_id, &key, user.username())?;
+
+    let size = request.size.unwrap_or(DEFAULT_SIZE);
+    if size < MIN_SIZE || size > MAX_SIZE {
+        return Err(
+            UrlShortenerError::InvalidQrParameter("size has to be between 64 and 2048").into(),
+        );
+    }
+    let margin = request.margin.unwrap_or(DEFAULT_MARGIN);
+    if margin > MAX_MARGIN {
+        return Err(UrlShortenerError::InvalidQrParameter("margin can be at most 16").into());
+    }
+
+    let logo = if request.logo.unwrap_or(false) {
+        Some(
+            data.qr_logo
+                .as_ref()
+                .ok_or(UrlShortenerError::InvalidQrParameter(
+                    "No logo is configured",
+                ))?,
+        )
+    } else {
+        None
+    };
+
+    let ec_level = match request.ec.as_ref().map(|ec| ec.as_str()) {
+        Some("L") => EcLevel::L,
+        Some("M") => EcLevel::M,
+        Some("Q") => EcLevel::Q,
+        Some("H") => EcLevel::H,
+        None if logo.is_some() => EcLevel::H,
+        None => EcLevel::M,
+        Some(_) => {
+            return Err(
+                UrlShortenerError::InvalidQrParameter("ec can only be one of L, M, Q, H").into(),
+            )
+        }
+    };
+
+    let short_url = get_short_url(&data, &url)?;
+    let code = QrCode::with_error_correction_level(short_url.as_bytes(), ec_level)
+        .map_err(|_| UrlShortenerError::QrGenerationFailed)?;
+    let matrix = Matrix::new(&code, margin, size);
+
+    match request.format.as_ref().map(|format| format.as_str()) {
+        None | Some("png") => Ok(HttpResponse::Ok()
+            .content_type("image/png")
+            .header(http::header::CACHE_CONTROL, "no-cache")
+            .body(render_png(&matrix, logo)?)),
+        Some("svg") => Ok(HttpResponse::Ok()
+            .content_type("image/svg+xml")
+            .header(http::header::CACHE_CONTROL, "no-cache")
+            .body(render_svg(&matrix, logo)?)),
+        Some(_) => {
+            Err(UrlShortenerError::InvalidQrParameter("format can only be png or svg").into())
+        }
+    }
+}
+
+/// The code's modules laid out with the margin around them
+struct Matrix {
+    colors: Vec<Color>,
+    width: u32,
+    margin: u32,
+    module_size: u32,
+}
+
+impl Matrix {
+    fn new(code: &QrCode, margin: u32, size: u32) -> Matrix {
+        let width = code.width() as u32;
+        Matrix {
+            colors: code.to_colors(),
+            width,
+            margin,
+            module_size: (size / (width + 2 * margin)).max(1),
+        }
+    }
+
+    /// Edge in modules, margin included
+    fn modules(&self) -> u32 {
+        self.width + 2 * self.margin
+    }
+
+    fn pixels(&self) -> u32 {
+        self.modules() * self.module_size
+    }
+
+    fn is_dark(&self, x: u32, y: u32) -> bool {
+        if x < self.margin || y < self.margin {
+            return false;
+        }
+        let (x, y) = (x - self.margin, y - self.margin);
+        x < self.width
+            && y < self.width
+            && self.colors[(y * self.width + x) as usize] == Color::Dark
+    }
+
+    /// Position and edge of the logo area in modules, centered on the code
+    fn logo_area(&self) -> (u32, u32) {
+        let edge = (self.width / LOGO_RATIO).max(1);
+        (self.margin + (self.width - edge) / 2, edge)
+    }
+}
+
+fn render_png(matrix: &Matrix, logo: Option<&DynamicImage>) -> Result<Vec<u8>, UrlShortenerError> {
+    let pixels = matrix.pixels();
+    let code = GrayImage::from_fn(pixels, pixels, |x, y| {
+        if matrix.is_dark(x / matrix.module_size, y / matrix.module_size) {
+            Luma([0u8])
+        } else {
+            Luma([255u8])
+        }
+    });
+
+    let mut image = DynamicImage::ImageLuma8(code);
+    if let Some(logo) = logo {
+        let (start, edge) = matrix.logo_area();
+        let (start, edge) = (start * matrix.module_size, edge * matrix.module_size);
+
+        let mut canvas = image.to_rgba();
+        for x in start..start + edge {
+            for y in start..start + edge {
+                canvas.put_pixel(x, y, image::Rgba([255, 255, 255, 255]));
+            }
+        }
+        let logo = logo.resize(edge, edge, FilterType::Lanczos3).to_rgba();
+        let offset_x = start + (edge - logo.width()) / 2;
+        let offset_y = start + (edge - logo.height()) / 2;
+        image::imageops::overlay(&mut canvas, &logo, offset_x, offset_y);
+        image = DynamicImage::ImageRgba8(canvas);
+    }
+
+    encode_png(&image)
+}
+
+fn render_svg(matrix: &Matrix, logo: Option<&DynamicImage>) -> Result<String, UrlShortenerError> {
+    let modules = matrix.modules();
+    let mut path = String::new();
+    for y in 0..modules {
+        for x in 0..modules {
+            if matrix.is_dark(x, y) {
+                path.push_str(&format!("M{},{}h1v1h-1z", x, y));
+            }
+        }
+    }
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\" \
+         viewBox=\"0 0 {modules} {modules}\" shape-rendering=\"crispEdges\">\
+         <rect width=\"{modules}\" height=\"{modules}\" fill=\"#fff\"/>\
+         <path d=\"{path}\" fill=\"#000\"/>",
+        size = matrix.pixels(),
+        modules = modules,
+        path = path,
+    );
+
+    if let Some(logo) = logo {
+        let (start, edge) = matrix.logo_area();
+        let logo = encode_png(logo)?;
+        svg.push_str(&format!(
+            "<rect x=\"{start}\" y=\"{start}\" width=\"{edge}\" height=\"{edge}\" fill=\"#fff\"/>\
+             <image x=\"{start}\" y=\"{start}\" width=\"{edge}\" height=\"{edge}\" \
+             href=\"data:image/png;base64,{logo}\"/>",
+            start = start,
+            edge = edge,
+            logo = base64::encode(&logo),
+        ));
+    }
+
+    svg.push_str("</svg>");
+    Ok(svg)
+}
+
+fn encode_png(image: &DynamicImage) -> Result<Vec<u8>, UrlShortenerError> {
+    let mut bytes = Vec::new();
+    image
+        .write_to(&mut bytes, ImageOutputFormat::Png)
+        .map_err(|_| UrlShortenerError::QrGenerationFailed)?;
+    Ok(bytes)
+}
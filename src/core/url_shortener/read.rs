@@ -1,59 +1,171 @@
 use actix_web::web::{Data, Query};
 use actix_web::{HttpRequest, HttpResponse, Result};
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 
 use crate::app_data::AppData;
-use crate::core::url_shortener::utils::authenticate;
-use crate::database::handler::url::Url;
+use crate::auth::model::SCOPE_URL_READ;
+use crate::core::authenticated_user::AuthenticatedUser;
+use crate::core::url_shortener::error::UrlShortenerError;
+use crate::core::url_shortener::utils::with_tags;
+use crate::database::handler::url::{TaggedUrl, UrlCursor, UrlSearch, UrlSort, UrlStatus};
+
+const MAX_PER_PAGE: i64 = 100;
 
 #[derive(Deserialize)]
 pub struct GetUrlRequest {
+    /// Links on this domain, or on the server's own host when left out
+    domain_id: Option<i32>,
+    q: Option<String>,
+    status: Option<String>,
+    tag: Option<String>,
+    created_after: Option<NaiveDateTime>,
+    created_before: Option<NaiveDateTime>,
+    /// A field name, prefixed with `-` for descending order
+    sort: Option<String>,
+    cursor: Option<String>,
     page: Option<i64>,
     per_page: Option<i64>,
 }
 
+#[derive(Deserialize)]
+pub struct GetOneUrlRequest {
+    domain_id: Option<i32>,
+}
+
 #[derive(Serialize)]
 pub struct GetUrlResponse {
-    urls: Vec<Url>,
+    urls: Vec<TaggedUrl>,
     page: i64,
     per_page: i64,
     total: i64,
+    /// Pass as `cursor` to get the links after these, `None` on the last page
+    next_cursor: Option<String>,
 }
 
 pub async fn handle(
     request: Query<GetUrlRequest>,
     data: Data<AppData>,
-    req: HttpRequest,
+    user: AuthenticatedUser,
 ) -> Result<HttpResponse> {
-    let token = authenticate(&data, &req)?;
+    user.require_scope(SCOPE_URL_READ)?;
 
     let page = request.page.unwrap_or(0);
     let per_page = request.per_page.unwrap_or(10);
+    if per_page < 1 || per_page > MAX_PER_PAGE {
+        return Err(
+            UrlShortenerError::InvalidSearch("per_page has to be between 1 and 100").into(),
+        );
+    }
 
-    let offset = per_page * page;
+    let search = parse_search(&request)?;
+    let cursor = match &request.cursor {
+        Some(cursor) => Some(decode_cursor(cursor)?),
+        None => None,
+    };
+
+    // With a cursor the position is known already, `page` only applies without one
+    let offset = match cursor {
+        Some(_) => 0,
+        None => per_page * page,
+    };
     let limit = per_page;
 
     let urls = data
         .url_handler
-        .get_by_username(&token.username, offset, limit)?;
+        .search(user.username(), &search, cursor.as_ref(), offset, limit)?;
+
+    let total = data.url_handler.count_search(user.username(), &search)?;
 
-    let total = data.url_handler.count_by_username(&token.username)?;
+    let next_cursor = match urls.last() {
+        Some(url) if urls.len() as i64 == limit => Some(encode_cursor(&UrlCursor::from(url))),
+        _ => None,
+    };
+    let urls = with_tags(&data, urls)?;
 
     Ok(HttpResponse::Ok().json(GetUrlResponse {
         urls,
         page,
         per_page,
         total,
+        next_cursor,
     }))
 }
 
-pub async fn handle_one(data: Data<AppData>, req: HttpRequest) -> Result<HttpResponse> {
-    let token = authenticate(&data, &req)?;
+fn parse_search(request: &GetUrlRequest) -> Result<UrlSearch, UrlShortenerError> {
+    let status = match request.status.as_ref().map(|status| status.as_str()) {
+        None => None,
+        Some("active") => Some(UrlStatus::Active),
+        Some("scheduled") => Some(UrlStatus::Scheduled),
+        Some("expired") => Some(UrlStatus::Expired),
+        Some("disabled") => Some(UrlStatus::Disabled),
+        Some(_) => {
+            return Err(UrlShortenerError::InvalidSearch(
+                "status can only be one of active, scheduled, expired, disabled",
+            ))
+        }
+    };
+
+    let sort = request
+        .sort
+        .as_ref()
+        .map(|sort| sort.as_str())
+        .unwrap_or("key");
+    let is_descending = sort.starts_with('-');
+    let sort = match sort.trim_start_matches('-') {
+        "key" => UrlSort::Key,
+        "clicks" => UrlSort::Clicks,
+        "created" => UrlSort::Created,
+        _ => {
+            return Err(UrlShortenerError::InvalidSearch(
+                "sort can only be key, clicks or created",
+            ))
+        }
+    };
+
+    Ok(UrlSearch {
+        domain_id: request.domain_id,
+        query: request.q.as_ref().filter(|q| !q.is_empty()),
+        status,
+        tag: request
+            .tag
+            .as_ref()
+            .map(|tag| tag.trim().to_lowercase())
+            .filter(|tag| !tag.is_empty()),
+        created_after: request.created_after.as_ref(),
+        created_before: request.created_before.as_ref(),
+        sort,
+        is_descending,
+    })
+}
+
+fn encode_cursor(cursor: &UrlCursor) -> String {
+    base64::encode_config(
+        &serde_json::to_vec(cursor).unwrap_or_default(),
+        base64::URL_SAFE_NO_PAD,
+    )
+}
+
+fn decode_cursor(cursor: &String) -> Result<UrlCursor, UrlShortenerError> {
+    base64::decode_config(cursor, base64::URL_SAFE_NO_PAD)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<UrlCursor>(&bytes).ok())
+        .ok_or(UrlShortenerError::InvalidSearch("Invalid cursor"))
+}
+
+pub async fn handle_one(
+    request: Query<GetOneUrlRequest>,
+    data: Data<AppData>,
+    user: AuthenticatedUser,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    user.require_scope(SCOPE_URL_READ)?;
     let key = String::from(req.match_info().get("key").unwrap());
 
     let url = data
         .url_handler
-        .get_by_key_and_username(&key, &token.username)?;
+        .get_by_key_and_username(request.domain_id, &key, user.username())?;
+    let url = with_tags(&data, vec![url])?.remove(0);
 
     Ok(HttpResponse::Ok().json(url))
 }
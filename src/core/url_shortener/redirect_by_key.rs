@@ -1,14 +1,165 @@
-use actix_web::web::Data;
+use actix_web::http::StatusCode;
+use actix_web::web::{Data, Query};
 use actix_web::{http, HttpRequest, HttpResponse, Result};
+use chrono::Utc;
+use serde::Deserialize;
 
 use crate::app_data::AppData;
+use crate::core::url_shortener::analytics::{record_click, Click};
+use crate::core::url_shortener::domain::{not_found, request_domain};
+use crate::core::url_shortener::rules::pick_rule;
+use crate::core::url_shortener::unlock::{is_unlocked, password_prompt};
+use crate::core::url_shortener::utils::is_blocked_target;
+use crate::database::handler::domain::Domain;
+use crate::database::handler::url::Url;
+use crate::database::handler::DbError;
 
-pub async fn handle(req: HttpRequest, data: Data<AppData>) -> Result<HttpResponse> {
-    let name = String::from(req.match_info().get("key").unwrap());
+/// Permanent redirects get cached by browsers regardless, so let shared caches keep them for a
+/// day. Temporary ones must reach us on every visit to pick up target edits and count clicks.
+const PERMANENT_CACHE_CONTROL: &str = "public, max-age=86400";
+const TEMPORARY_CACHE_CONTROL: &str = "no-store";
+const GATED_CACHE_CONTROL: &str = "private, no-store";
 
-    let url_entry = data.url_handler.get_by_key(&name)?;
+/// Appending this to a key (`/redirect/{key}+`) shows the preview instead of redirecting.
+/// It can't collide with real keys, which never contain it.
+pub const PREVIEW_SUFFIX: char = '+';
 
-    Ok(HttpResponse::PermanentRedirect()
-        .header(http::header::LOCATION, url_entry.target)
+#[derive(Deserialize)]
+pub struct RedirectRequest {
+    preview: Option<String>,
+    confirmed: Option<String>,
+}
+
+pub async fn handle(
+    request: Query<RedirectRequest>,
+    req: HttpRequest,
+    data: Data<AppData>,
+) -> Result<HttpResponse> {
+    redirect(&request, &req, &data, None)
+}
+
+/// `https://{hostname}/{key}` on a custom domain
+pub async fn handle_on_domain(
+    request: Query<RedirectRequest>,
+    req: HttpRequest,
+    data: Data<AppData>,
+) -> Result<HttpResponse> {
+    match request_domain(&data, &req)? {
+        Some(domain) => redirect(&request, &req, &data, Some(&domain)),
+        None => not_found(&data, None),
+    }
+}
+
+fn redirect(
+    request: &RedirectRequest,
+    req: &HttpRequest,
+    data: &Data<AppData>,
+    domain: Option<&Domain>,
+) -> Result<HttpResponse> {
+    let name = req.match_info().get("key").unwrap();
+    let is_preview = request.preview.is_some() || name.ends_with(PREVIEW_SUFFIX);
+    let name = String::from(name.trim_end_matches(PREVIEW_SUFFIX));
+
+    let url_entry = match data
+        .url_handler
+        .get_by_key(domain.map(|domain| domain.id), &name)
+    {
+        Ok(url_entry) => url_entry,
+        Err(DbError::NotFound) if domain.is_some() => return not_found(data, domain),
+        Err(e) => return Err(e.into()),
+    };
+
+    if url_entry.is_disabled || is_blocked_target(data, &url_entry.target) {
+        return disabled(data);
+    }
+
+    let now = Utc::now().naive_utc();
+    if !url_entry.is_active(&now) {
+        let template = data
+            .templater
+            .url_unavailable_page(&"This link isn't active yet".to_owned())?;
+        return Ok(HttpResponse::NotFound().body(template));
+    }
+    if url_entry.is_expired(&now) {
+        return expired(data);
+    }
+
+    // The preview shows the target, so it is only reachable after unlocking as well
+    if !is_unlocked(data, req, &url_entry) {
+        return password_prompt(data, "This link is password protected");
+    }
+
+    if is_preview || (url_entry.always_preview && request.confirmed.is_none()) {
+        return preview(data, &url_entry, !is_preview);
+    }
+
+    let mut click = Click::from_request(&data.config.url, url_entry.id, req);
+    let rules = data.url_rule_handler.get_by_url_id(url_entry.id)?;
+    let rule = pick_rule(data, &rules, req, &click);
+    let target = match rule {
+        Some(rule) => &rule.target,
+        None => &url_entry.target,
+    };
+    if rule.is_some() && is_blocked_target(data, target) {
+        return disabled(data);
+    }
+
+    if !data.url_handler.register_click(url_entry.id)? {
+        return expired(data);
+    }
+
+    click.rule_id = rule.map(|rule| rule.id);
+    record_click(data, click);
+
+    let status = StatusCode::from_u16(url_entry.redirect_type as u16).unwrap_or(StatusCode::FOUND);
+    // A cached redirect would skip the password and limit checks, and where a link with rules
+    // leads depends on the visitor, so shared caches can keep neither
+    let cache_control = if url_entry.is_gated() {
+        GATED_CACHE_CONTROL
+    } else if url_entry.is_permanent_redirect() && rules.is_empty() {
+        PERMANENT_CACHE_CONTROL
+    } else {
+        TEMPORARY_CACHE_CONTROL
+    };
+
+    Ok(HttpResponse::build(status)
+        .header(http::header::LOCATION, target.as_str())
+        .header(http::header::CACHE_CONTROL, cache_control)
         .finish())
 }
+
+fn disabled(data: &Data<AppData>) -> Result<HttpResponse> {
+    let template = data
+        .templater
+        .url_unavailable_page(&"This link has been disabled".to_owned())?;
+    Ok(HttpResponse::Gone().body(template))
+}
+
+fn expired(data: &Data<AppData>) -> Result<HttpResponse> {
+    let template = data
+        .templater
+        .url_unavailable_page(&"This link has expired".to_owned())?;
+    Ok(HttpResponse::Gone().body(template))
+}
+
+/// Previews don't count as clicks, the visitor hasn't been sent anywhere yet
+fn preview(data: &Data<AppData>, url_entry: &Url, is_warning: bool) -> Result<HttpResponse> {
+    let owner = match url_entry.organization_id {
+        Some(organization_id) => data.organization_handler.get_by_id(organization_id)?.name,
+        None => url_entry.username.clone(),
+    };
+
+    let created_at = url_entry.created_at.format("%Y-%m-%d").to_string();
+
+    let template = data.templater.url_preview_page(
+        &url_entry.key,
+        &url_entry.target,
+        &owner,
+        &created_at,
+        is_warning,
+    )?;
+
+    Ok(HttpResponse::Ok()
+        .header(http::header::CACHE_CONTROL, TEMPORARY_CACHE_CONTROL)
+        .body(template))
+}
@@ -0,0 +1,419 @@
+use actix_web::web::{Data, Json, Query};
+use actix_web::{http, HttpRequest, HttpResponse, Result};
+use regex::Regex;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::app_data::AppData;
+use crate::auth::model::{SCOPE_URL_READ, SCOPE_URL_WRITE};
+use crate::core::authenticated_user::AuthenticatedUser;
+use crate::core::url_shortener::analytics::{lookup_country, visitor_hash, Click};
+use crate::core::url_shortener::error::UrlShortenerError;
+use crate::core::url_shortener::utils::validate_target;
+use crate::database::handler::url_rule::{
+    UrlRule, UrlRuleChanges, RULE_KIND_COUNTRY, RULE_KIND_DEVICE, RULE_KIND_LANGUAGE,
+    RULE_KIND_SPLIT,
+};
+
+const MAX_RULES: usize = 20;
+const DEVICES: [&str; 4] = ["ios", "android", "mobile", "desktop"];
+
+#[derive(Deserialize)]
+pub struct GetRulesRequest {
+    domain_id: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct SetRulesRequest {
+    domain_id: Option<i32>,
+    /// In the order they are tried
+    rules: Vec<RuleRequest>,
+}
+
+#[derive(Deserialize)]
+pub struct RuleRequest {
+    /// Set to keep an existing rule and its statistics
+    id: Option<i32>,
+    kind: String,
+    value: String,
+    target: String,
+}
+
+pub async fn handle_list(
+    request: Query<GetRulesRequest>,
+    data: Data<AppData>,
+    user: AuthenticatedUser,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    user.require_scope(SCOPE_URL_READ)?;
+    let key = String::from(req.match_info().get("key").unwrap());
+
+    let url = data
+        .url_handler
+        .get_by_key_and_username(request.domain_id, &key, user.username())?;
+    let rules = data.url_rule_handler.get_by_url_id(url.id)?;
+
+    Ok(HttpResponse::Ok().json(rules))
+}
+
+/// Replaces all rules of the link
+pub async fn handle_set(
+    request: Json<SetRulesRequest>,
+    data: Data<AppData>,
+    user: AuthenticatedUser,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    user.require_scope(SCOPE_URL_WRITE)?;
+    let key = String::from(req.match_info().get("key").unwrap());
+
+    if request.rules.len() > MAX_RULES {
+        return Err(UrlShortenerError::InvalidRule("A link can have at most 20 rules").into());
+    }
+
+    let mut values = Vec::with_capacity(request.rules.len());
+    let mut split_total = 0;
+    for rule in &request.rules {
+        let value = normalize_value(&rule.kind, &rule.value)?;
+        if rule.kind == RULE_KIND_SPLIT {
+            split_total += value.parse::<u32>().unwrap_or(0);
+        }
+        validate_target(&data, &rule.target)?;
+        values.push(value);
+    }
+    if split_total > 100 {
+        return Err(UrlShortenerError::InvalidRule("Split rules can add up to at most 100").into());
+    }
+
+    let url = data
+        .url_handler
+        .get_writable_by_key(request.domain_id, &key, user.username())?;
+    let changes: Vec<UrlRuleChanges> = request
+        .rules
+        .iter()
+        .zip(values.iter())
+        .enumerate()
+        .map(|(position, (rule, value))| UrlRuleChanges {
+            id: rule.id,
+            position: position as i32,
+            kind: &rule.kind,
+            value,
+            target: &rule.target,
+        })
+        .collect();
+    let rules = data.url_rule_handler.set_rules(url.id, &changes)?;
+
+    Ok(HttpResponse::Ok().json(rules))
+}
+
+/// Comma separated values in the form matching compares them in
+fn normalize_value(kind: &String, value: &String) -> Result<String, UrlShortenerError> {
+    lazy_static! {
+        static ref LANGUAGE_RE: Regex = Regex::new("^[a-z]{2,3}(-[a-z0-9]{2,8})*$").unwrap();
+        static ref COUNTRY_RE: Regex = Regex::new("^[A-Z]{2}$").unwrap();
+    }
+
+    let list = |normalize: fn(&str) -> String| -> Vec<String> {
+        value
+            .split(',')
+            .map(|item| normalize(item.trim()))
+            .filter(|item| !item.is_empty())
+            .collect()
+    };
+
+    let values = match kind.as_str() {
+        RULE_KIND_DEVICE => {
+            let devices = list(|item| item.to_lowercase());
+            if !devices
+                .iter()
+                .all(|device| DEVICES.contains(&device.as_str()))
+            {
+                return Err(UrlShortenerError::InvalidRule(
+                    "Devices can only be ios, android, mobile or desktop",
+                ));
+            }
+            devices
+        }
+        RULE_KIND_LANGUAGE => {
+            let languages = list(|item| item.to_lowercase());
+            if !languages
+                .iter()
+                .all(|language| LANGUAGE_RE.is_match(language))
+            {
+                return Err(UrlShortenerError::InvalidRule(
+                    "Languages have to be tags such as en or pt-BR",
+                ));
+            }
+            languages
+        }
+        RULE_KIND_COUNTRY => {
+            let countries = list(|item| item.to_uppercase());
+            if !countries.iter().all(|country| COUNTRY_RE.is_match(country)) {
+                return Err(UrlShortenerError::InvalidRule(
+                    "Countries have to be two letter ISO codes",
+                ));
+            }
+            countries
+        }
+        RULE_KIND_SPLIT => match value.trim().parse::<u32>() {
+            Ok(weight) if weight >= 1 && weight <= 100 => vec![weight.to_string()],
+            _ => {
+                return Err(UrlShortenerError::InvalidRule(
+                    "Split has to be a percentage between 1 and 100",
+                ))
+            }
+        },
+        _ => return Err(UrlShortenerError::InvalidRuleKind),
+    };
+
+    if values.is_empty() {
+        return Err(UrlShortenerError::InvalidRule("Rule value can't be empty"));
+    }
+    Ok(values.join(","))
+}
+
+/// The first of `rules` the visitor matches. Split rules share one roll per visitor, each taking
+/// the next band of it, so visitors outside every band fall through to the rules after them.
+pub fn pick_rule<'a>(
+    data: &AppData,
+    rules: &'a [UrlRule],
+    req: &HttpRequest,
+    click: &Click,
+) -> Option<&'a UrlRule> {
+    if rules.is_empty() {
+        return None;
+    }
+
+    let visitor = Visitor {
+        user_agent: click
+            .user_agent
+            .as_ref()
+            .map(|user_agent| user_agent.as_str())
+            .unwrap_or(""),
+        language: req
+            .headers()
+            .get(http::header::ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(preferred_language),
+        country: if rules.iter().any(|rule| rule.kind == RULE_KIND_COUNTRY) {
+            lookup_country(data, click.ip)
+        } else {
+            None
+        },
+        roll: if rules.iter().any(|rule| rule.kind == RULE_KIND_SPLIT) {
+            split_roll(data, req, click.url_id)
+        } else {
+            0
+        },
+    };
+    match_rule(rules, &visitor)
+}
+
+/// What rules are matched against, gathered once per redirect
+struct Visitor<'a> {
+    user_agent: &'a str,
+    language: Option<String>,
+    country: Option<String>,
+    /// See `split_roll`
+    roll: u32,
+}
+
+fn match_rule<'a>(rules: &'a [UrlRule], visitor: &Visitor) -> Option<&'a UrlRule> {
+    let mut split_start = 0;
+    rules.iter().find(|rule| {
+        let mut values = rule.value.split(',');
+        match rule.kind.as_str() {
+            RULE_KIND_DEVICE => values.any(|device| is_device(visitor.user_agent, device)),
+            RULE_KIND_LANGUAGE => match &visitor.language {
+                Some(language) => values
+                    .any(|value| language == value || language.starts_with(&format!("{}-", value))),
+                None => false,
+            },
+            RULE_KIND_COUNTRY => match &visitor.country {
+                Some(country) => values.any(|value| value == country),
+                None => false,
+            },
+            RULE_KIND_SPLIT => {
+                let start = split_start;
+                split_start += rule.value.parse::<u32>().unwrap_or(0);
+                visitor.roll >= start && visitor.roll < split_start
+            }
+            _ => false,
+        }
+    })
+}
+
+/// A number from 0 to 99 derived from the visitor's salted IP hash and the link, so returning
+/// visitors keep landing on the same target without a cookie
+fn split_roll(data: &AppData, req: &HttpRequest, url_id: i32) -> u32 {
+    let digest = Sha256::digest(format!("{}.{}", url_id, visitor_hash(data, req)).as_bytes());
+    let value = u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]);
+    value % 100
+}
+
+/// Going by the user agent alone, tablets count as mobile
+fn is_device(user_agent: &str, device: &str) -> bool {
+    let is_ios = ["iPhone", "iPad", "iPod"]
+        .iter()
+        .any(|name| user_agent.contains(name));
+    let is_android = user_agent.contains("Android");
+    let is_mobile = is_ios || is_android || user_agent.contains("Mobi");
+    match device {
+        "ios" => is_ios,
+        "android" => is_android,
+        "mobile" => is_mobile,
+        "desktop" => !is_mobile,
+        _ => false,
+    }
+}
+
+/// The `Accept-Language` entry with the highest quality, lowercased
+fn preferred_language(header: &str) -> Option<String> {
+    let mut preferred: Option<(String, f32)> = None;
+    for entry in header.split(',') {
+        let mut parts = entry.split(';');
+        let tag = parts.next().unwrap_or("").trim().to_lowercase();
+        let quality = parts
+            .map(|part| part.trim())
+            .find(|part| part.starts_with("q="))
+            .and_then(|part| part[2..].parse::<f32>().ok())
+            .unwrap_or(1.0);
+        if tag.is_empty() || tag == "*" || quality <= 0.0 {
+            continue;
+        }
+        match &preferred {
+            Some((_, best)) if *best >= quality => {}
+            _ => preferred = Some((tag, quality)),
+        }
+    }
+    preferred.map(|(tag, _)| tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    const DESKTOP: &str = "Mozilla/5.0 (X11; Linux x86_64; rv:78.0) Gecko/20100101 Firefox/78.0";
+    const IPHONE: &str = "Mozilla/5.0 (iPhone; CPU iPhone OS 13_5 like Mac OS X) Mobile/15E148";
+
+    fn normalize(kind: &str, value: &str) -> Result<String, UrlShortenerError> {
+        normalize_value(&kind.to_owned(), &value.to_owned())
+    }
+
+    fn rule(id: i32, kind: &str, value: &str) -> UrlRule {
+        UrlRule {
+            id,
+            url_id: 1,
+            position: id,
+            kind: kind.to_owned(),
+            value: value.to_owned(),
+            target: format!("https://example.com/{}", id),
+            created_at: NaiveDate::from_ymd(2020, 7, 1).and_hms(12, 0, 0),
+        }
+    }
+
+    fn visitor(user_agent: &str, roll: u32) -> Visitor {
+        Visitor {
+            user_agent,
+            language: None,
+            country: None,
+            roll,
+        }
+    }
+
+    fn matched_id(rules: &[UrlRule], visitor: &Visitor) -> Option<i32> {
+        match_rule(rules, visitor).map(|rule| rule.id)
+    }
+
+    #[test]
+    fn normalizes_lists() {
+        assert_eq!(
+            normalize("device", " iOS , Desktop,").unwrap(),
+            "ios,desktop"
+        );
+        assert_eq!(normalize("language", "EN, pt-BR").unwrap(), "en,pt-br");
+        assert_eq!(normalize("country", "us,de").unwrap(), "US,DE");
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        assert!(normalize("device", "tablet").is_err());
+        assert!(normalize("language", "english").is_err());
+        assert!(normalize("country", "USA").is_err());
+        assert!(normalize("country", " , ").is_err());
+        assert!(normalize("weather", "sunny").is_err());
+    }
+
+    #[test]
+    fn accepts_split_percentages() {
+        assert_eq!(normalize("split", " 50 ").unwrap(), "50");
+        assert_eq!(normalize("split", "100").unwrap(), "100");
+        assert!(normalize("split", "0").is_err());
+        assert!(normalize("split", "101").is_err());
+        assert!(normalize("split", "half").is_err());
+    }
+
+    #[test]
+    fn splits_into_consecutive_bands() {
+        let rules = vec![
+            rule(1, "split", "30"),
+            rule(2, "split", "50"),
+            rule(3, "device", "desktop"),
+        ];
+        assert_eq!(matched_id(&rules, &visitor(DESKTOP, 0)), Some(1));
+        assert_eq!(matched_id(&rules, &visitor(DESKTOP, 29)), Some(1));
+        assert_eq!(matched_id(&rules, &visitor(DESKTOP, 30)), Some(2));
+        assert_eq!(matched_id(&rules, &visitor(DESKTOP, 79)), Some(2));
+        assert_eq!(matched_id(&rules, &visitor(DESKTOP, 80)), Some(3));
+        assert_eq!(matched_id(&rules, &visitor(IPHONE, 80)), None);
+    }
+
+    #[test]
+    fn tries_rules_in_order() {
+        let rules = vec![
+            rule(1, "device", "ios"),
+            rule(2, "language", "pt"),
+            rule(3, "country", "BR"),
+        ];
+        let mut visitor = visitor(DESKTOP, 0);
+        visitor.language = Some("pt-br".to_owned());
+        visitor.country = Some("BR".to_owned());
+        assert_eq!(matched_id(&rules, &visitor), Some(2));
+
+        visitor.user_agent = IPHONE;
+        assert_eq!(matched_id(&rules, &visitor), Some(1));
+
+        visitor.user_agent = DESKTOP;
+        visitor.language = Some("en".to_owned());
+        assert_eq!(matched_id(&rules, &visitor), Some(3));
+    }
+
+    #[test]
+    fn prefers_highest_quality_language() {
+        assert_eq!(
+            preferred_language("fr;q=0.8, de-CH, en;q=0.9").as_deref(),
+            Some("de-ch")
+        );
+        assert_eq!(
+            preferred_language("en;q=0.5, pt-BR;q=0.7").as_deref(),
+            Some("pt-br")
+        );
+    }
+
+    #[test]
+    fn keeps_first_language_on_equal_quality() {
+        assert_eq!(preferred_language("en, de").as_deref(), Some("en"));
+        assert_eq!(
+            preferred_language("en;q=0.5,de;q=0.5").as_deref(),
+            Some("en")
+        );
+    }
+
+    #[test]
+    fn skips_wildcard_and_refused_languages() {
+        assert_eq!(preferred_language("*, nl;q=0.3").as_deref(), Some("nl"));
+        assert_eq!(preferred_language("en;q=0"), None);
+        assert_eq!(preferred_language(""), None);
+    }
+}
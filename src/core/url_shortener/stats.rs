@@ -0,0 +1,71 @@
+use actix_web::web::{Data, Query};
+use actix_web::{HttpRequest, HttpResponse, Result};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::app_data::AppData;
+use crate::auth::model::SCOPE_URL_READ;
+use crate::core::authenticated_user::AuthenticatedUser;
+use crate::database::handler::url_click::{ClickBucket, CountryClicks, RuleClicks};
+
+const BUCKETS: [&str; 4] = ["hour", "day", "week", "month"];
+/// Ten years, far beyond any stored click while keeping `Duration::days` from overflowing
+const MAX_DAYS: i64 = 3650;
+
+#[derive(Deserialize)]
+pub struct GetStatsRequest {
+    domain_id: Option<i32>,
+    bucket: Option<String>,
+    days: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct GetStatsResponse {
+    key: String,
+    bucket: String,
+    days: i64,
+    clicks: i64,
+    unique_visitors: i64,
+    series: Vec<ClickBucket>,
+    countries: Vec<CountryClicks>,
+    rules: Vec<RuleClicks>,
+}
+
+pub async fn handle(
+    request: Query<GetStatsRequest>,
+    data: Data<AppData>,
+    user: AuthenticatedUser,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    user.require_scope(SCOPE_URL_READ)?;
+    let key = String::from(req.match_info().get("key").unwrap());
+
+    let bucket = request.bucket.to_owned().unwrap_or("day".to_owned());
+    if !BUCKETS.contains(&bucket.as_str()) {
+        return Ok(
+            HttpResponse::BadRequest().body(format!("Bucket can only be one of {:?}", BUCKETS))
+        );
+    }
+    let days = request.days.unwrap_or(30).max(1).min(MAX_DAYS);
+
+    let url = data
+        .url_handler
+        .get_by_key_and_username(request.domain_id, &key, user.username())?;
+    let since = Utc::now().naive_utc() - Duration::days(days);
+
+    let summary = data.url_click_handler.get_summary(url.id, &since)?;
+    let series = data.url_click_handler.get_series(url.id, &bucket, &since)?;
+    let countries = data.url_click_handler.get_countries(url.id, &since)?;
+    let rules = data.url_click_handler.get_rules(url.id, &since)?;
+
+    Ok(HttpResponse::Ok().json(GetStatsResponse {
+        key: url.key,
+        bucket,
+        days,
+        clicks: summary.clicks,
+        unique_visitors: summary.unique_visitors,
+        series,
+        countries,
+        rules,
+    }))
+}
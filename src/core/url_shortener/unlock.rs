@@ -0,0 +1,178 @@
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::web::{Data, Form};
+use actix_web::{http, HttpRequest, HttpResponse, Result};
+use chrono::{Duration, Utc};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+use serde::Deserialize;
+use subtle::ConstantTimeEq;
+
+use crate::app_data::AppData;
+use crate::core::url_shortener::analytics::visitor_hash;
+use crate::core::url_shortener::domain::{not_found, request_domain};
+use crate::core::url_shortener::error::UrlShortenerError;
+use crate::core::url_shortener::redirect_by_key::PREVIEW_SUFFIX;
+use crate::database::handler::url::Url;
+
+const UNLOCK_COOKIE_PREFIX: &str = "url_unlock_";
+const PASSWORD_HASH_COST: u32 = 12;
+
+#[derive(Deserialize)]
+pub struct UnlockRequest {
+    password: String,
+}
+
+/// Checks the password posted from the prompt and sends the visitor back to the link with a
+/// cookie that lets them through until it expires.
+pub async fn handle(
+    form: Form<UnlockRequest>,
+    req: HttpRequest,
+    data: Data<AppData>,
+) -> Result<HttpResponse> {
+    unlock(&form, &req, &data, None)
+}
+
+pub async fn handle_on_domain(
+    form: Form<UnlockRequest>,
+    req: HttpRequest,
+    data: Data<AppData>,
+) -> Result<HttpResponse> {
+    match request_domain(&data, &req)? {
+        Some(domain) => unlock(&form, &req, &data, Some(domain.id)),
+        None => not_found(&data, None),
+    }
+}
+
+fn unlock(
+    form: &UnlockRequest,
+    req: &HttpRequest,
+    data: &Data<AppData>,
+    domain_id: Option<i32>,
+) -> Result<HttpResponse> {
+    let name = req.match_info().get("key").unwrap();
+    let name = String::from(name.trim_end_matches(PREVIEW_SUFFIX));
+
+    let url_entry = data.url_handler.get_by_key(domain_id, &name)?;
+    let mut back = HttpResponse::SeeOther();
+    back.header(http::header::LOCATION, req.uri().to_string());
+
+    let password_hash = match &url_entry.password_hash {
+        Some(password_hash) => password_hash,
+        None => return Ok(back.finish()),
+    };
+
+    // Checked before bcrypt, so throttled visitors cost no hashing
+    let visitor = visitor_hash(data, req);
+    let window_start =
+        Utc::now().naive_utc() - Duration::seconds(data.config.url.unlock_attempt_window as i64);
+    let failed_attempts =
+        data.url_unlock_attempt_handler
+            .count_failed(url_entry.id, &visitor, &window_start)?;
+    if failed_attempts >= data.config.url.max_unlock_attempts {
+        return too_many_attempts(data);
+    }
+
+    let is_valid = bcrypt::verify(&form.password, password_hash)
+        .map_err(|_| UrlShortenerError::PasswordHashFailed)?;
+    if !is_valid {
+        data.url_unlock_attempt_handler
+            .record_failed(url_entry.id, &visitor, &window_start)?;
+        return password_prompt(data, "Wrong password");
+    }
+
+    data.url_unlock_attempt_handler
+        .clear(url_entry.id, &visitor)?;
+    Ok(back.cookie(unlock_cookie(data, &url_entry)?).finish())
+}
+
+fn too_many_attempts(data: &Data<AppData>) -> Result<HttpResponse> {
+    let template = data
+        .templater
+        .url_password_page(&"Too many wrong passwords, try again later".to_owned())?;
+    Ok(HttpResponse::TooManyRequests()
+        .header(http::header::CACHE_CONTROL, "no-store")
+        .header(
+            http::header::RETRY_AFTER,
+            data.config.url.unlock_attempt_window.to_string(),
+        )
+        .body(template))
+}
+
+pub fn password_prompt(data: &Data<AppData>, message: &str) -> Result<HttpResponse> {
+    let template = data.templater.url_password_page(&message.to_owned())?;
+    Ok(HttpResponse::Unauthorized()
+        .header(http::header::CACHE_CONTROL, "no-store")
+        .body(template))
+}
+
+pub fn hash_password(password: &String) -> Result<String, UrlShortenerError> {
+    bcrypt::hash(password, PASSWORD_HASH_COST).map_err(|_| UrlShortenerError::PasswordHashFailed)
+}
+
+/// Whether the visitor may follow the link, either because it has no password or because they
+/// carry a valid unlock cookie for it.
+pub fn is_unlocked(data: &AppData, req: &HttpRequest, url_entry: &Url) -> bool {
+    let password_hash = match &url_entry.password_hash {
+        Some(password_hash) => password_hash,
+        None => return true,
+    };
+    let cookie = match req.cookie(&cookie_name(&url_entry.key)) {
+        Some(cookie) => cookie,
+        None => return false,
+    };
+
+    let mut parts = cookie.value().splitn(2, '.');
+    let (expires_at, signature) = match (parts.next(), parts.next()) {
+        (Some(expires_at), Some(signature)) => (expires_at, signature),
+        _ => return false,
+    };
+    let expires_at = match expires_at.parse::<i64>() {
+        Ok(expires_at) => expires_at,
+        Err(_) => return false,
+    };
+    if expires_at <= Utc::now().timestamp() {
+        return false;
+    }
+
+    match sign(data, &url_entry.key, password_hash, expires_at) {
+        Some(expected) => expected.as_bytes().ct_eq(signature.as_bytes()).into(),
+        None => false,
+    }
+}
+
+fn unlock_cookie(data: &AppData, url_entry: &Url) -> Result<Cookie<'static>, UrlShortenerError> {
+    let password_hash = url_entry
+        .password_hash
+        .as_ref()
+        .ok_or(UrlShortenerError::PasswordHashFailed)?;
+    let expires_at = Utc::now().timestamp() + data.config.url.unlock_cookie_lifetime as i64;
+    let signature = sign(data, &url_entry.key, password_hash, expires_at)
+        .ok_or(UrlShortenerError::PasswordHashFailed)?;
+
+    Ok(Cookie::build(
+        cookie_name(&url_entry.key),
+        format!("{}.{}", expires_at, signature),
+    )
+    .same_site(SameSite::Lax)
+    .http_only(true)
+    .path("/")
+    .finish())
+}
+
+fn cookie_name(key: &String) -> String {
+    format!("{}{}", UNLOCK_COOKIE_PREFIX, key)
+}
+
+/// HMAC over the key, the current password hash and the expiry, so changing the password
+/// invalidates cookies handed out for the old one.
+fn sign(data: &AppData, key: &String, password_hash: &String, expires_at: i64) -> Option<String> {
+    let secret = PKey::hmac(data.config.url.unlock_cookie_secret.as_bytes()).ok()?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &secret).ok()?;
+    signer
+        .update(format!("{}.{}.{}", key, password_hash, expires_at).as_bytes())
+        .ok()?;
+    let signature = signer.sign_to_vec().ok()?;
+
+    Some(signature.iter().map(|b| format!("{:02x}", b)).collect())
+}
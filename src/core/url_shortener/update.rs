@@ -1,40 +1,157 @@
 use actix_web::web::{Data, Json};
-use actix_web::{HttpRequest, HttpResponse, Result};
-use serde::{Deserialize, Serialize};
+use actix_web::{HttpResponse, Result};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Deserializer, Serialize};
 
 use crate::app_data::AppData;
-use crate::core::url_shortener::utils::{authenticate, is_valid_url_key};
-use crate::database::handler::url::Url;
+use crate::auth::model::SCOPE_URL_WRITE;
+use crate::core::authenticated_user::AuthenticatedUser;
+use crate::core::url_shortener::error::UrlShortenerError;
+use crate::core::url_shortener::unlock::hash_password;
+use crate::core::url_shortener::utils::{
+    get_membership, is_valid_url_key, normalize_tags, validate_limits, validate_target, with_tags,
+};
+use crate::database::handler::url::{TaggedUrl, UrlChanges, REDIRECT_TYPES};
 
 #[derive(Deserialize)]
 pub struct UpdateUrlRequest {
+    domain_id: Option<i32>,
     old_key: String,
     key: String,
     target: String,
+    /// For each of the limits, left out keeps the current value and `null` removes it
+    #[serde(default, deserialize_with = "nullable")]
+    expires_at: Option<Option<NaiveDateTime>>,
+    #[serde(default, deserialize_with = "nullable")]
+    activates_at: Option<Option<NaiveDateTime>>,
+    #[serde(default, deserialize_with = "nullable")]
+    max_clicks: Option<Option<i32>>,
+    /// Left out keeps the current redirect type
+    redirect_type: Option<i32>,
+    /// Left out keeps the current setting
+    always_preview: Option<bool>,
+    /// Left out keeps the current password, an empty one removes it
+    password: Option<String>,
+    /// Left out keeps the current description, an empty one removes it
+    description: Option<String>,
+    /// Left out keeps the current tags
+    tags: Option<Vec<String>>,
+    /// Moves a personal link into an organization, only its owner can
+    organization_id: Option<i32>,
+}
+
+/// Tells a field set to `null` (`Some(None)`) apart from one left out (`None`)
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
 }
 
 #[derive(Serialize)]
 pub struct UpdateUrlResponse {
-    url: Url,
+    url: TaggedUrl,
 }
 
 pub async fn handle(
     request: Json<UpdateUrlRequest>,
     data: Data<AppData>,
-    req: HttpRequest,
+    user: AuthenticatedUser,
 ) -> Result<HttpResponse> {
-    let token = authenticate(&data, &req)?;
+    user.require_scope(SCOPE_URL_WRITE)?;
 
     if !is_valid_url_key(&request.key) {
         return Ok(HttpResponse::BadRequest().body("Key can only have alphanumeric and \"_-.\""));
     }
 
-    let url = data.url_handler.update(
+    validate_target(&data, &request.target)?;
+
+    let current = data.url_handler.get_writable_by_key(
+        request.domain_id,
+        &request.old_key,
+        user.username(),
+    )?;
+
+    let expires_at = match &request.expires_at {
+        Some(expires_at) => expires_at.as_ref(),
+        None => current.expires_at.as_ref(),
+    };
+    let activates_at = match &request.activates_at {
+        Some(activates_at) => activates_at.as_ref(),
+        None => current.activates_at.as_ref(),
+    };
+    let max_clicks = request.max_clicks.unwrap_or(current.max_clicks);
+    validate_limits(expires_at, activates_at, max_clicks)?;
+
+    let move_to = match request.organization_id {
+        Some(organization_id) if current.organization_id != Some(organization_id) => {
+            if current.organization_id.is_some() || &current.username != user.username() {
+                return Err(UrlShortenerError::NotLinkOwner.into());
+            }
+            if !get_membership(&data, organization_id, user.username())?.can_write() {
+                return Err(UrlShortenerError::InsufficientOrganizationRole.into());
+            }
+            Some(organization_id)
+        }
+        _ => None,
+    };
+
+    let redirect_type = request.redirect_type.unwrap_or(current.redirect_type);
+    if !REDIRECT_TYPES.contains(&redirect_type) {
+        return Err(UrlShortenerError::InvalidRedirectType.into());
+    }
+
+    let tags = match &request.tags {
+        Some(tags) => Some(normalize_tags(tags)?),
+        None => None,
+    };
+
+    let description = match &request.description {
+        Some(description) if description.is_empty() => None,
+        Some(description) => Some(description),
+        None => current.description.as_ref(),
+    };
+
+    let password_hash = match &request.password {
+        Some(password) if !password.is_empty() => Some(Some(hash_password(password)?)),
+        Some(_) => Some(None),
+        None => None,
+    };
+
+    let mut url = data.url_handler.update(
+        request.domain_id,
         &request.old_key,
-        &token.username,
-        &request.key,
-        &request.target,
+        user.username(),
+        &UrlChanges {
+            key: &request.key,
+            target: &request.target,
+            expires_at,
+            activates_at,
+            max_clicks,
+            redirect_type,
+            always_preview: request.always_preview.unwrap_or(current.always_preview),
+            description,
+        },
     )?;
 
+    if let Some(password_hash) = password_hash {
+        url =
+            data.url_handler
+                .set_password_hash(url.id, user.username(), password_hash.as_ref())?;
+    }
+
+    if let Some(organization_id) = move_to {
+        url = data
+            .url_handler
+            .move_to_organization(url.id, user.username(), organization_id)?;
+    }
+
+    if let Some(tags) = tags {
+        data.url_handler.set_tags(url.id, &tags)?;
+    }
+
+    let url = with_tags(&data, vec![url])?.remove(0);
+
     Ok(HttpResponse::Ok().json(UpdateUrlResponse { url }))
 }
@@ -1,20 +1,185 @@
 use crate::app_data::AppData;
 use crate::auth::model::TokenPayload;
-use crate::auth::AuthError;
+use crate::config::Config;
+use crate::core::authenticated_user::AuthenticatedUser;
+use crate::core::url_shortener::error::UrlShortenerError;
+use crate::database::handler::organization::OrganizationMember;
+use crate::database::handler::url::{TaggedUrl, Url as ShortUrl};
+use crate::database::handler::DbError;
+use actix_web::http::{header, HeaderMap};
 use actix_web::web::Data;
 use actix_web::HttpRequest;
+use chrono::{Duration, NaiveDateTime, Utc};
+use rand::{thread_rng, Rng};
 use regex::Regex;
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use url::{Host, Url};
 
 pub fn authenticate(data: &Data<AppData>, req: &HttpRequest) -> actix_web::Result<TokenPayload> {
-    let auth_header = req
-        .headers()
-        .get("Authorization")
-        .ok_or(actix_web::error::ErrorUnauthorized(AuthError::InvalidToken))?
-        .to_str()
-        .map_err(|_| AuthError::InvalidToken)?
-        .to_owned();
+    Ok(AuthenticatedUser::authenticate(data, req)?.token)
+}
+
+pub fn get_membership(
+    data: &Data<AppData>,
+    organization_id: i32,
+    username: &String,
+) -> actix_web::Result<OrganizationMember> {
+    data.organization_handler
+        .get_member(organization_id, username)
+        .map_err(|e| match e {
+            DbError::NotFound => UrlShortenerError::NotAMember.into(),
+            e => e.into(),
+        })
+}
+
+pub fn validate_limits(
+    expires_at: Option<&NaiveDateTime>,
+    activates_at: Option<&NaiveDateTime>,
+    max_clicks: Option<i32>,
+) -> Result<(), UrlShortenerError> {
+    if let (Some(expires_at), Some(activates_at)) = (expires_at, activates_at) {
+        if expires_at <= activates_at {
+            return Err(UrlShortenerError::InvalidLimits(
+                "expires_at has to be after activates_at",
+            ));
+        }
+    }
+    if let Some(max_clicks) = max_clicks {
+        if max_clicks < 1 {
+            return Err(UrlShortenerError::InvalidLimits(
+                "max_clicks has to be at least 1",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Targets have to be absolute http(s) URLs. Depending on configuration, hosts on the
+/// blocklist and literal private/loopback addresses are refused as well.
+pub fn validate_target(data: &AppData, target: &String) -> Result<(), UrlShortenerError> {
+    let url = Url::parse(target)
+        .map_err(|_| UrlShortenerError::InvalidTarget("Target has to be an absolute URL"))?;
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(UrlShortenerError::InvalidTarget(
+            "Target has to be an http or https URL",
+        ));
+    }
+
+    let host = url.host().ok_or(UrlShortenerError::InvalidTarget(
+        "Target has to have a host",
+    ))?;
+
+    if data.config.url.reject_private_targets && is_private_host(&host) {
+        return Err(UrlShortenerError::InvalidTarget(
+            "Target can't point at a private address",
+        ));
+    }
+
+    if let (Host::Domain(domain), Some(blocklist)) = (&host, &data.domain_blocklist) {
+        if blocklist.is_blocked(domain) {
+            return Err(UrlShortenerError::BlockedTarget);
+        }
+    }
+    Ok(())
+}
+
+/// Links created before their domain got listed are refused at redirect time.
+pub fn is_blocked_target(data: &AppData, target: &String) -> bool {
+    let blocklist = match &data.domain_blocklist {
+        Some(blocklist) => blocklist,
+        None => return false,
+    };
+    match Url::parse(target)
+        .ok()
+        .as_ref()
+        .and_then(|url| url.host_str())
+    {
+        Some(host) => blocklist.is_blocked(host),
+        None => false,
+    }
+}
+
+/// Only literal addresses and `localhost` are checked, names aren't resolved.
+fn is_private_host(host: &Host<&str>) -> bool {
+    match host {
+        Host::Domain(domain) => {
+            let domain = domain.trim_end_matches('.').to_lowercase();
+            domain == "localhost" || domain.ends_with(".localhost")
+        }
+        Host::Ipv4(ip) => is_private_ipv4(ip),
+        // `to_ipv4` also turns `::1` into `0.0.0.1`, so the IPv6 ranges go first
+        Host::Ipv6(ip) => {
+            is_private_ipv6(ip) || ip.to_ipv4().map_or(false, |ip| is_private_ipv4(&ip))
+        }
+    }
+}
+
+fn is_private_ipv4(ip: &Ipv4Addr) -> bool {
+    ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        // Shared address space, 100.64.0.0/10
+        || (ip.octets()[0] == 100 && ip.octets()[1] & 0xc0 == 64)
+}
+
+fn is_private_ipv6(ip: &Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    ip.is_loopback()
+        || ip.is_unspecified()
+        // Unique local, fc00::/7
+        || first & 0xfe00 == 0xfc00
+        // Link local, fe80::/10
+        || first & 0xffc0 == 0xfe80
+}
+
+const MAX_TAGS: usize = 20;
+const MAX_TAG_LENGTH: usize = 50;
+
+/// Trimmed, lowercased and deduplicated, so `Docs` and ` docs` end up as the same tag
+pub fn normalize_tags(tags: &[String]) -> Result<Vec<String>, UrlShortenerError> {
+    let mut normalized: Vec<String> = tags.iter().map(|tag| tag.trim().to_lowercase()).collect();
+    normalized.sort();
+    normalized.dedup();
+
+    if normalized.len() > MAX_TAGS {
+        return Err(UrlShortenerError::InvalidTags(
+            "A link can have at most 20 tags",
+        ));
+    }
+    if normalized
+        .iter()
+        .any(|tag| tag.is_empty() || tag.chars().count() > MAX_TAG_LENGTH)
+    {
+        return Err(UrlShortenerError::InvalidTags(
+            "Tags have to be between 1 and 50 characters",
+        ));
+    }
+    Ok(normalized)
+}
+
+/// Loads the tags of all `urls` in one query
+pub fn with_tags(data: &AppData, urls: Vec<ShortUrl>) -> actix_web::Result<Vec<TaggedUrl>> {
+    let ids: Vec<i32> = urls.iter().map(|url| url.id).collect();
+    let mut tags: HashMap<i32, Vec<String>> = HashMap::new();
+    for url_tag in data.url_handler.get_tags(&ids)? {
+        tags.entry(url_tag.url_id).or_default().push(url_tag.name);
+    }
 
-    Ok(data.auth_handler.inspect(&auth_header)?)
+    Ok(urls
+        .into_iter()
+        .map(|url| TaggedUrl {
+            tags: tags.remove(&url.id).unwrap_or_default(),
+            url,
+        })
+        .collect())
+}
+
+/// Links deleted before this can't be restored anymore
+pub fn restore_deadline(data: &AppData) -> NaiveDateTime {
+    Utc::now().naive_utc() - Duration::seconds(data.config.url.restore_window as i64)
 }
 
 pub fn is_valid_url_key(key: &String) -> bool {
@@ -23,3 +188,155 @@ pub fn is_valid_url_key(key: &String) -> bool {
     }
     RE.is_match(key)
 }
+
+/// Generated keys containing one of these, read with common digit substitutions, are drawn
+/// again. Custom alphabets may well include vowels.
+const OFFENSIVE_WORDS: &[&str] = &[
+    "anal", "anus", "arse", "ass", "bitch", "boob", "cock", "crap", "cum", "cunt", "damn", "dick",
+    "dildo", "dyke", "fag", "fuck", "jizz", "kkk", "nazi", "nigg", "penis", "piss", "porn",
+    "pussy", "rape", "sex", "shit", "slut", "tit", "twat", "wank", "whore",
+];
+/// An alphabet that keeps spelling words still has to produce a key eventually
+const MAX_KEY_DRAWS: usize = 100;
+/// How often a generated key is drawn again after colliding with an existing one
+pub const MAX_KEY_ATTEMPTS: usize = 5;
+
+/// A random key drawn from the configured alphabet, which `config::get_config` has checked
+/// to be non-empty and made of key characters. Collisions are left to the unique index,
+/// callers retry on `DbError::DuplicateKey`.
+pub fn generate_url_key(alphabet: &String, length: usize) -> String {
+    let alphabet: Vec<char> = alphabet.chars().collect();
+    let mut rng = thread_rng();
+    let mut draw = || -> String {
+        (0..length)
+            .map(|_| alphabet[rng.gen_range(0, alphabet.len())])
+            .collect()
+    };
+
+    let mut key = draw();
+    for _ in 1..MAX_KEY_DRAWS {
+        if !is_offensive(&key) {
+            break;
+        }
+        key = draw();
+    }
+    key
+}
+
+fn is_offensive(key: &String) -> bool {
+    let readable: String = key
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            '0' => 'o',
+            '1' => 'i',
+            '3' => 'e',
+            '4' => 'a',
+            '5' => 's',
+            '7' => 't',
+            c => c,
+        })
+        .collect();
+    OFFENSIVE_WORDS.iter().any(|word| readable.contains(word))
+}
+
+/// Links on a custom domain live at its root, the others under the configured base
+pub fn get_short_url(data: &AppData, url: &ShortUrl) -> actix_web::Result<String> {
+    Ok(match url.domain_id {
+        Some(domain_id) => {
+            let domain = data.domain_handler.get_by_id(domain_id)?;
+            format!("https://{}/{}", domain.hostname, url.key)
+        }
+        None => format!(
+            "{}/{}",
+            data.config.url.short_url_base.trim_end_matches('/'),
+            url.key
+        ),
+    })
+}
+
+/// The `Host` header without its port, lowercased
+pub fn request_hostname(headers: &HeaderMap) -> Option<String> {
+    let host = headers.get(header::HOST)?.to_str().ok()?.trim();
+    // IPv6 literals are bracketed and have colons of their own
+    let host = if host.ends_with(']') {
+        host
+    } else {
+        host.rsplitn(2, ':').last().unwrap_or(host)
+    };
+    Some(host.trim_end_matches('.').to_lowercase())
+}
+
+/// Whether the server itself answers on `hostname`, for its short links, the SSO pages or as
+/// SAML identity provider
+pub fn is_server_host(config: &Config, hostname: &str) -> bool {
+    let mut server_urls = vec![&config.url.short_url_base, &config.auth.base_url];
+    if let Some(saml) = &config.saml {
+        server_urls.push(&saml.entity_id);
+    }
+
+    server_urls.into_iter().any(|server_url| {
+        Url::parse(server_url)
+            .ok()
+            .as_ref()
+            .and_then(|url| url.host_str())
+            .map(|host| host.trim_end_matches('.').eq_ignore_ascii_case(hostname))
+            .unwrap_or(false)
+    })
+}
+
+/// Lowercased, punycode encoded and without a trailing dot, the way browsers send it in `Host`.
+/// Has to be a domain name with at least two labels, other than the server's own.
+pub fn normalize_hostname(config: &Config, hostname: &String) -> Result<String, UrlShortenerError> {
+    let hostname = match Host::parse(hostname.trim().trim_end_matches('.')) {
+        Ok(Host::Domain(domain)) if domain.contains('.') => domain,
+        _ => {
+            return Err(UrlShortenerError::InvalidDomain(
+                "Hostname has to be a domain name such as sho.rt",
+            ))
+        }
+    };
+    if is_server_host(config, &hostname) {
+        return Err(UrlShortenerError::InvalidDomain(
+            "The server's own hosts can't be registered as a domain",
+        ));
+    }
+    Ok(hostname)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_private(target: &str) -> bool {
+        let url = Url::parse(target).unwrap();
+        is_private_host(&url.host().unwrap())
+    }
+
+    #[test]
+    fn rejects_loopback() {
+        assert!(is_private("http://127.0.0.1/"));
+        assert!(is_private("http://[::1]/"));
+        assert!(is_private("http://[::ffff:127.0.0.1]/"));
+    }
+
+    #[test]
+    fn rejects_private_and_link_local_ranges() {
+        assert!(is_private("http://10.1.2.3/"));
+        assert!(is_private("http://169.254.169.254/"));
+        assert!(is_private("http://[fe80::1]/"));
+    }
+
+    #[test]
+    fn rejects_localhost_with_trailing_dot() {
+        assert!(is_private("http://localhost./"));
+        assert!(is_private("http://api.localhost/"));
+    }
+
+    #[test]
+    fn allows_public_hosts() {
+        assert!(!is_private("http://93.184.216.34/"));
+        assert!(!is_private("http://[2606:4700::1111]/"));
+        assert!(!is_private("https://example.com/"));
+    }
+}
@@ -0,0 +1,84 @@
+use chrono::NaiveDateTime;
+use diesel::{insert_into, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl};
+use serde::Serialize;
+
+use crate::database::handler::DbResult;
+use crate::schema::audit_log as audit_log_schema;
+use crate::schema::audit_log::dsl as audit_log;
+use std::rc::Rc;
+
+pub trait AuditLogHandler {
+    fn insert(&self, new_audit_log: &NewAuditLog) -> DbResult<()>;
+    fn get_by_target(
+        &self,
+        target: Option<&String>,
+        offset: i64,
+        limit: i64,
+    ) -> DbResult<Vec<AuditLog>>;
+    fn count_by_target(&self, target: Option<&String>) -> DbResult<i64>;
+}
+
+#[derive(Queryable, Serialize)]
+pub struct AuditLog {
+    pub id: i32,
+    pub actor: String,
+    pub action: String,
+    pub target: String,
+    pub detail: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[table_name = "audit_log_schema"]
+pub struct NewAuditLog<'a> {
+    pub actor: &'a String,
+    pub action: &'a str,
+    pub target: &'a String,
+    pub detail: &'a String,
+}
+
+pub struct AuditLogPostgresHandler {
+    pub connection: Rc<PgConnection>,
+}
+
+impl AuditLogPostgresHandler {
+    pub fn new(connection: Rc<PgConnection>) -> AuditLogPostgresHandler {
+        AuditLogPostgresHandler { connection }
+    }
+}
+
+impl AuditLogHandler for AuditLogPostgresHandler {
+    fn insert(&self, new_audit_log: &NewAuditLog) -> DbResult<()> {
+        insert_into(audit_log::audit_log)
+            .values(new_audit_log)
+            .execute(self.connection.as_ref())?;
+        Ok(())
+    }
+
+    fn get_by_target(
+        &self,
+        target: Option<&String>,
+        offset: i64,
+        limit: i64,
+    ) -> DbResult<Vec<AuditLog>> {
+        let mut query = audit_log::audit_log.into_boxed();
+        if let Some(target) = target {
+            query = query.filter(audit_log::target.eq(target));
+        }
+
+        Ok(query
+            .order(audit_log::id.desc())
+            .offset(offset)
+            .limit(limit)
+            .load::<AuditLog>(self.connection.as_ref())?)
+    }
+
+    fn count_by_target(&self, target: Option<&String>) -> DbResult<i64> {
+        let mut query = audit_log::audit_log.count().into_boxed();
+        if let Some(target) = target {
+            query = query.filter(audit_log::target.eq(target));
+        }
+
+        Ok(query.first::<i64>(self.connection.as_ref())?)
+    }
+}
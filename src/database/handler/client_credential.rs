@@ -1,17 +1,62 @@
-use diesel::{PgConnection, QueryDsl, QueryResult, RunQueryDsl, TextExpressionMethods};
+use chrono::NaiveDateTime;
+use diesel::{
+    delete, insert_into, update, ExpressionMethods, PgConnection, QueryDsl, QueryResult,
+    RunQueryDsl, TextExpressionMethods,
+};
+use serde::Serialize;
 
+use crate::database::handler::{DbError, DbResult};
+use crate::schema::client_credential as client_credential_schema;
 use crate::schema::client_credential::dsl as client_credential;
 use std::rc::Rc;
 
 pub trait ClientCredentialHandler {
     fn get_by_id(&self, id: &String) -> QueryResult<ClientCredential>;
+    fn get_all(&self, offset: i64, limit: i64) -> DbResult<Vec<ClientCredential>>;
+    fn count(&self) -> DbResult<i64>;
+    fn insert(&self, new_client_credential: &NewClientCredential) -> DbResult<ClientCredential>;
+    fn update_metadata(
+        &self,
+        id: &String,
+        metadata: &ClientCredentialMetadata,
+    ) -> DbResult<ClientCredential>;
+    fn update_secret(&self, id: &String, secret: &String) -> DbResult<ClientCredential>;
+    fn delete_at_least_one(&self, id: &String) -> DbResult<usize>;
 }
 
-#[derive(Queryable)]
+#[derive(Queryable, Serialize)]
 pub struct ClientCredential {
     pub id: String,
+    #[serde(skip_serializing)]
     pub secret: String,
-    pub redirect_uri: String,
+    pub redirect_uris: Vec<String>,
+    pub name: String,
+    pub logo_uri: Option<String>,
+    pub grant_types: Vec<String>,
+    pub owner: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[table_name = "client_credential_schema"]
+pub struct NewClientCredential<'a> {
+    pub id: &'a String,
+    pub secret: &'a String,
+    pub redirect_uris: &'a Vec<String>,
+    pub name: &'a String,
+    pub logo_uri: Option<&'a String>,
+    pub grant_types: &'a Vec<String>,
+    pub owner: &'a String,
+}
+
+#[derive(AsChangeset)]
+#[table_name = "client_credential_schema"]
+#[changeset_options(treat_none_as_null = "true")]
+pub struct ClientCredentialMetadata<'a> {
+    pub redirect_uris: &'a Vec<String>,
+    pub name: &'a String,
+    pub logo_uri: Option<&'a String>,
+    pub grant_types: &'a Vec<String>,
 }
 
 pub struct ClientCredentialPostgresHandler {
@@ -30,4 +75,55 @@ impl ClientCredentialHandler for ClientCredentialPostgresHandler {
             .filter(client_credential::id.like(id))
             .first::<ClientCredential>(self.connection.as_ref())
     }
+
+    fn get_all(&self, offset: i64, limit: i64) -> DbResult<Vec<ClientCredential>> {
+        Ok(client_credential::client_credential
+            .order(client_credential::id)
+            .offset(offset)
+            .limit(limit)
+            .load::<ClientCredential>(self.connection.as_ref())?)
+    }
+
+    fn count(&self) -> DbResult<i64> {
+        Ok(client_credential::client_credential
+            .count()
+            .first::<i64>(self.connection.as_ref())?)
+    }
+
+    fn insert(&self, new_client_credential: &NewClientCredential) -> DbResult<ClientCredential> {
+        Ok(insert_into(client_credential::client_credential)
+            .values(new_client_credential)
+            .get_result::<ClientCredential>(self.connection.as_ref())?)
+    }
+
+    fn update_metadata(
+        &self,
+        id: &String,
+        metadata: &ClientCredentialMetadata,
+    ) -> DbResult<ClientCredential> {
+        Ok(
+            update(client_credential::client_credential.filter(client_credential::id.eq(id)))
+                .set(metadata)
+                .get_result::<ClientCredential>(self.connection.as_ref())?,
+        )
+    }
+
+    fn update_secret(&self, id: &String, secret: &String) -> DbResult<ClientCredential> {
+        Ok(
+            update(client_credential::client_credential.filter(client_credential::id.eq(id)))
+                .set(client_credential::secret.eq(secret))
+                .get_result::<ClientCredential>(self.connection.as_ref())?,
+        )
+    }
+
+    fn delete_at_least_one(&self, id: &String) -> DbResult<usize> {
+        let count =
+            delete(client_credential::client_credential.filter(client_credential::id.eq(id)))
+                .execute(self.connection.as_ref())?;
+        if count == 0 {
+            Err(DbError::NotFound)
+        } else {
+            Ok(count)
+        }
+    }
 }
@@ -0,0 +1,113 @@
+use chrono::NaiveDateTime;
+use diesel::{delete, insert_into, update, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl};
+
+use crate::database::handler::DbResult;
+use crate::schema::device_authorization as device_authorization_schema;
+use crate::schema::device_authorization::dsl as device_authorization;
+use std::rc::Rc;
+
+pub trait DeviceAuthorizationHandler {
+    fn get_by_device_code(&self, device_code: &String) -> DbResult<DeviceAuthorization>;
+    fn get_by_user_code(&self, user_code: &String) -> DbResult<DeviceAuthorization>;
+    fn insert(&self, new_device_authorization: &NewDeviceAuthorization) -> DbResult<()>;
+    fn decide(&self, user_code: &String, username: &String, is_denied: bool) -> DbResult<usize>;
+    fn record_poll(
+        &self,
+        device_code: &String,
+        polled_at: &NaiveDateTime,
+        poll_interval: i32,
+    ) -> DbResult<usize>;
+    fn delete(&self, device_code: &String) -> DbResult<usize>;
+}
+
+#[derive(Queryable)]
+pub struct DeviceAuthorization {
+    pub device_code: String,
+    pub user_code: String,
+    pub client_id: String,
+    pub username: Option<String>,
+    pub is_denied: bool,
+    pub poll_interval: i32,
+    pub last_polled_at: Option<NaiveDateTime>,
+    pub expires_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[table_name = "device_authorization_schema"]
+pub struct NewDeviceAuthorization<'a> {
+    pub device_code: &'a String,
+    pub user_code: &'a String,
+    pub client_id: &'a String,
+    pub poll_interval: i32,
+    pub expires_at: &'a NaiveDateTime,
+}
+
+pub struct DeviceAuthorizationPostgresHandler {
+    pub connection: Rc<PgConnection>,
+}
+
+impl DeviceAuthorizationPostgresHandler {
+    pub fn new(connection: Rc<PgConnection>) -> DeviceAuthorizationPostgresHandler {
+        DeviceAuthorizationPostgresHandler { connection }
+    }
+}
+
+impl DeviceAuthorizationHandler for DeviceAuthorizationPostgresHandler {
+    fn get_by_device_code(&self, device_code: &String) -> DbResult<DeviceAuthorization> {
+        Ok(device_authorization::device_authorization
+            .filter(device_authorization::device_code.eq(device_code))
+            .first::<DeviceAuthorization>(self.connection.as_ref())?)
+    }
+
+    fn get_by_user_code(&self, user_code: &String) -> DbResult<DeviceAuthorization> {
+        Ok(device_authorization::device_authorization
+            .filter(device_authorization::user_code.eq(user_code))
+            .first::<DeviceAuthorization>(self.connection.as_ref())?)
+    }
+
+    fn insert(&self, new_device_authorization: &NewDeviceAuthorization) -> DbResult<()> {
+        insert_into(device_authorization::device_authorization)
+            .values(new_device_authorization)
+            .execute(self.connection.as_ref())?;
+        Ok(())
+    }
+
+    fn decide(&self, user_code: &String, username: &String, is_denied: bool) -> DbResult<usize> {
+        Ok(update(
+            device_authorization::device_authorization
+                .filter(device_authorization::user_code.eq(user_code))
+                .filter(device_authorization::username.is_null()),
+        )
+        .set((
+            device_authorization::username.eq(username),
+            device_authorization::is_denied.eq(is_denied),
+        ))
+        .execute(self.connection.as_ref())?)
+    }
+
+    fn record_poll(
+        &self,
+        device_code: &String,
+        polled_at: &NaiveDateTime,
+        poll_interval: i32,
+    ) -> DbResult<usize> {
+        Ok(update(
+            device_authorization::device_authorization
+                .filter(device_authorization::device_code.eq(device_code)),
+        )
+        .set((
+            device_authorization::last_polled_at.eq(polled_at),
+            device_authorization::poll_interval.eq(poll_interval),
+        ))
+        .execute(self.connection.as_ref())?)
+    }
+
+    fn delete(&self, device_code: &String) -> DbResult<usize> {
+        Ok(delete(
+            device_authorization::device_authorization
+                .filter(device_authorization::device_code.eq(device_code)),
+        )
+        .execute(self.connection.as_ref())?)
+    }
+}
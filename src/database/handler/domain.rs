@@ -0,0 +1,213 @@
+use chrono::NaiveDateTime;
+use diesel::dsl::now;
+use diesel::expression::BoxableExpression;
+use diesel::pg::Pg;
+use diesel::sql_types::Bool;
+use diesel::{
+    delete, insert_into, update, BoolExpressionMethods, ExpressionMethods,
+    NullableExpressionMethods, PgConnection, QueryDsl, RunQueryDsl,
+};
+use serde::Serialize;
+
+use crate::database::handler::organization::ORGANIZATION_WRITE_ROLES;
+use crate::database::handler::{DbError, DbResult};
+use crate::schema::domain as domain_schema;
+use crate::schema::domain::dsl as domain;
+use crate::schema::organization_member::dsl as organization_member;
+use std::rc::Rc;
+
+pub trait DomainHandler {
+    fn get_by_id(&self, id: i32) -> DbResult<Domain>;
+    fn get_approved_by_hostname(&self, hostname: &String) -> DbResult<Domain>;
+    fn get_approved_hostnames(&self) -> DbResult<Vec<String>>;
+    fn get_pending(&self, offset: i64, limit: i64) -> DbResult<Vec<Domain>>;
+    fn count_pending(&self) -> DbResult<i64>;
+    fn approve(&self, id: i32, approved_by: &String) -> DbResult<Domain>;
+    fn delete_pending(&self, id: i32) -> DbResult<Domain>;
+    fn get_by_username(&self, username: &String) -> DbResult<Vec<Domain>>;
+    fn get_by_id_and_username(&self, id: i32, username: &String) -> DbResult<Domain>;
+    fn insert(&self, new_domain: &NewDomain) -> DbResult<Domain>;
+    fn set_not_found_url(
+        &self,
+        id: i32,
+        username: &String,
+        not_found_url: Option<&String>,
+    ) -> DbResult<Domain>;
+    fn delete_at_least_one(&self, id: i32, username: &String) -> DbResult<usize>;
+}
+
+/// A hostname pointed at this server, whose links are served at `https://{hostname}/{key}` once
+/// an admin approved it
+#[derive(Queryable, Serialize)]
+pub struct Domain {
+    pub id: i32,
+    pub hostname: String,
+    pub username: String,
+    pub organization_id: Option<i32>,
+    /// Where visitors of unknown keys are sent instead of the built-in 404 page
+    pub not_found_url: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub approved_at: Option<NaiveDateTime>,
+    #[serde(skip_serializing)]
+    pub approved_by: Option<String>,
+}
+
+#[derive(Insertable)]
+#[table_name = "domain_schema"]
+pub struct NewDomain<'a> {
+    pub hostname: &'a String,
+    pub username: &'a String,
+    pub organization_id: Option<i32>,
+    pub not_found_url: Option<&'a String>,
+}
+
+pub struct DomainPostgresHandler {
+    pub connection: Rc<PgConnection>,
+}
+
+impl DomainPostgresHandler {
+    pub fn new(connection: Rc<PgConnection>) -> DomainPostgresHandler {
+        DomainPostgresHandler { connection }
+    }
+}
+
+type DomainFilter = Box<dyn BoxableExpression<domain_schema::table, Pg, SqlType = Bool>>;
+
+/// Personal domains of `username` plus the domains of every organization they are a member of
+fn readable_by(username: &String) -> DomainFilter {
+    Box::new(
+        domain::username
+            .eq(username.to_owned())
+            .and(domain::organization_id.is_null())
+            .or(domain::organization_id.eq_any(
+                organization_member::organization_member
+                    .select(organization_member::organization_id.nullable())
+                    .filter(organization_member::username.eq(username.to_owned())),
+            )),
+    )
+}
+
+/// Personal domains of `username` plus the domains of organizations where they may edit links
+fn writable_by(username: &String) -> DomainFilter {
+    Box::new(
+        domain::username
+            .eq(username.to_owned())
+            .and(domain::organization_id.is_null())
+            .or(domain::organization_id.eq_any(
+                organization_member::organization_member
+                    .select(organization_member::organization_id.nullable())
+                    .filter(organization_member::username.eq(username.to_owned()))
+                    .filter(organization_member::role.eq_any(ORGANIZATION_WRITE_ROLES.to_vec())),
+            )),
+    )
+}
+
+impl DomainHandler for DomainPostgresHandler {
+    fn get_by_id(&self, id: i32) -> DbResult<Domain> {
+        Ok(domain::domain
+            .find(id)
+            .first::<Domain>(self.connection.as_ref())?)
+    }
+
+    fn get_approved_by_hostname(&self, hostname: &String) -> DbResult<Domain> {
+        Ok(domain::domain
+            .filter(domain::hostname.eq(hostname))
+            .filter(domain::approved_at.is_not_null())
+            .first::<Domain>(self.connection.as_ref())?)
+    }
+
+    fn get_approved_hostnames(&self) -> DbResult<Vec<String>> {
+        Ok(domain::domain
+            .select(domain::hostname)
+            .filter(domain::approved_at.is_not_null())
+            .load::<String>(self.connection.as_ref())?)
+    }
+
+    fn get_pending(&self, offset: i64, limit: i64) -> DbResult<Vec<Domain>> {
+        Ok(domain::domain
+            .filter(domain::approved_at.is_null())
+            .order(domain::created_at)
+            .offset(offset)
+            .limit(limit)
+            .load::<Domain>(self.connection.as_ref())?)
+    }
+
+    fn count_pending(&self) -> DbResult<i64> {
+        Ok(domain::domain
+            .filter(domain::approved_at.is_null())
+            .count()
+            .first::<i64>(self.connection.as_ref())?)
+    }
+
+    fn approve(&self, id: i32, approved_by: &String) -> DbResult<Domain> {
+        Ok(update(
+            domain::domain
+                .filter(domain::id.eq(id))
+                .filter(domain::approved_at.is_null()),
+        )
+        .set((
+            domain::approved_at.eq(now.nullable()),
+            domain::approved_by.eq(approved_by),
+        ))
+        .get_result::<Domain>(self.connection.as_ref())?)
+    }
+
+    /// Rejects a registration, approved domains are only deleted by their owners
+    fn delete_pending(&self, id: i32) -> DbResult<Domain> {
+        Ok(delete(
+            domain::domain
+                .filter(domain::id.eq(id))
+                .filter(domain::approved_at.is_null()),
+        )
+        .get_result::<Domain>(self.connection.as_ref())?)
+    }
+
+    fn get_by_username(&self, username: &String) -> DbResult<Vec<Domain>> {
+        Ok(domain::domain
+            .filter(readable_by(username))
+            .order(domain::hostname)
+            .load::<Domain>(self.connection.as_ref())?)
+    }
+
+    fn get_by_id_and_username(&self, id: i32, username: &String) -> DbResult<Domain> {
+        Ok(domain::domain
+            .filter(domain::id.eq(id))
+            .filter(writable_by(username))
+            .first::<Domain>(self.connection.as_ref())?)
+    }
+
+    fn insert(&self, new_domain: &NewDomain) -> DbResult<Domain> {
+        Ok(insert_into(domain::domain)
+            .values(new_domain)
+            .get_result::<Domain>(self.connection.as_ref())?)
+    }
+
+    fn set_not_found_url(
+        &self,
+        id: i32,
+        username: &String,
+        not_found_url: Option<&String>,
+    ) -> DbResult<Domain> {
+        Ok(update(
+            domain::domain
+                .filter(domain::id.eq(id))
+                .filter(writable_by(username)),
+        )
+        .set(domain::not_found_url.eq(not_found_url))
+        .get_result::<Domain>(self.connection.as_ref())?)
+    }
+
+    fn delete_at_least_one(&self, id: i32, username: &String) -> DbResult<usize> {
+        let count = delete(
+            domain::domain
+                .filter(domain::id.eq(id))
+                .filter(writable_by(username)),
+        )
+        .execute(self.connection.as_ref())?;
+        if count == 0 {
+            Err(DbError::NotFound)
+        } else {
+            Ok(count)
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use chrono::NaiveDateTime;
+use diesel::{insert_into, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl};
+
+use crate::database::handler::DbResult;
+use crate::schema::federated_identity as federated_identity_schema;
+use crate::schema::federated_identity::dsl as federated_identity;
+use std::rc::Rc;
+
+pub trait FederatedIdentityHandler {
+    fn get_by_subject(&self, provider: &String, subject: &String) -> DbResult<FederatedIdentity>;
+    fn insert(&self, new_federated_identity: &NewFederatedIdentity) -> DbResult<()>;
+}
+
+#[derive(Queryable)]
+pub struct FederatedIdentity {
+    pub id: i32,
+    pub provider: String,
+    pub subject: String,
+    pub user_id: i32,
+    pub email: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[table_name = "federated_identity_schema"]
+pub struct NewFederatedIdentity<'a> {
+    pub provider: &'a String,
+    pub subject: &'a String,
+    pub user_id: i32,
+    pub email: &'a String,
+}
+
+pub struct FederatedIdentityPostgresHandler {
+    pub connection: Rc<PgConnection>,
+}
+
+impl FederatedIdentityPostgresHandler {
+    pub fn new(connection: Rc<PgConnection>) -> FederatedIdentityPostgresHandler {
+        FederatedIdentityPostgresHandler { connection }
+    }
+}
+
+impl FederatedIdentityHandler for FederatedIdentityPostgresHandler {
+    fn get_by_subject(&self, provider: &String, subject: &String) -> DbResult<FederatedIdentity> {
+        Ok(federated_identity::federated_identity
+            .filter(federated_identity::provider.eq(provider))
+            .filter(federated_identity::subject.eq(subject))
+            .first::<FederatedIdentity>(self.connection.as_ref())?)
+    }
+
+    fn insert(&self, new_federated_identity: &NewFederatedIdentity) -> DbResult<()> {
+        insert_into(federated_identity::federated_identity)
+            .values(new_federated_identity)
+            .execute(self.connection.as_ref())?;
+        Ok(())
+    }
+}
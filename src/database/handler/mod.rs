@@ -3,8 +3,20 @@ use serde::export::Formatter;
 use std::error::Error;
 use std::fmt;
 
+pub mod audit_log;
 pub mod client_credential;
+pub mod device_authorization;
+pub mod domain;
+pub mod federated_identity;
+pub mod organization;
+pub mod personal_access_token;
+pub mod saml_service_provider;
+pub mod signing_key;
 pub mod url;
+pub mod url_click;
+pub mod url_revision;
+pub mod url_rule;
+pub mod url_unlock_attempt;
 pub mod user;
 
 pub type DbResult<T> = Result<T, DbError>;
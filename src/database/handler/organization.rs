@@ -0,0 +1,195 @@
+use chrono::NaiveDateTime;
+use diesel::{
+    delete, insert_into, Connection, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl,
+};
+use serde::Serialize;
+
+use crate::database::handler::{DbError, DbResult};
+use crate::schema::organization as organization_schema;
+use crate::schema::organization::dsl as organization;
+use crate::schema::organization_member as organization_member_schema;
+use crate::schema::organization_member::dsl as organization_member;
+use std::rc::Rc;
+
+pub const ORGANIZATION_ROLE_VIEWER: &str = "viewer";
+pub const ORGANIZATION_ROLE_EDITOR: &str = "editor";
+pub const ORGANIZATION_ROLE_OWNER: &str = "owner";
+pub const ORGANIZATION_ROLES: [&str; 3] = [
+    ORGANIZATION_ROLE_VIEWER,
+    ORGANIZATION_ROLE_EDITOR,
+    ORGANIZATION_ROLE_OWNER,
+];
+/// Roles allowed to create, update and delete the organization's links
+pub const ORGANIZATION_WRITE_ROLES: [&str; 2] = [ORGANIZATION_ROLE_EDITOR, ORGANIZATION_ROLE_OWNER];
+
+pub trait OrganizationHandler {
+    fn get_by_id(&self, id: i32) -> DbResult<Organization>;
+    fn get_by_username(&self, username: &String) -> DbResult<Vec<Organization>>;
+    fn create(&self, slug: &String, name: &String, owner: &String) -> DbResult<Organization>;
+    fn get_members(&self, organization_id: i32) -> DbResult<Vec<OrganizationMember>>;
+    fn get_member(&self, organization_id: i32, username: &String) -> DbResult<OrganizationMember>;
+    fn count_owners(&self, organization_id: i32) -> DbResult<i64>;
+    fn set_member(
+        &self,
+        organization_id: i32,
+        username: &String,
+        role: &String,
+    ) -> DbResult<OrganizationMember>;
+    fn remove_member_at_least_one(
+        &self,
+        organization_id: i32,
+        username: &String,
+    ) -> DbResult<usize>;
+}
+
+#[derive(Queryable, Serialize)]
+pub struct Organization {
+    pub id: i32,
+    pub slug: String,
+    pub name: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Queryable, Serialize)]
+pub struct OrganizationMember {
+    pub organization_id: i32,
+    pub username: String,
+    pub role: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl OrganizationMember {
+    pub fn is_owner(&self) -> bool {
+        self.role == ORGANIZATION_ROLE_OWNER
+    }
+
+    pub fn can_write(&self) -> bool {
+        ORGANIZATION_WRITE_ROLES.contains(&self.role.as_str())
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "organization_schema"]
+pub struct NewOrganization<'a> {
+    pub slug: &'a String,
+    pub name: &'a String,
+}
+
+#[derive(Insertable)]
+#[table_name = "organization_member_schema"]
+pub struct NewOrganizationMember<'a> {
+    pub organization_id: i32,
+    pub username: &'a String,
+    pub role: &'a str,
+}
+
+pub struct OrganizationPostgresHandler {
+    pub connection: Rc<PgConnection>,
+}
+
+impl OrganizationPostgresHandler {
+    pub fn new(connection: Rc<PgConnection>) -> OrganizationPostgresHandler {
+        OrganizationPostgresHandler { connection }
+    }
+}
+
+impl OrganizationHandler for OrganizationPostgresHandler {
+    fn get_by_id(&self, id: i32) -> DbResult<Organization> {
+        Ok(organization::organization
+            .find(id)
+            .first::<Organization>(self.connection.as_ref())?)
+    }
+
+    fn get_by_username(&self, username: &String) -> DbResult<Vec<Organization>> {
+        Ok(organization::organization
+            .filter(
+                organization::id.eq_any(
+                    organization_member::organization_member
+                        .select(organization_member::organization_id)
+                        .filter(organization_member::username.eq(username)),
+                ),
+            )
+            .order(organization::slug)
+            .load::<Organization>(self.connection.as_ref())?)
+    }
+
+    fn create(&self, slug: &String, name: &String, owner: &String) -> DbResult<Organization> {
+        let connection = self.connection.as_ref();
+        connection.transaction::<Organization, DbError, _>(|| {
+            let new_organization = insert_into(organization::organization)
+                .values(&NewOrganization { slug, name })
+                .get_result::<Organization>(connection)?;
+
+            insert_into(organization_member::organization_member)
+                .values(&NewOrganizationMember {
+                    organization_id: new_organization.id,
+                    username: owner,
+                    role: ORGANIZATION_ROLE_OWNER,
+                })
+                .execute(connection)?;
+
+            Ok(new_organization)
+        })
+    }
+
+    fn get_members(&self, organization_id: i32) -> DbResult<Vec<OrganizationMember>> {
+        Ok(organization_member::organization_member
+            .filter(organization_member::organization_id.eq(organization_id))
+            .order(organization_member::username)
+            .load::<OrganizationMember>(self.connection.as_ref())?)
+    }
+
+    fn get_member(&self, organization_id: i32, username: &String) -> DbResult<OrganizationMember> {
+        Ok(organization_member::organization_member
+            .filter(organization_member::organization_id.eq(organization_id))
+            .filter(organization_member::username.eq(username))
+            .first::<OrganizationMember>(self.connection.as_ref())?)
+    }
+
+    fn count_owners(&self, organization_id: i32) -> DbResult<i64> {
+        Ok(organization_member::organization_member
+            .filter(organization_member::organization_id.eq(organization_id))
+            .filter(organization_member::role.eq(ORGANIZATION_ROLE_OWNER))
+            .count()
+            .first::<i64>(self.connection.as_ref())?)
+    }
+
+    fn set_member(
+        &self,
+        organization_id: i32,
+        username: &String,
+        role: &String,
+    ) -> DbResult<OrganizationMember> {
+        Ok(insert_into(organization_member::organization_member)
+            .values(&NewOrganizationMember {
+                organization_id,
+                username,
+                role,
+            })
+            .on_conflict((
+                organization_member::organization_id,
+                organization_member::username,
+            ))
+            .do_update()
+            .set(organization_member::role.eq(role))
+            .get_result::<OrganizationMember>(self.connection.as_ref())?)
+    }
+
+    fn remove_member_at_least_one(
+        &self,
+        organization_id: i32,
+        username: &String,
+    ) -> DbResult<usize> {
+        let count = delete(
+            organization_member::organization_member
+                .filter(organization_member::organization_id.eq(organization_id))
+                .filter(organization_member::username.eq(username)),
+        )
+        .execute(self.connection.as_ref())?;
+        if count == 0 {
+            Err(DbError::NotFound)
+        } else {
+            Ok(count)
+        }
+    }
+}
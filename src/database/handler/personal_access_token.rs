@@ -0,0 +1,109 @@
+use chrono::NaiveDateTime;
+use diesel::{insert_into, update, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl};
+use serde::Serialize;
+
+use crate::database::handler::{DbError, DbResult};
+use crate::schema::personal_access_token as personal_access_token_schema;
+use crate::schema::personal_access_token::dsl as personal_access_token;
+use std::rc::Rc;
+
+pub trait PersonalAccessTokenHandler {
+    fn get_by_token_hash(&self, token_hash: &String) -> DbResult<PersonalAccessToken>;
+    fn get_by_user_id(&self, user_id: i32) -> DbResult<Vec<PersonalAccessToken>>;
+    fn insert(
+        &self,
+        new_personal_access_token: &NewPersonalAccessToken,
+    ) -> DbResult<PersonalAccessToken>;
+    fn touch(&self, id: i32, used_at: &NaiveDateTime) -> DbResult<usize>;
+    fn revoke(&self, id: i32, user_id: i32, revoked_at: &NaiveDateTime) -> DbResult<usize>;
+}
+
+#[derive(Queryable, Serialize)]
+pub struct PersonalAccessToken {
+    pub id: i32,
+    #[serde(skip_serializing)]
+    pub user_id: i32,
+    pub name: String,
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    pub scopes: Vec<String>,
+    pub created_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub expires_at: NaiveDateTime,
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+impl PersonalAccessToken {
+    pub fn is_usable(&self, current_time: &NaiveDateTime) -> bool {
+        self.revoked_at.is_none() && &self.expires_at > current_time
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "personal_access_token_schema"]
+pub struct NewPersonalAccessToken<'a> {
+    pub user_id: i32,
+    pub name: &'a String,
+    pub token_hash: &'a String,
+    pub scopes: &'a Vec<String>,
+    pub expires_at: &'a NaiveDateTime,
+}
+
+pub struct PersonalAccessTokenPostgresHandler {
+    pub connection: Rc<PgConnection>,
+}
+
+impl PersonalAccessTokenPostgresHandler {
+    pub fn new(connection: Rc<PgConnection>) -> PersonalAccessTokenPostgresHandler {
+        PersonalAccessTokenPostgresHandler { connection }
+    }
+}
+
+impl PersonalAccessTokenHandler for PersonalAccessTokenPostgresHandler {
+    fn get_by_token_hash(&self, token_hash: &String) -> DbResult<PersonalAccessToken> {
+        Ok(personal_access_token::personal_access_token
+            .filter(personal_access_token::token_hash.eq(token_hash))
+            .first::<PersonalAccessToken>(self.connection.as_ref())?)
+    }
+
+    fn get_by_user_id(&self, user_id: i32) -> DbResult<Vec<PersonalAccessToken>> {
+        Ok(personal_access_token::personal_access_token
+            .filter(personal_access_token::user_id.eq(user_id))
+            .filter(personal_access_token::revoked_at.is_null())
+            .order(personal_access_token::created_at.desc())
+            .load::<PersonalAccessToken>(self.connection.as_ref())?)
+    }
+
+    fn insert(
+        &self,
+        new_personal_access_token: &NewPersonalAccessToken,
+    ) -> DbResult<PersonalAccessToken> {
+        Ok(insert_into(personal_access_token::personal_access_token)
+            .values(new_personal_access_token)
+            .get_result::<PersonalAccessToken>(self.connection.as_ref())?)
+    }
+
+    fn touch(&self, id: i32, used_at: &NaiveDateTime) -> DbResult<usize> {
+        Ok(
+            update(personal_access_token::personal_access_token.find(id))
+                .set(personal_access_token::last_used_at.eq(used_at))
+                .execute(self.connection.as_ref())?,
+        )
+    }
+
+    fn revoke(&self, id: i32, user_id: i32, revoked_at: &NaiveDateTime) -> DbResult<usize> {
+        let count = update(
+            personal_access_token::personal_access_token
+                .filter(personal_access_token::id.eq(id))
+                .filter(personal_access_token::user_id.eq(user_id))
+                .filter(personal_access_token::revoked_at.is_null()),
+        )
+        .set(personal_access_token::revoked_at.eq(revoked_at))
+        .execute(self.connection.as_ref())?;
+        if count == 0 {
+            Err(DbError::NotFound)
+        } else {
+            Ok(count)
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use chrono::NaiveDateTime;
+use diesel::{delete, insert_into, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl};
+use serde::{Deserialize, Serialize};
+
+use crate::database::handler::{DbError, DbResult};
+use crate::schema::saml_service_provider as saml_service_provider_schema;
+use crate::schema::saml_service_provider::dsl as saml_service_provider;
+use std::rc::Rc;
+
+pub trait SamlServiceProviderHandler {
+    fn get_by_entity_id(&self, entity_id: &String) -> DbResult<SamlServiceProvider>;
+    fn get_all(&self) -> DbResult<Vec<SamlServiceProvider>>;
+    fn insert(&self, new_service_provider: &NewSamlServiceProvider) -> DbResult<()>;
+    fn delete_at_least_one(&self, entity_id: &String) -> DbResult<usize>;
+}
+
+#[derive(Queryable, Serialize)]
+pub struct SamlServiceProvider {
+    pub entity_id: String,
+    pub acs_url: String,
+    pub name: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Deserialize)]
+#[table_name = "saml_service_provider_schema"]
+pub struct NewSamlServiceProvider {
+    pub entity_id: String,
+    pub acs_url: String,
+    pub name: String,
+}
+
+pub struct SamlServiceProviderPostgresHandler {
+    pub connection: Rc<PgConnection>,
+}
+
+impl SamlServiceProviderPostgresHandler {
+    pub fn new(connection: Rc<PgConnection>) -> SamlServiceProviderPostgresHandler {
+        SamlServiceProviderPostgresHandler { connection }
+    }
+}
+
+impl SamlServiceProviderHandler for SamlServiceProviderPostgresHandler {
+    fn get_by_entity_id(&self, entity_id: &String) -> DbResult<SamlServiceProvider> {
+        Ok(saml_service_provider::saml_service_provider
+            .filter(saml_service_provider::entity_id.eq(entity_id))
+            .first::<SamlServiceProvider>(self.connection.as_ref())?)
+    }
+
+    fn get_all(&self) -> DbResult<Vec<SamlServiceProvider>> {
+        Ok(saml_service_provider::saml_service_provider
+            .order(saml_service_provider::entity_id)
+            .load::<SamlServiceProvider>(self.connection.as_ref())?)
+    }
+
+    fn insert(&self, new_service_provider: &NewSamlServiceProvider) -> DbResult<()> {
+        insert_into(saml_service_provider::saml_service_provider)
+            .values(new_service_provider)
+            .execute(self.connection.as_ref())?;
+        Ok(())
+    }
+
+    fn delete_at_least_one(&self, entity_id: &String) -> DbResult<usize> {
+        let count = delete(
+            saml_service_provider::saml_service_provider
+                .filter(saml_service_provider::entity_id.eq(entity_id)),
+        )
+        .execute(self.connection.as_ref())?;
+        if count == 0 {
+            Err(DbError::NotFound)
+        } else {
+            Ok(count)
+        }
+    }
+}
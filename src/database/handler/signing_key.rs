@@ -0,0 +1,106 @@
+use chrono::NaiveDateTime;
+use diesel::{
+    insert_into, update, Connection, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl,
+};
+use serde::Serialize;
+
+use crate::database::handler::{DbError, DbResult};
+use crate::schema::signing_key as signing_key_schema;
+use crate::schema::signing_key::dsl as signing_key;
+use std::rc::Rc;
+
+pub trait SigningKeyHandler {
+    fn get_active(&self) -> DbResult<SigningKey>;
+    fn get_by_id(&self, id: &String) -> DbResult<SigningKey>;
+    fn get_all(&self) -> DbResult<Vec<SigningKey>>;
+    fn insert(&self, new_signing_key: &NewSigningKey) -> DbResult<SigningKey>;
+    fn rotate(
+        &self,
+        new_signing_key: &NewSigningKey,
+        retired_expires_at: &NaiveDateTime,
+    ) -> DbResult<SigningKey>;
+}
+
+#[derive(Queryable, Serialize)]
+pub struct SigningKey {
+    pub id: String,
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub is_active: bool,
+    pub created_at: NaiveDateTime,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+impl SigningKey {
+    pub fn is_expired(&self, current_time: &NaiveDateTime) -> bool {
+        match &self.expires_at {
+            Some(expires_at) => expires_at <= current_time,
+            None => false,
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "signing_key_schema"]
+pub struct NewSigningKey<'a> {
+    pub id: &'a String,
+    pub secret: &'a String,
+    pub is_active: bool,
+}
+
+pub struct SigningKeyPostgresHandler {
+    pub connection: Rc<PgConnection>,
+}
+
+impl SigningKeyPostgresHandler {
+    pub fn new(connection: Rc<PgConnection>) -> SigningKeyPostgresHandler {
+        SigningKeyPostgresHandler { connection }
+    }
+}
+
+impl SigningKeyHandler for SigningKeyPostgresHandler {
+    fn get_active(&self) -> DbResult<SigningKey> {
+        Ok(signing_key::signing_key
+            .filter(signing_key::is_active.eq(true))
+            .order(signing_key::created_at.desc())
+            .first::<SigningKey>(self.connection.as_ref())?)
+    }
+
+    fn get_by_id(&self, id: &String) -> DbResult<SigningKey> {
+        Ok(signing_key::signing_key
+            .filter(signing_key::id.eq(id))
+            .first::<SigningKey>(self.connection.as_ref())?)
+    }
+
+    fn get_all(&self) -> DbResult<Vec<SigningKey>> {
+        Ok(signing_key::signing_key
+            .order(signing_key::created_at.desc())
+            .load::<SigningKey>(self.connection.as_ref())?)
+    }
+
+    fn insert(&self, new_signing_key: &NewSigningKey) -> DbResult<SigningKey> {
+        Ok(insert_into(signing_key::signing_key)
+            .values(new_signing_key)
+            .get_result::<SigningKey>(self.connection.as_ref())?)
+    }
+
+    fn rotate(
+        &self,
+        new_signing_key: &NewSigningKey,
+        retired_expires_at: &NaiveDateTime,
+    ) -> DbResult<SigningKey> {
+        let connection = self.connection.as_ref();
+        connection.transaction::<SigningKey, DbError, _>(|| {
+            update(signing_key::signing_key.filter(signing_key::is_active.eq(true)))
+                .set((
+                    signing_key::is_active.eq(false),
+                    signing_key::expires_at.eq(retired_expires_at),
+                ))
+                .execute(connection)?;
+
+            Ok(insert_into(signing_key::signing_key)
+                .values(new_signing_key)
+                .get_result::<SigningKey>(connection)?)
+        })
+    }
+}
@@ -1,26 +1,130 @@
-use diesel::{delete, insert_into, update, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl};
-use serde::Serialize;
+use chrono::NaiveDateTime;
+use diesel::dsl::{now, sql};
+use diesel::expression::BoxableExpression;
+use diesel::pg::Pg;
+use diesel::sql_types::Bool;
+use diesel::{
+    delete, insert_into, update, BoolExpressionMethods, Connection, ExpressionMethods,
+    NullableExpressionMethods, PgConnection, PgTextExpressionMethods, QueryDsl, RunQueryDsl,
+};
+use serde::{Deserialize, Serialize};
 
+use crate::database::handler::organization::ORGANIZATION_WRITE_ROLES;
+use crate::database::handler::url_revision::{
+    NewUrlRevision, UrlRevision, REVISION_ACTION_CREATE, REVISION_ACTION_DELETE,
+    REVISION_ACTION_RESTORE, REVISION_ACTION_ROLLBACK, REVISION_ACTION_UPDATE,
+};
 use crate::database::handler::{DbError, DbResult};
+use crate::schema::organization_member::dsl as organization_member;
+use crate::schema::tag::dsl as tag;
 use crate::schema::url as url_schema;
 use crate::schema::url::dsl as url;
+use crate::schema::url_revision::dsl as url_revision;
+use crate::schema::url_tag::dsl as url_tag;
 use std::rc::Rc;
 
+pub const REDIRECT_MOVED_PERMANENTLY: i32 = 301;
+pub const REDIRECT_FOUND: i32 = 302;
+pub const REDIRECT_TEMPORARY: i32 = 307;
+pub const REDIRECT_PERMANENT: i32 = 308;
+pub const REDIRECT_TYPES: [i32; 4] = [
+    REDIRECT_MOVED_PERMANENTLY,
+    REDIRECT_FOUND,
+    REDIRECT_TEMPORARY,
+    REDIRECT_PERMANENT,
+];
+/// Temporary so that edits to the target reach visitors who followed the link before
+pub const DEFAULT_REDIRECT_TYPE: i32 = REDIRECT_FOUND;
+
 pub trait UrlHandler {
-    fn get_by_key(&self, url_key: &String) -> DbResult<Url>;
+    fn get_by_key(&self, domain_id: Option<i32>, key: &String) -> DbResult<Url>;
     fn get_by_username(&self, username: &String, offset: i64, limit: i64) -> DbResult<Vec<Url>>;
     fn count_by_username(&self, username: &String) -> DbResult<i64>;
-    fn get_by_key_and_username(&self, key: &String, username: &String) -> DbResult<Url>;
-    fn insert(&self, key: &String, target: &String, username: &String) -> DbResult<()>;
-    fn delete(&self, key: &String, username: &String) -> DbResult<usize>;
-    fn delete_at_least_one(&self, key: &String, username: &String) -> DbResult<usize>;
+    fn search(
+        &self,
+        username: &String,
+        search: &UrlSearch,
+        cursor: Option<&UrlCursor>,
+        offset: i64,
+        limit: i64,
+    ) -> DbResult<Vec<Url>>;
+    fn count_search(&self, username: &String, search: &UrlSearch) -> DbResult<i64>;
+    fn get_by_username_after(
+        &self,
+        username: &String,
+        domain_id: Option<i32>,
+        after_key: Option<&String>,
+        limit: i64,
+    ) -> DbResult<Vec<Url>>;
+    fn get_by_organization(
+        &self,
+        organization_id: i32,
+        offset: i64,
+        limit: i64,
+    ) -> DbResult<Vec<Url>>;
+    fn count_by_organization(&self, organization_id: i32) -> DbResult<i64>;
+    fn count_by_domain(&self, domain_id: i32, deleted_after: &NaiveDateTime) -> DbResult<i64>;
+    fn get_by_key_and_username(
+        &self,
+        domain_id: Option<i32>,
+        key: &String,
+        username: &String,
+    ) -> DbResult<Url>;
+    fn get_writable_by_key(
+        &self,
+        domain_id: Option<i32>,
+        key: &String,
+        username: &String,
+    ) -> DbResult<Url>;
+    fn insert(&self, new_url: &NewUrl) -> DbResult<Url>;
+    fn insert_many(&self, new_urls: &[NewUrl]) -> DbResult<Vec<DbResult<Url>>>;
+    fn delete(&self, domain_id: Option<i32>, key: &String, username: &String) -> DbResult<usize>;
+    fn delete_at_least_one(
+        &self,
+        domain_id: Option<i32>,
+        key: &String,
+        username: &String,
+    ) -> DbResult<usize>;
+    fn restore(
+        &self,
+        domain_id: Option<i32>,
+        key: &String,
+        username: &String,
+        deleted_after: &NaiveDateTime,
+    ) -> DbResult<Url>;
+    fn purge_deleted(&self, deleted_before: &NaiveDateTime) -> DbResult<usize>;
     fn update(
         &self,
+        domain_id: Option<i32>,
         old_key: &String,
         username: &String,
-        new_key: &String,
-        target: &String,
+        changes: &UrlChanges,
+    ) -> DbResult<Url>;
+    fn rollback(&self, id: i32, username: &String, revision: &UrlRevision) -> DbResult<Url>;
+    fn set_password_hash(
+        &self,
+        id: i32,
+        username: &String,
+        password_hash: Option<&String>,
+    ) -> DbResult<Url>;
+    fn move_to_organization(
+        &self,
+        id: i32,
+        username: &String,
+        organization_id: i32,
+    ) -> DbResult<Url>;
+    fn register_click(&self, id: i32) -> DbResult<bool>;
+    fn get_disabled(&self, offset: i64, limit: i64) -> DbResult<Vec<Url>>;
+    fn count_disabled(&self) -> DbResult<i64>;
+    fn set_disabled(
+        &self,
+        domain_id: Option<i32>,
+        key: &String,
+        is_disabled: bool,
+        reason: Option<&String>,
     ) -> DbResult<Url>;
+    fn get_tags(&self, ids: &[i32]) -> DbResult<Vec<UrlTag>>;
+    fn set_tags(&self, id: i32, names: &[String]) -> DbResult<()>;
 }
 
 #[derive(Queryable, Serialize)]
@@ -28,6 +132,113 @@ pub struct Url {
     pub key: String,
     pub target: String,
     pub username: String,
+    pub organization_id: Option<i32>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub activates_at: Option<NaiveDateTime>,
+    pub max_clicks: Option<i32>,
+    pub click_count: i32,
+    pub redirect_type: i32,
+    pub is_disabled: bool,
+    pub disabled_reason: Option<String>,
+    pub always_preview: bool,
+    #[serde(skip_serializing)]
+    pub password_hash: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub description: Option<String>,
+    #[serde(skip_serializing)]
+    pub deleted_at: Option<NaiveDateTime>,
+    #[serde(skip_serializing)]
+    pub id: i32,
+    /// `None` for links on the server's own host
+    pub domain_id: Option<i32>,
+}
+
+#[derive(Queryable)]
+pub struct UrlTag {
+    pub url_id: i32,
+    pub name: String,
+}
+
+/// A link along with its tag names, as the API returns it
+#[derive(Serialize)]
+pub struct TaggedUrl {
+    #[serde(flatten)]
+    pub url: Url,
+    pub tags: Vec<String>,
+}
+
+impl Url {
+    pub fn is_active(&self, current_time: &NaiveDateTime) -> bool {
+        match &self.activates_at {
+            Some(activates_at) => activates_at <= current_time,
+            None => true,
+        }
+    }
+
+    pub fn is_permanent_redirect(&self) -> bool {
+        self.redirect_type == REDIRECT_MOVED_PERMANENTLY || self.redirect_type == REDIRECT_PERMANENT
+    }
+
+    /// Whether following the link depends on a password or a limit checked on every visit
+    pub fn is_gated(&self) -> bool {
+        self.password_hash.is_some()
+            || self.max_clicks.is_some()
+            || self.expires_at.is_some()
+            || self.activates_at.is_some()
+    }
+
+    pub fn is_expired(&self, current_time: &NaiveDateTime) -> bool {
+        match &self.expires_at {
+            Some(expires_at) => expires_at <= current_time,
+            None => false,
+        }
+    }
+}
+
+pub enum UrlStatus {
+    Active,
+    Scheduled,
+    Expired,
+    Disabled,
+}
+
+pub enum UrlSort {
+    Key,
+    Clicks,
+    Created,
+}
+
+pub struct UrlSearch<'a> {
+    pub domain_id: Option<i32>,
+    /// Matched case-insensitively anywhere in the key or the target
+    pub query: Option<&'a String>,
+    pub status: Option<UrlStatus>,
+    /// Compared after normalizing, the way tags are stored
+    pub tag: Option<String>,
+    pub created_after: Option<&'a NaiveDateTime>,
+    pub created_before: Option<&'a NaiveDateTime>,
+    pub sort: UrlSort,
+    pub is_descending: bool,
+}
+
+/// Where the previous page ended. Holds the sorted value next to the key, which breaks ties,
+/// so pages don't shift when links are added.
+#[derive(Serialize, Deserialize)]
+pub struct UrlCursor {
+    pub key: String,
+    pub click_count: i32,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<&Url> for UrlCursor {
+    fn from(url: &Url) -> Self {
+        UrlCursor {
+            key: url.key.to_owned(),
+            click_count: url.click_count,
+            created_at: url.created_at,
+        }
+    }
 }
 
 pub struct UrlPostgresHandler {
@@ -42,22 +253,202 @@ impl UrlPostgresHandler {
 
 #[derive(Insertable)]
 #[table_name = "url_schema"]
-pub struct NewUser<'a> {
+pub struct NewUrl<'a> {
     pub key: &'a String,
     pub target: &'a String,
     pub username: &'a String,
+    pub organization_id: Option<i32>,
+    pub expires_at: Option<&'a NaiveDateTime>,
+    pub activates_at: Option<&'a NaiveDateTime>,
+    pub max_clicks: Option<i32>,
+    pub redirect_type: i32,
+    pub always_preview: bool,
+    pub password_hash: Option<&'a String>,
+    pub description: Option<&'a String>,
+    pub domain_id: Option<i32>,
+}
+
+#[derive(AsChangeset)]
+#[table_name = "url_schema"]
+#[changeset_options(treat_none_as_null = "true")]
+pub struct UrlChanges<'a> {
+    pub key: &'a String,
+    pub target: &'a String,
+    pub expires_at: Option<&'a NaiveDateTime>,
+    pub activates_at: Option<&'a NaiveDateTime>,
+    pub max_clicks: Option<i32>,
+    pub redirect_type: i32,
+    pub always_preview: bool,
+    pub description: Option<&'a String>,
+}
+
+type UrlFilter = Box<dyn BoxableExpression<url_schema::table, Pg, SqlType = Bool>>;
+
+/// Personal links of `username` plus the links of every organization they are a member of
+fn readable_by(username: &String) -> UrlFilter {
+    Box::new(
+        url::deleted_at.is_null().and(
+            url::username
+                .eq(username.to_owned())
+                .and(url::organization_id.is_null())
+                .or(url::organization_id.eq_any(
+                    organization_member::organization_member
+                        .select(organization_member::organization_id.nullable())
+                        .filter(organization_member::username.eq(username.to_owned())),
+                )),
+        ),
+    )
+}
+
+/// Personal links of `username` plus the links of organizations where they may edit links
+fn writable_by(username: &String) -> UrlFilter {
+    Box::new(url::deleted_at.is_null().and(write_access(username)))
+}
+
+/// Same as `writable_by`, deleted links included
+fn write_access(username: &String) -> UrlFilter {
+    Box::new(
+        url::username
+            .eq(username.to_owned())
+            .and(url::organization_id.is_null())
+            .or(url::organization_id.eq_any(
+                organization_member::organization_member
+                    .select(organization_member::organization_id.nullable())
+                    .filter(organization_member::username.eq(username.to_owned()))
+                    .filter(organization_member::role.eq_any(ORGANIZATION_WRITE_ROLES.to_vec())),
+            )),
+    )
+}
+
+/// Keys are only unique within a domain
+fn in_domain(domain_id: Option<i32>) -> UrlFilter {
+    match domain_id {
+        Some(domain_id) => Box::new(url::domain_id.eq(domain_id)),
+        None => Box::new(url::domain_id.is_null()),
+    }
+}
+
+fn search_query<'a>(username: &String, search: &UrlSearch) -> url_schema::BoxedQuery<'a, Pg> {
+    let mut query = url::url
+        .into_boxed()
+        .filter(readable_by(username))
+        .filter(in_domain(search.domain_id));
+    if let Some(text) = search.query {
+        let pattern = format!("%{}%", escape_like(text));
+        query = query.filter(
+            url::key
+                .ilike(pattern.clone())
+                .or(url::target.ilike(pattern)),
+        );
+    }
+    if let Some(status) = &search.status {
+        query = query.filter(status_filter(status));
+    }
+    if let Some(name) = &search.tag {
+        query = query.filter(
+            url::id.eq_any(
+                url_tag::url_tag
+                    .inner_join(tag::tag)
+                    .filter(tag::name.eq(name.to_owned()))
+                    .select(url_tag::url_id),
+            ),
+        );
+    }
+    if let Some(created_after) = search.created_after {
+        query = query.filter(url::created_at.ge(created_after.to_owned()));
+    }
+    if let Some(created_before) = search.created_before {
+        query = query.filter(url::created_at.lt(created_before.to_owned()));
+    }
+    query
+}
+
+/// Compared against the database clock in UTC, which is how timestamps are stored
+fn status_filter(status: &UrlStatus) -> UrlFilter {
+    Box::new(sql::<Bool>(match status {
+        UrlStatus::Active => {
+            "NOT is_disabled \
+             AND (activates_at IS NULL OR activates_at <= (now() AT TIME ZONE 'UTC')) \
+             AND (expires_at IS NULL OR expires_at > (now() AT TIME ZONE 'UTC')) \
+             AND (max_clicks IS NULL OR click_count < max_clicks)"
+        }
+        UrlStatus::Scheduled => "NOT is_disabled AND activates_at > (now() AT TIME ZONE 'UTC')",
+        UrlStatus::Expired => {
+            "NOT is_disabled \
+             AND (expires_at <= (now() AT TIME ZONE 'UTC') OR click_count >= max_clicks)"
+        }
+        UrlStatus::Disabled => "is_disabled",
+    }))
+}
+
+fn after_cursor(search: &UrlSearch, cursor: &UrlCursor) -> UrlFilter {
+    let key = cursor.key.to_owned();
+    let click_count = cursor.click_count;
+    let created_at = cursor.created_at;
+    match (&search.sort, search.is_descending) {
+        (UrlSort::Key, false) => Box::new(url::key.gt(key)),
+        (UrlSort::Key, true) => Box::new(url::key.lt(key)),
+        (UrlSort::Clicks, false) => Box::new(
+            url::click_count
+                .gt(click_count)
+                .or(url::click_count.eq(click_count).and(url::key.gt(key))),
+        ),
+        (UrlSort::Clicks, true) => Box::new(
+            url::click_count
+                .lt(click_count)
+                .or(url::click_count.eq(click_count).and(url::key.lt(key))),
+        ),
+        (UrlSort::Created, false) => Box::new(
+            url::created_at
+                .gt(created_at)
+                .or(url::created_at.eq(created_at).and(url::key.gt(key))),
+        ),
+        (UrlSort::Created, true) => Box::new(
+            url::created_at
+                .lt(created_at)
+                .or(url::created_at.eq(created_at).and(url::key.lt(key))),
+        ),
+    }
+}
+
+fn escape_like(text: &String) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+fn insert_with_revision(connection: &PgConnection, new_url: &NewUrl) -> DbResult<Url> {
+    let url = insert_into(url::url)
+        .values(new_url)
+        .get_result::<Url>(connection)?;
+    record_revision(connection, &url, new_url.username, REVISION_ACTION_CREATE)?;
+    Ok(url)
+}
+
+fn record_revision(
+    connection: &PgConnection,
+    url: &Url,
+    changed_by: &String,
+    action: &str,
+) -> DbResult<()> {
+    insert_into(url_revision::url_revision)
+        .values(&NewUrlRevision::new(url, changed_by, action))
+        .execute(connection)?;
+    Ok(())
 }
 
 impl UrlHandler for UrlPostgresHandler {
-    fn get_by_key(&self, url_key: &String) -> DbResult<Url> {
+    fn get_by_key(&self, domain_id: Option<i32>, key: &String) -> DbResult<Url> {
         Ok(url::url
-            .filter(url::key.eq(url_key))
+            .filter(url::key.eq(key))
+            .filter(in_domain(domain_id))
+            .filter(url::deleted_at.is_null())
             .first::<Url>(self.connection.as_ref())?)
     }
 
     fn get_by_username(&self, username: &String, offset: i64, limit: i64) -> DbResult<Vec<Url>> {
         Ok(url::url
-            .filter(url::username.eq(username))
+            .filter(readable_by(username))
             .order(url::key)
             .offset(offset)
             .limit(limit)
@@ -66,42 +457,177 @@ impl UrlHandler for UrlPostgresHandler {
 
     fn count_by_username(&self, username: &String) -> DbResult<i64> {
         Ok(url::url
-            .filter(url::username.eq(username))
+            .filter(readable_by(username))
+            .count()
+            .first::<i64>(self.connection.as_ref())?)
+    }
+
+    fn search(
+        &self,
+        username: &String,
+        search: &UrlSearch,
+        cursor: Option<&UrlCursor>,
+        offset: i64,
+        limit: i64,
+    ) -> DbResult<Vec<Url>> {
+        let mut query = search_query(username, search);
+        if let Some(cursor) = cursor {
+            query = query.filter(after_cursor(search, cursor));
+        }
+        query = match (&search.sort, search.is_descending) {
+            (UrlSort::Key, false) => query.order(url::key.asc()),
+            (UrlSort::Key, true) => query.order(url::key.desc()),
+            (UrlSort::Clicks, false) => query.order((url::click_count.asc(), url::key.asc())),
+            (UrlSort::Clicks, true) => query.order((url::click_count.desc(), url::key.desc())),
+            (UrlSort::Created, false) => query.order((url::created_at.asc(), url::key.asc())),
+            (UrlSort::Created, true) => query.order((url::created_at.desc(), url::key.desc())),
+        };
+
+        Ok(query
+            .offset(offset)
+            .limit(limit)
+            .load::<Url>(self.connection.as_ref())?)
+    }
+
+    fn count_search(&self, username: &String, search: &UrlSearch) -> DbResult<i64> {
+        Ok(search_query(username, search)
+            .count()
+            .get_result::<i64>(self.connection.as_ref())?)
+    }
+
+    fn get_by_username_after(
+        &self,
+        username: &String,
+        domain_id: Option<i32>,
+        after_key: Option<&String>,
+        limit: i64,
+    ) -> DbResult<Vec<Url>> {
+        let mut query = url::url
+            .into_boxed()
+            .filter(readable_by(username))
+            .filter(in_domain(domain_id));
+        if let Some(after_key) = after_key {
+            query = query.filter(url::key.gt(after_key.to_owned()));
+        }
+
+        Ok(query
+            .order(url::key)
+            .limit(limit)
+            .load::<Url>(self.connection.as_ref())?)
+    }
+
+    fn get_by_organization(
+        &self,
+        organization_id: i32,
+        offset: i64,
+        limit: i64,
+    ) -> DbResult<Vec<Url>> {
+        Ok(url::url
+            .filter(url::organization_id.eq(organization_id))
+            .filter(url::deleted_at.is_null())
+            .order(url::key)
+            .offset(offset)
+            .limit(limit)
+            .load::<Url>(self.connection.as_ref())?)
+    }
+
+    fn count_by_organization(&self, organization_id: i32) -> DbResult<i64> {
+        Ok(url::url
+            .filter(url::organization_id.eq(organization_id))
+            .filter(url::deleted_at.is_null())
             .count()
             .first::<i64>(self.connection.as_ref())?)
     }
 
-    fn get_by_key_and_username(&self, key: &String, username: &String) -> DbResult<Url> {
+    /// Deleted links that can still be restored included, they hold on to the domain
+    fn count_by_domain(&self, domain_id: i32, deleted_after: &NaiveDateTime) -> DbResult<i64> {
+        Ok(url::url
+            .filter(url::domain_id.eq(domain_id))
+            .filter(
+                url::deleted_at
+                    .is_null()
+                    .or(url::deleted_at.ge(deleted_after.to_owned())),
+            )
+            .count()
+            .first::<i64>(self.connection.as_ref())?)
+    }
+
+    fn get_by_key_and_username(
+        &self,
+        domain_id: Option<i32>,
+        key: &String,
+        username: &String,
+    ) -> DbResult<Url> {
         Ok(url::url
             .filter(url::key.eq(key))
-            .filter(url::username.eq(username))
+            .filter(in_domain(domain_id))
+            .filter(readable_by(username))
             .first::<Url>(self.connection.as_ref())?)
     }
 
-    fn insert(&self, key: &String, target: &String, username: &String) -> DbResult<()> {
-        let new_user = NewUser {
-            key,
-            target,
-            username,
-        };
-        insert_into(url::url)
-            .values(new_user)
-            .execute(self.connection.as_ref())?;
-        Ok(())
+    fn get_writable_by_key(
+        &self,
+        domain_id: Option<i32>,
+        key: &String,
+        username: &String,
+    ) -> DbResult<Url> {
+        Ok(url::url
+            .filter(url::key.eq(key))
+            .filter(in_domain(domain_id))
+            .filter(writable_by(username))
+            .first::<Url>(self.connection.as_ref())?)
     }
 
-    fn delete(&self, key: &String, username: &String) -> DbResult<usize> {
-        let count = delete(
-            url::url
-                .filter(url::key.eq(key))
-                .filter(url::username.eq(username)),
-        )
-        .execute(self.connection.as_ref())?;
-        Ok(count)
+    fn insert(&self, new_url: &NewUrl) -> DbResult<Url> {
+        let connection = self.connection.as_ref();
+        connection.transaction::<Url, DbError, _>(|| insert_with_revision(connection, new_url))
     }
 
-    fn delete_at_least_one(&self, key: &String, username: &String) -> DbResult<usize> {
-        let count = self.delete(key, username)?;
+    fn insert_many(&self, new_urls: &[NewUrl]) -> DbResult<Vec<DbResult<Url>>> {
+        let connection = self.connection.as_ref();
+        connection.transaction::<Vec<DbResult<Url>>, DbError, _>(|| {
+            let mut results = Vec::with_capacity(new_urls.len());
+            for new_url in new_urls {
+                // Each row gets its own savepoint so a duplicate doesn't abort the others
+                let result = connection
+                    .transaction::<Url, DbError, _>(|| insert_with_revision(connection, new_url));
+                match result {
+                    Err(DbError::InternalError(e)) => return Err(DbError::InternalError(e)),
+                    result => results.push(result),
+                }
+            }
+            Ok(results)
+        })
+    }
+
+    /// Only marks the link as deleted, `purge_deleted` removes it for good. Its key is free for
+    /// new links right away.
+    fn delete(&self, domain_id: Option<i32>, key: &String, username: &String) -> DbResult<usize> {
+        let connection = self.connection.as_ref();
+        connection.transaction::<usize, DbError, _>(|| {
+            let deleted = update(
+                url::url
+                    .filter(url::key.eq(key))
+                    .filter(in_domain(domain_id))
+                    .filter(writable_by(username)),
+            )
+            .set(url::deleted_at.eq(now.nullable()))
+            .get_results::<Url>(connection)?;
+
+            for url in &deleted {
+                record_revision(connection, url, username, REVISION_ACTION_DELETE)?;
+            }
+            Ok(deleted.len())
+        })
+    }
+
+    fn delete_at_least_one(
+        &self,
+        domain_id: Option<i32>,
+        key: &String,
+        username: &String,
+    ) -> DbResult<usize> {
+        let count = self.delete(domain_id, key, username)?;
         if count == 0 {
             Err(DbError::NotFound)
         } else {
@@ -109,21 +635,298 @@ impl UrlHandler for UrlPostgresHandler {
         }
     }
 
+    fn restore(
+        &self,
+        domain_id: Option<i32>,
+        key: &String,
+        username: &String,
+        deleted_after: &NaiveDateTime,
+    ) -> DbResult<Url> {
+        let connection = self.connection.as_ref();
+        connection.transaction::<Url, DbError, _>(|| {
+            // The key may have been deleted more than once, the latest deletion is undone. A
+            // link created with the key since makes this fail with `DuplicateKey`.
+            let id = url::url
+                .select(url::id)
+                .filter(url::key.eq(key))
+                .filter(in_domain(domain_id))
+                .filter(url::deleted_at.ge(deleted_after.to_owned()))
+                .filter(write_access(username))
+                .order(url::deleted_at.desc())
+                .first::<i32>(connection)?;
+
+            let url = update(url::url.filter(url::id.eq(id)))
+                .set(url::deleted_at.eq(None::<NaiveDateTime>))
+                .get_result::<Url>(connection)?;
+
+            record_revision(connection, &url, username, REVISION_ACTION_RESTORE)?;
+            Ok(url)
+        })
+    }
+
+    fn purge_deleted(&self, deleted_before: &NaiveDateTime) -> DbResult<usize> {
+        Ok(
+            delete(url::url.filter(url::deleted_at.lt(deleted_before.to_owned())))
+                .execute(self.connection.as_ref())?,
+        )
+    }
+
     fn update(
         &self,
+        domain_id: Option<i32>,
         old_key: &String,
         username: &String,
-        new_key: &String,
-        target: &String,
+        changes: &UrlChanges,
+    ) -> DbResult<Url> {
+        let connection = self.connection.as_ref();
+        connection.transaction::<Url, DbError, _>(|| {
+            let url = update(
+                url::url
+                    .filter(url::key.eq(old_key))
+                    .filter(in_domain(domain_id))
+                    .filter(writable_by(username)),
+            )
+            .set((changes, url::updated_at.eq(now)))
+            .get_result::<Url>(connection)?;
+
+            record_revision(connection, &url, username, REVISION_ACTION_UPDATE)?;
+            Ok(url)
+        })
+    }
+
+    fn rollback(&self, id: i32, username: &String, revision: &UrlRevision) -> DbResult<Url> {
+        let changes = UrlChanges {
+            key: &revision.key,
+            target: &revision.target,
+            expires_at: revision.expires_at.as_ref(),
+            activates_at: revision.activates_at.as_ref(),
+            max_clicks: revision.max_clicks,
+            redirect_type: revision.redirect_type,
+            always_preview: revision.always_preview,
+            description: revision.description.as_ref(),
+        };
+
+        let connection = self.connection.as_ref();
+        connection.transaction::<Url, DbError, _>(|| {
+            let url = update(
+                url::url
+                    .filter(url::id.eq(id))
+                    .filter(writable_by(username)),
+            )
+            .set((&changes, url::updated_at.eq(now)))
+            .get_result::<Url>(connection)?;
+
+            record_revision(connection, &url, username, REVISION_ACTION_ROLLBACK)?;
+            Ok(url)
+        })
+    }
+
+    fn set_password_hash(
+        &self,
+        id: i32,
+        username: &String,
+        password_hash: Option<&String>,
+    ) -> DbResult<Url> {
+        Ok(update(
+            url::url
+                .filter(url::id.eq(id))
+                .filter(writable_by(username)),
+        )
+        .set((
+            url::password_hash.eq(password_hash),
+            url::updated_at.eq(now),
+        ))
+        .get_result::<Url>(self.connection.as_ref())?)
+    }
+
+    fn move_to_organization(
+        &self,
+        id: i32,
+        username: &String,
+        organization_id: i32,
     ) -> DbResult<Url> {
-        let result = update(
+        Ok(update(
             url::url
-                .filter(url::key.eq(old_key))
-                .filter(url::username.eq(username)),
+                .filter(url::id.eq(id))
+                .filter(url::username.eq(username))
+                .filter(url::organization_id.is_null())
+                .filter(url::deleted_at.is_null()),
         )
-        .set((url::key.eq(new_key), url::target.eq(target)))
-        .get_result::<Url>(self.connection.as_ref())?;
+        .set((
+            url::organization_id.eq(organization_id),
+            url::updated_at.eq(now),
+        ))
+        .get_result::<Url>(self.connection.as_ref())?)
+    }
+
+    fn register_click(&self, id: i32) -> DbResult<bool> {
+        // Checking and counting in one statement keeps concurrent visitors within max_clicks
+        let count = update(
+            url::url.filter(url::id.eq(id)).filter(
+                url::max_clicks
+                    .is_null()
+                    .nullable()
+                    .or(url::click_count.nullable().lt(url::max_clicks)),
+            ),
+        )
+        .set(url::click_count.eq(url::click_count + 1))
+        .execute(self.connection.as_ref())?;
+
+        Ok(count > 0)
+    }
+
+    fn get_disabled(&self, offset: i64, limit: i64) -> DbResult<Vec<Url>> {
+        Ok(url::url
+            .filter(url::is_disabled.eq(true))
+            .filter(url::deleted_at.is_null())
+            .order(url::key)
+            .offset(offset)
+            .limit(limit)
+            .load::<Url>(self.connection.as_ref())?)
+    }
+
+    fn count_disabled(&self) -> DbResult<i64> {
+        Ok(url::url
+            .filter(url::is_disabled.eq(true))
+            .filter(url::deleted_at.is_null())
+            .count()
+            .first::<i64>(self.connection.as_ref())?)
+    }
+
+    fn set_disabled(
+        &self,
+        domain_id: Option<i32>,
+        key: &String,
+        is_disabled: bool,
+        reason: Option<&String>,
+    ) -> DbResult<Url> {
+        Ok(update(
+            url::url
+                .filter(url::key.eq(key))
+                .filter(in_domain(domain_id))
+                .filter(url::deleted_at.is_null()),
+        )
+        .set((
+            url::is_disabled.eq(is_disabled),
+            url::disabled_reason.eq(reason),
+        ))
+        .get_result::<Url>(self.connection.as_ref())?)
+    }
+
+    fn get_tags(&self, ids: &[i32]) -> DbResult<Vec<UrlTag>> {
+        Ok(url_tag::url_tag
+            .inner_join(tag::tag)
+            .filter(url_tag::url_id.eq_any(ids))
+            .select((url_tag::url_id, tag::name))
+            .order(tag::name)
+            .load::<UrlTag>(self.connection.as_ref())?)
+    }
+
+    fn set_tags(&self, id: i32, names: &[String]) -> DbResult<()> {
+        let connection = self.connection.as_ref();
+        connection.transaction::<(), DbError, _>(|| {
+            delete(url_tag::url_tag.filter(url_tag::url_id.eq(id))).execute(connection)?;
+            if names.is_empty() {
+                return Ok(());
+            }
+
+            let new_tags: Vec<_> = names.iter().map(|name| tag::name.eq(name)).collect();
+            insert_into(tag::tag)
+                .values(&new_tags)
+                .on_conflict(tag::name)
+                .do_nothing()
+                .execute(connection)?;
+
+            let tag_ids = tag::tag
+                .filter(tag::name.eq_any(names))
+                .select(tag::id)
+                .load::<i32>(connection)?;
+            let new_url_tags: Vec<_> = tag_ids
+                .into_iter()
+                .map(|tag_id| (url_tag::url_id.eq(id), url_tag::tag_id.eq(tag_id)))
+                .collect();
+            insert_into(url_tag::url_tag)
+                .values(&new_url_tags)
+                .execute(connection)?;
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use diesel::debug_query;
+
+    use super::*;
+
+    fn search(sort: UrlSort, is_descending: bool) -> UrlSearch<'static> {
+        UrlSearch {
+            domain_id: None,
+            query: None,
+            status: None,
+            tag: None,
+            created_after: None,
+            created_before: None,
+            sort,
+            is_descending,
+        }
+    }
+
+    fn cursor() -> UrlCursor {
+        UrlCursor {
+            key: "m".to_owned(),
+            click_count: 5,
+            created_at: NaiveDate::from_ymd(2020, 7, 1).and_hms(12, 0, 0),
+        }
+    }
+
+    fn where_clause(search: &UrlSearch) -> String {
+        let sql =
+            debug_query::<Pg, _>(&url::url.filter(after_cursor(search, &cursor()))).to_string();
+        sql[sql.find(" WHERE ").unwrap()..].to_owned()
+    }
+
+    #[test]
+    fn continues_after_key() {
+        assert_eq!(
+            where_clause(&search(UrlSort::Key, false)),
+            " WHERE \"url\".\"key\" > $1 -- binds: [\"m\"]"
+        );
+        assert_eq!(
+            where_clause(&search(UrlSort::Key, true)),
+            " WHERE \"url\".\"key\" < $1 -- binds: [\"m\"]"
+        );
+    }
+
+    #[test]
+    fn breaks_click_count_ties_by_key() {
+        assert_eq!(
+            where_clause(&search(UrlSort::Clicks, false)),
+            " WHERE (\"url\".\"click_count\" > $1 OR \"url\".\"click_count\" = $2 \
+             AND \"url\".\"key\" > $3) -- binds: [5, 5, \"m\"]"
+        );
+        assert_eq!(
+            where_clause(&search(UrlSort::Clicks, true)),
+            " WHERE (\"url\".\"click_count\" < $1 OR \"url\".\"click_count\" = $2 \
+             AND \"url\".\"key\" < $3) -- binds: [5, 5, \"m\"]"
+        );
+    }
+
+    #[test]
+    fn breaks_creation_time_ties_by_key() {
+        assert_eq!(
+            where_clause(&search(UrlSort::Created, true)),
+            " WHERE (\"url\".\"created_at\" < $1 OR \"url\".\"created_at\" = $2 \
+             AND \"url\".\"key\" < $3) -- binds: [2020-07-01T12:00:00, 2020-07-01T12:00:00, \"m\"]"
+        );
+    }
 
-        Ok(result)
+    #[test]
+    fn escapes_like_wildcards() {
+        assert_eq!(escape_like(&"50%_off".to_owned()), "50\\%\\_off");
+        assert_eq!(escape_like(&"a\\%".to_owned()), "a\\\\\\%");
+        assert_eq!(escape_like(&"plain".to_owned()), "plain");
     }
 }
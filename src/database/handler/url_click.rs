@@ -0,0 +1,138 @@
+use chrono::NaiveDateTime;
+use diesel::sql_types::{BigInt, Integer, Nullable, Text, Timestamp, Varchar};
+use diesel::{insert_into, sql_query, PgConnection, RunQueryDsl};
+use serde::Serialize;
+
+use crate::database::handler::DbResult;
+use crate::schema::url_click as url_click_schema;
+use crate::schema::url_click::dsl as url_click;
+use std::rc::Rc;
+
+pub trait UrlClickHandler {
+    fn insert(&self, new_url_click: &NewUrlClick) -> DbResult<()>;
+    fn get_summary(&self, url_id: i32, since: &NaiveDateTime) -> DbResult<ClickSummary>;
+    fn get_series(
+        &self,
+        url_id: i32,
+        bucket: &str,
+        since: &NaiveDateTime,
+    ) -> DbResult<Vec<ClickBucket>>;
+    fn get_countries(&self, url_id: i32, since: &NaiveDateTime) -> DbResult<Vec<CountryClicks>>;
+    fn get_rules(&self, url_id: i32, since: &NaiveDateTime) -> DbResult<Vec<RuleClicks>>;
+}
+
+#[derive(Insertable)]
+#[table_name = "url_click_schema"]
+pub struct NewUrlClick<'a> {
+    pub url_id: i32,
+    pub referrer: Option<&'a String>,
+    pub user_agent: Option<&'a String>,
+    pub country: Option<&'a String>,
+    pub ip_hash: &'a String,
+    pub rule_id: Option<i32>,
+}
+
+#[derive(QueryableByName, Serialize)]
+pub struct ClickSummary {
+    #[sql_type = "BigInt"]
+    pub clicks: i64,
+    #[sql_type = "BigInt"]
+    pub unique_visitors: i64,
+}
+
+#[derive(QueryableByName, Serialize)]
+pub struct ClickBucket {
+    #[sql_type = "Timestamp"]
+    pub bucket: NaiveDateTime,
+    #[sql_type = "BigInt"]
+    pub clicks: i64,
+    #[sql_type = "BigInt"]
+    pub unique_visitors: i64,
+}
+
+#[derive(QueryableByName, Serialize)]
+pub struct CountryClicks {
+    #[sql_type = "Nullable<Varchar>"]
+    pub country: Option<String>,
+    #[sql_type = "BigInt"]
+    pub clicks: i64,
+}
+
+/// Clicks per redirect rule, `None` for visitors who got the link's own target or whose rule
+/// has been removed since
+#[derive(QueryableByName, Serialize)]
+pub struct RuleClicks {
+    #[sql_type = "Nullable<Integer>"]
+    pub rule_id: Option<i32>,
+    #[sql_type = "BigInt"]
+    pub clicks: i64,
+}
+
+pub struct UrlClickPostgresHandler {
+    pub connection: Rc<PgConnection>,
+}
+
+impl UrlClickPostgresHandler {
+    pub fn new(connection: Rc<PgConnection>) -> UrlClickPostgresHandler {
+        UrlClickPostgresHandler { connection }
+    }
+}
+
+impl UrlClickHandler for UrlClickPostgresHandler {
+    fn insert(&self, new_url_click: &NewUrlClick) -> DbResult<()> {
+        insert_into(url_click::url_click)
+            .values(new_url_click)
+            .execute(self.connection.as_ref())?;
+        Ok(())
+    }
+
+    fn get_summary(&self, url_id: i32, since: &NaiveDateTime) -> DbResult<ClickSummary> {
+        Ok(sql_query(
+            "SELECT COUNT(*) AS clicks, COUNT(DISTINCT ip_hash) AS unique_visitors \
+             FROM url_click WHERE url_id = $1 AND clicked_at >= $2",
+        )
+        .bind::<Integer, _>(url_id)
+        .bind::<Timestamp, _>(since)
+        .get_result::<ClickSummary>(self.connection.as_ref())?)
+    }
+
+    fn get_series(
+        &self,
+        url_id: i32,
+        bucket: &str,
+        since: &NaiveDateTime,
+    ) -> DbResult<Vec<ClickBucket>> {
+        Ok(sql_query(
+            "SELECT date_trunc($1, clicked_at) AS bucket, COUNT(*) AS clicks, \
+             COUNT(DISTINCT ip_hash) AS unique_visitors \
+             FROM url_click WHERE url_id = $2 AND clicked_at >= $3 \
+             GROUP BY 1 ORDER BY 1",
+        )
+        .bind::<Text, _>(bucket)
+        .bind::<Integer, _>(url_id)
+        .bind::<Timestamp, _>(since)
+        .load::<ClickBucket>(self.connection.as_ref())?)
+    }
+
+    fn get_countries(&self, url_id: i32, since: &NaiveDateTime) -> DbResult<Vec<CountryClicks>> {
+        Ok(sql_query(
+            "SELECT country, COUNT(*) AS clicks \
+             FROM url_click WHERE url_id = $1 AND clicked_at >= $2 \
+             GROUP BY country ORDER BY clicks DESC",
+        )
+        .bind::<Integer, _>(url_id)
+        .bind::<Timestamp, _>(since)
+        .load::<CountryClicks>(self.connection.as_ref())?)
+    }
+
+    fn get_rules(&self, url_id: i32, since: &NaiveDateTime) -> DbResult<Vec<RuleClicks>> {
+        Ok(sql_query(
+            "SELECT rule_id, COUNT(*) AS clicks \
+             FROM url_click WHERE url_id = $1 AND clicked_at >= $2 \
+             GROUP BY rule_id ORDER BY clicks DESC",
+        )
+        .bind::<Integer, _>(url_id)
+        .bind::<Timestamp, _>(since)
+        .load::<RuleClicks>(self.connection.as_ref())?)
+    }
+}
@@ -0,0 +1,99 @@
+use chrono::NaiveDateTime;
+use diesel::{ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl};
+use serde::Serialize;
+
+use crate::database::handler::url::Url;
+use crate::database::handler::DbResult;
+use crate::schema::url_revision as url_revision_schema;
+use crate::schema::url_revision::dsl as url_revision;
+use std::rc::Rc;
+
+pub const REVISION_ACTION_CREATE: &str = "create";
+pub const REVISION_ACTION_UPDATE: &str = "update";
+pub const REVISION_ACTION_DELETE: &str = "delete";
+pub const REVISION_ACTION_RESTORE: &str = "restore";
+pub const REVISION_ACTION_ROLLBACK: &str = "rollback";
+
+pub trait UrlRevisionHandler {
+    fn get_by_url_id(&self, url_id: i32) -> DbResult<Vec<UrlRevision>>;
+    fn get_by_id(&self, url_id: i32, id: i32) -> DbResult<UrlRevision>;
+}
+
+/// A link as it was right after a change. Tags and passwords aren't part of the history.
+#[derive(Queryable, Serialize)]
+pub struct UrlRevision {
+    pub id: i32,
+    pub changed_by: String,
+    pub action: String,
+    pub key: String,
+    pub target: String,
+    pub expires_at: Option<NaiveDateTime>,
+    pub activates_at: Option<NaiveDateTime>,
+    pub max_clicks: Option<i32>,
+    pub redirect_type: i32,
+    pub always_preview: bool,
+    pub description: Option<String>,
+    pub created_at: NaiveDateTime,
+    #[serde(skip_serializing)]
+    pub url_id: i32,
+}
+
+#[derive(Insertable)]
+#[table_name = "url_revision_schema"]
+pub struct NewUrlRevision<'a> {
+    pub url_id: i32,
+    pub changed_by: &'a String,
+    pub action: &'a str,
+    pub key: &'a String,
+    pub target: &'a String,
+    pub expires_at: Option<&'a NaiveDateTime>,
+    pub activates_at: Option<&'a NaiveDateTime>,
+    pub max_clicks: Option<i32>,
+    pub redirect_type: i32,
+    pub always_preview: bool,
+    pub description: Option<&'a String>,
+}
+
+impl<'a> NewUrlRevision<'a> {
+    pub fn new(url: &'a Url, changed_by: &'a String, action: &'a str) -> NewUrlRevision<'a> {
+        NewUrlRevision {
+            url_id: url.id,
+            changed_by,
+            action,
+            key: &url.key,
+            target: &url.target,
+            expires_at: url.expires_at.as_ref(),
+            activates_at: url.activates_at.as_ref(),
+            max_clicks: url.max_clicks,
+            redirect_type: url.redirect_type,
+            always_preview: url.always_preview,
+            description: url.description.as_ref(),
+        }
+    }
+}
+
+pub struct UrlRevisionPostgresHandler {
+    pub connection: Rc<PgConnection>,
+}
+
+impl UrlRevisionPostgresHandler {
+    pub fn new(connection: Rc<PgConnection>) -> UrlRevisionPostgresHandler {
+        UrlRevisionPostgresHandler { connection }
+    }
+}
+
+impl UrlRevisionHandler for UrlRevisionPostgresHandler {
+    fn get_by_url_id(&self, url_id: i32) -> DbResult<Vec<UrlRevision>> {
+        Ok(url_revision::url_revision
+            .filter(url_revision::url_id.eq(url_id))
+            .order(url_revision::id.desc())
+            .load::<UrlRevision>(self.connection.as_ref())?)
+    }
+
+    fn get_by_id(&self, url_id: i32, id: i32) -> DbResult<UrlRevision> {
+        Ok(url_revision::url_revision
+            .filter(url_revision::url_id.eq(url_id))
+            .filter(url_revision::id.eq(id))
+            .first::<UrlRevision>(self.connection.as_ref())?)
+    }
+}
@@ -0,0 +1,121 @@
+use chrono::NaiveDateTime;
+use diesel::{
+    delete, insert_into, update, Connection, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl,
+};
+use serde::Serialize;
+
+use crate::database::handler::{DbError, DbResult};
+use crate::schema::url_rule::dsl as url_rule;
+use std::rc::Rc;
+
+/// `value` lists `ios`, `android`, `mobile` or `desktop`
+pub const RULE_KIND_DEVICE: &str = "device";
+/// `value` lists language tags, matched against the visitor's preferred language
+pub const RULE_KIND_LANGUAGE: &str = "language";
+/// `value` lists ISO country codes, looked up in the GeoIP database
+pub const RULE_KIND_COUNTRY: &str = "country";
+/// `value` is the percentage of visitors sent to the target
+pub const RULE_KIND_SPLIT: &str = "split";
+pub const RULE_KINDS: [&str; 4] = [
+    RULE_KIND_DEVICE,
+    RULE_KIND_LANGUAGE,
+    RULE_KIND_COUNTRY,
+    RULE_KIND_SPLIT,
+];
+
+pub trait UrlRuleHandler {
+    fn get_by_url_id(&self, url_id: i32) -> DbResult<Vec<UrlRule>>;
+    fn set_rules(&self, url_id: i32, rules: &[UrlRuleChanges]) -> DbResult<Vec<UrlRule>>;
+}
+
+/// Sends visitors matching it to `target` instead of the link's own. A link's rules are tried
+/// by `position` and the first match wins.
+#[derive(Queryable, Serialize)]
+pub struct UrlRule {
+    pub id: i32,
+    #[serde(skip_serializing)]
+    pub url_id: i32,
+    pub position: i32,
+    pub kind: String,
+    pub value: String,
+    pub target: String,
+    pub created_at: NaiveDateTime,
+}
+
+pub struct UrlRuleChanges<'a> {
+    /// An existing rule of the link, `None` creates a new one
+    pub id: Option<i32>,
+    pub position: i32,
+    pub kind: &'a String,
+    pub value: &'a String,
+    pub target: &'a String,
+}
+
+pub struct UrlRulePostgresHandler {
+    pub connection: Rc<PgConnection>,
+}
+
+impl UrlRulePostgresHandler {
+    pub fn new(connection: Rc<PgConnection>) -> UrlRulePostgresHandler {
+        UrlRulePostgresHandler { connection }
+    }
+}
+
+impl UrlRuleHandler for UrlRulePostgresHandler {
+    fn get_by_url_id(&self, url_id: i32) -> DbResult<Vec<UrlRule>> {
+        Ok(url_rule::url_rule
+            .filter(url_rule::url_id.eq(url_id))
+            .order(url_rule::position)
+            .load::<UrlRule>(self.connection.as_ref())?)
+    }
+
+    /// Replaces the link's rules with `rules`. Rules that keep their id are updated in place so
+    /// their click statistics carry over.
+    fn set_rules(&self, url_id: i32, rules: &[UrlRuleChanges]) -> DbResult<Vec<UrlRule>> {
+        let connection = self.connection.as_ref();
+        connection.transaction::<Vec<UrlRule>, DbError, _>(|| {
+            let kept_ids: Vec<i32> = rules.iter().filter_map(|rule| rule.id).collect();
+            delete(
+                url_rule::url_rule
+                    .filter(url_rule::url_id.eq(url_id))
+                    .filter(url_rule::id.ne_all(kept_ids)),
+            )
+            .execute(connection)?;
+
+            for rule in rules {
+                match rule.id {
+                    Some(id) => {
+                        update(
+                            url_rule::url_rule
+                                .filter(url_rule::id.eq(id))
+                                .filter(url_rule::url_id.eq(url_id)),
+                        )
+                        .set((
+                            url_rule::position.eq(rule.position),
+                            url_rule::kind.eq(rule.kind),
+                            url_rule::value.eq(rule.value),
+                            url_rule::target.eq(rule.target),
+                        ))
+                        .get_result::<UrlRule>(connection)?;
+                    }
+                    None => {
+                        insert_into(url_rule::url_rule)
+                            .values((
+                                url_rule::url_id.eq(url_id),
+                                url_rule::position.eq(rule.position),
+                                url_rule::kind.eq(rule.kind),
+                                url_rule::value.eq(rule.value),
+                                url_rule::target.eq(rule.target),
+                            ))
+                            .execute(connection)?;
+                    }
+                }
+            }
+
+            Ok(url_rule::url_rule
+                .filter(url_rule::url_id.eq(url_id))
+                .order(url_rule::position)
+                .load::<UrlRule>(connection)?)
+        })
+    }
+}
@@ -0,0 +1,89 @@
+use chrono::NaiveDateTime;
+use diesel::sql_types::{Integer, Timestamp, Varchar};
+use diesel::{
+    delete, sql_query, ExpressionMethods, OptionalExtension, PgConnection, QueryDsl, RunQueryDsl,
+};
+
+use crate::database::handler::DbResult;
+use crate::schema::url_unlock_attempt::dsl as url_unlock_attempt;
+use std::rc::Rc;
+
+/// Wrong passwords entered for a protected link, counted per visitor within a window that
+/// starts at the first failure.
+pub trait UrlUnlockAttemptHandler {
+    /// Failures of the visitor in the window that started at or after `window_start`
+    fn count_failed(
+        &self,
+        url_id: i32,
+        ip_hash: &String,
+        window_start: &NaiveDateTime,
+    ) -> DbResult<i32>;
+    /// Counts a failure, starting a new window if the last one began before `window_start`
+    fn record_failed(
+        &self,
+        url_id: i32,
+        ip_hash: &String,
+        window_start: &NaiveDateTime,
+    ) -> DbResult<()>;
+    fn clear(&self, url_id: i32, ip_hash: &String) -> DbResult<()>;
+}
+
+pub struct UrlUnlockAttemptPostgresHandler {
+    pub connection: Rc<PgConnection>,
+}
+
+impl UrlUnlockAttemptPostgresHandler {
+    pub fn new(connection: Rc<PgConnection>) -> UrlUnlockAttemptPostgresHandler {
+        UrlUnlockAttemptPostgresHandler { connection }
+    }
+}
+
+impl UrlUnlockAttemptHandler for UrlUnlockAttemptPostgresHandler {
+    fn count_failed(
+        &self,
+        url_id: i32,
+        ip_hash: &String,
+        window_start: &NaiveDateTime,
+    ) -> DbResult<i32> {
+        Ok(url_unlock_attempt::url_unlock_attempt
+            .select(url_unlock_attempt::failed_attempts)
+            .filter(url_unlock_attempt::url_id.eq(url_id))
+            .filter(url_unlock_attempt::ip_hash.eq(ip_hash))
+            .filter(url_unlock_attempt::first_failed_at.ge(window_start))
+            .first::<i32>(self.connection.as_ref())
+            .optional()?
+            .unwrap_or(0))
+    }
+
+    fn record_failed(
+        &self,
+        url_id: i32,
+        ip_hash: &String,
+        window_start: &NaiveDateTime,
+    ) -> DbResult<()> {
+        sql_query(
+            "INSERT INTO url_unlock_attempt (url_id, ip_hash, failed_attempts) \
+             VALUES ($1, $2, 1) \
+             ON CONFLICT (url_id, ip_hash) DO UPDATE SET \
+             failed_attempts = CASE WHEN url_unlock_attempt.first_failed_at >= $3 \
+             THEN url_unlock_attempt.failed_attempts + 1 ELSE 1 END, \
+             first_failed_at = CASE WHEN url_unlock_attempt.first_failed_at >= $3 \
+             THEN url_unlock_attempt.first_failed_at ELSE NOW() END",
+        )
+        .bind::<Integer, _>(url_id)
+        .bind::<Varchar, _>(ip_hash)
+        .bind::<Timestamp, _>(window_start)
+        .execute(self.connection.as_ref())?;
+        Ok(())
+    }
+
+    fn clear(&self, url_id: i32, ip_hash: &String) -> DbResult<()> {
+        delete(
+            url_unlock_attempt::url_unlock_attempt
+                .filter(url_unlock_attempt::url_id.eq(url_id))
+                .filter(url_unlock_attempt::ip_hash.eq(ip_hash)),
+        )
+        .execute(self.connection.as_ref())?;
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use diesel::{
-    insert_into, update, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl,
-    TextExpressionMethods,
+    insert_into, update, BoolExpressionMethods, ExpressionMethods, PgConnection,
+    PgTextExpressionMethods, QueryDsl, RunQueryDsl, TextExpressionMethods,
 };
 
 use crate::database::handler::DbResult;
@@ -8,10 +8,30 @@ use crate::schema::user as user_schema;
 use crate::schema::user::dsl as user;
 use std::rc::Rc;
 
+pub const ROLE_USER: &str = "user";
+pub const ROLE_DEVELOPER: &str = "developer";
+pub const ROLE_ADMIN: &str = "admin";
+pub const ROLES: [&str; 3] = [ROLE_USER, ROLE_DEVELOPER, ROLE_ADMIN];
+
 pub trait UserHandler {
     fn new_user(&self, new_user: &NewUser) -> DbResult<()>;
     fn get_by_username(&self, username: &String) -> DbResult<User>;
+    fn get_by_id(&self, id: i32) -> DbResult<User>;
+    fn get_by_email(&self, email: &String) -> DbResult<User>;
     fn activate_by_username(&self, username: &String) -> DbResult<usize>;
+    fn search(&self, query: &String, offset: i64, limit: i64) -> DbResult<Vec<User>>;
+    fn count_search(&self, query: &String) -> DbResult<i64>;
+    fn set_activated(&self, username: &String, is_activated: bool) -> DbResult<User>;
+    fn set_role(&self, username: &String, role: &String) -> DbResult<User>;
+    fn set_must_reset_password(&self, username: &String, must_reset: bool) -> DbResult<User>;
+    fn update_password(
+        &self,
+        username: &String,
+        password: &String,
+        salt: &String,
+    ) -> DbResult<User>;
+    fn record_failed_login(&self, username: &String, max_attempts: i32) -> DbResult<User>;
+    fn unlock(&self, username: &String) -> DbResult<User>;
 }
 
 #[derive(Queryable)]
@@ -22,6 +42,20 @@ pub struct User {
     pub salt: String,
     pub email: String,
     pub is_activated: bool,
+    pub role: String,
+    pub is_locked: bool,
+    pub failed_login_attempts: i32,
+    pub must_reset_password: bool,
+}
+
+impl User {
+    pub fn is_admin(&self) -> bool {
+        self.role == ROLE_ADMIN
+    }
+
+    pub fn can_register_client(&self) -> bool {
+        self.role == ROLE_DEVELOPER || self.is_admin()
+    }
 }
 
 #[derive(Insertable)]
@@ -57,6 +91,18 @@ impl UserHandler for UserPostgresHandler {
             .first::<User>(self.connection.as_ref())?)
     }
 
+    fn get_by_id(&self, id: i32) -> DbResult<User> {
+        Ok(user::user
+            .filter(user::id.eq(id))
+            .first::<User>(self.connection.as_ref())?)
+    }
+
+    fn get_by_email(&self, email: &String) -> DbResult<User> {
+        Ok(user::user
+            .filter(user::email.eq(email))
+            .first::<User>(self.connection.as_ref())?)
+    }
+
     fn activate_by_username(&self, username: &String) -> DbResult<usize> {
         let result = update(user::user.filter(user::username.eq(username)))
             .set(user::is_activated.eq(true))
@@ -64,4 +110,80 @@ impl UserHandler for UserPostgresHandler {
 
         Ok(result)
     }
+
+    fn search(&self, query: &String, offset: i64, limit: i64) -> DbResult<Vec<User>> {
+        let pattern = format!("%{}%", query);
+        Ok(user::user
+            .filter(
+                user::username
+                    .ilike(&pattern)
+                    .or(user::email.ilike(&pattern)),
+            )
+            .order(user::username)
+            .offset(offset)
+            .limit(limit)
+            .load::<User>(self.connection.as_ref())?)
+    }
+
+    fn count_search(&self, query: &String) -> DbResult<i64> {
+        let pattern = format!("%{}%", query);
+        Ok(user::user
+            .filter(
+                user::username
+                    .ilike(&pattern)
+                    .or(user::email.ilike(&pattern)),
+            )
+            .count()
+            .first::<i64>(self.connection.as_ref())?)
+    }
+
+    fn set_activated(&self, username: &String, is_activated: bool) -> DbResult<User> {
+        Ok(update(user::user.filter(user::username.eq(username)))
+            .set(user::is_activated.eq(is_activated))
+            .get_result::<User>(self.connection.as_ref())?)
+    }
+
+    fn set_role(&self, username: &String, role: &String) -> DbResult<User> {
+        Ok(update(user::user.filter(user::username.eq(username)))
+            .set(user::role.eq(role))
+            .get_result::<User>(self.connection.as_ref())?)
+    }
+
+    fn set_must_reset_password(&self, username: &String, must_reset: bool) -> DbResult<User> {
+        Ok(update(user::user.filter(user::username.eq(username)))
+            .set(user::must_reset_password.eq(must_reset))
+            .get_result::<User>(self.connection.as_ref())?)
+    }
+
+    fn update_password(
+        &self,
+        username: &String,
+        password: &String,
+        salt: &String,
+    ) -> DbResult<User> {
+        Ok(update(user::user.filter(user::username.eq(username)))
+            .set((
+                user::password.eq(password),
+                user::salt.eq(salt),
+                user::must_reset_password.eq(false),
+                user::is_locked.eq(false),
+                user::failed_login_attempts.eq(0),
+            ))
+            .get_result::<User>(self.connection.as_ref())?)
+    }
+
+    fn record_failed_login(&self, username: &String, max_attempts: i32) -> DbResult<User> {
+        Ok(update(user::user.filter(user::username.eq(username)))
+            .set((
+                user::failed_login_attempts.eq(user::failed_login_attempts + 1),
+                user::is_locked.eq((user::failed_login_attempts + 1).ge(max_attempts)),
+            ))
+            .get_result::<User>(self.connection.as_ref())?)
+    }
+
+    fn unlock(&self, username: &String) -> DbResult<User> {
+        Ok(update(user::user.filter(user::username.eq(username)))
+            .set((user::is_locked.eq(false), user::failed_login_attempts.eq(0)))
+            .get_result::<User>(self.connection.as_ref())?)
+    }
 }
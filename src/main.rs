@@ -6,12 +6,15 @@ extern crate magic_crypt;
 extern crate lazy_static;
 
 use std::fmt::Error;
+use std::io;
 
 use actix_web::{middleware, web, App, HttpResponse, HttpServer};
 
 use crate::app_data::AppData;
 use crate::config::{get_config, Config};
+use crate::core::url_shortener::utils::restore_deadline;
 use crate::database::establish_connection;
+use crate::database::handler::audit_log::NewAuditLog;
 use actix_cors::Cors;
 
 mod config;
@@ -32,13 +35,67 @@ fn init(config: Config) -> Result<AppData, Error> {
     Ok(AppData::new(connection, &config))
 }
 
+fn rotate_key(config: Config, grace_period: Option<&String>) -> io::Result<()> {
+    let grace_period = match grace_period {
+        Some(grace_period) => grace_period
+            .parse::<u64>()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?,
+        None => config.auth.key_rotation_grace_period,
+    };
+
+    let app_data = init(config).unwrap();
+    let to_io_error =
+        |e: &dyn std::fmt::Display| io::Error::new(io::ErrorKind::Other, e.to_string());
+
+    let key = app_data
+        .auth_handler
+        .rotate_signing_key(grace_period)
+        .map_err(|e| to_io_error(&e))?;
+    app_data
+        .audit_log_handler
+        .insert(&NewAuditLog {
+            actor: &"cli".to_owned(),
+            action: "rotate_signing_key",
+            target: &key.id,
+            detail: &format!("grace_period={}", grace_period),
+        })
+        .map_err(|e| to_io_error(&e))?;
+
+    println!("Rotated signing key, new key id: {}", key.id);
+    Ok(())
+}
+
+/// Drops links whose restore window has passed along with their history, meant to be run
+/// periodically, e.g. from cron
+fn purge_deleted(config: Config) -> io::Result<()> {
+    let app_data = init(config).unwrap();
+
+    let purged = app_data
+        .url_handler
+        .purge_deleted(&restore_deadline(&app_data))
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+
+    println!("Purged {} deleted links", purged);
+    Ok(())
+}
+
 #[actix_rt::main]
 async fn main() -> std::io::Result<()> {
     let config = get_config();
 
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 1 && args[1] == "rotate-key" {
+        return rotate_key(config, args.get(2));
+    }
+    if args.len() > 1 && args[1] == "purge-deleted" {
+        return purge_deleted(config);
+    }
+
     let server = HttpServer::new(move || {
+        let app_data = init(config.clone()).unwrap();
         App::new()
-            .data(init(config.clone()).unwrap())
+            .service(core::url_shortener::domain_service(&app_data))
+            .data(app_data)
             .wrap(middleware::NormalizePath)
             .wrap(middleware::Logger::default())
             .wrap(Cors::new().supports_credentials().max_age(3600).finish())
@@ -46,6 +103,7 @@ async fn main() -> std::io::Result<()> {
             .service(core::resizer::service("/resizer"))
             .service(core::url_shortener::service("/url"))
             .service(core::sso::service("/sso"))
+            .service(core::admin::service("/admin"))
             .default_service(web::to(|| HttpResponse::NotFound().body("404")))
     })
     .bind("0.0.0.0:8000")?
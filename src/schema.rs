@@ -1,16 +1,203 @@
+table! {
+    audit_log (id) {
+        id -> Int4,
+        actor -> Varchar,
+        action -> Varchar,
+        target -> Varchar,
+        detail -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
 table! {
     client_credential (id) {
         id -> Varchar,
         secret -> Varchar,
-        redirect_uri -> Varchar,
+        redirect_uris -> Array<Text>,
+        name -> Varchar,
+        logo_uri -> Nullable<Varchar>,
+        grant_types -> Array<Text>,
+        owner -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    device_authorization (device_code) {
+        device_code -> Varchar,
+        user_code -> Varchar,
+        client_id -> Varchar,
+        username -> Nullable<Varchar>,
+        is_denied -> Bool,
+        poll_interval -> Int4,
+        last_polled_at -> Nullable<Timestamp>,
+        expires_at -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    domain (id) {
+        id -> Int4,
+        hostname -> Varchar,
+        username -> Varchar,
+        organization_id -> Nullable<Int4>,
+        not_found_url -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        approved_at -> Nullable<Timestamp>,
+        approved_by -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    federated_identity (id) {
+        id -> Int4,
+        provider -> Varchar,
+        subject -> Varchar,
+        user_id -> Int4,
+        email -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    organization (id) {
+        id -> Int4,
+        slug -> Varchar,
+        name -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    organization_member (organization_id, username) {
+        organization_id -> Int4,
+        username -> Varchar,
+        role -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    personal_access_token (id) {
+        id -> Int4,
+        user_id -> Int4,
+        name -> Varchar,
+        token_hash -> Varchar,
+        scopes -> Array<Text>,
+        created_at -> Timestamp,
+        last_used_at -> Nullable<Timestamp>,
+        expires_at -> Timestamp,
+        revoked_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    saml_service_provider (entity_id) {
+        entity_id -> Varchar,
+        acs_url -> Varchar,
+        name -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    signing_key (id) {
+        id -> Varchar,
+        secret -> Varchar,
+        is_active -> Bool,
+        created_at -> Timestamp,
+        expires_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    tag (id) {
+        id -> Int4,
+        name -> Varchar,
     }
 }
 
 table! {
-    url (key) {
+    url (id) {
         key -> Varchar,
         target -> Varchar,
         username -> Varchar,
+        organization_id -> Nullable<Int4>,
+        expires_at -> Nullable<Timestamp>,
+        activates_at -> Nullable<Timestamp>,
+        max_clicks -> Nullable<Int4>,
+        click_count -> Int4,
+        redirect_type -> Int4,
+        is_disabled -> Bool,
+        disabled_reason -> Nullable<Varchar>,
+        always_preview -> Bool,
+        password_hash -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        description -> Nullable<Varchar>,
+        deleted_at -> Nullable<Timestamp>,
+        id -> Int4,
+        domain_id -> Nullable<Int4>,
+    }
+}
+
+table! {
+    url_click (id) {
+        id -> Int4,
+        clicked_at -> Timestamp,
+        referrer -> Nullable<Varchar>,
+        user_agent -> Nullable<Varchar>,
+        country -> Nullable<Varchar>,
+        ip_hash -> Varchar,
+        url_id -> Int4,
+        rule_id -> Nullable<Int4>,
+    }
+}
+
+table! {
+    url_revision (id) {
+        id -> Int4,
+        changed_by -> Varchar,
+        action -> Varchar,
+        key -> Varchar,
+        target -> Varchar,
+        expires_at -> Nullable<Timestamp>,
+        activates_at -> Nullable<Timestamp>,
+        max_clicks -> Nullable<Int4>,
+        redirect_type -> Int4,
+        always_preview -> Bool,
+        description -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        url_id -> Int4,
+    }
+}
+
+table! {
+    url_rule (id) {
+        id -> Int4,
+        url_id -> Int4,
+        position -> Int4,
+        kind -> Varchar,
+        value -> Varchar,
+        target -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    url_tag (url_id, tag_id) {
+        tag_id -> Int4,
+        url_id -> Int4,
+    }
+}
+
+table! {
+    url_unlock_attempt (url_id, ip_hash) {
+        url_id -> Int4,
+        ip_hash -> Varchar,
+        failed_attempts -> Int4,
+        first_failed_at -> Timestamp,
     }
 }
 
@@ -22,7 +209,45 @@ table! {
         salt -> Varchar,
         email -> Varchar,
         is_activated -> Bool,
+        role -> Varchar,
+        is_locked -> Bool,
+        failed_login_attempts -> Int4,
+        must_reset_password -> Bool,
     }
 }
 
-allow_tables_to_appear_in_same_query!(client_credential, url, user,);
+joinable!(device_authorization -> client_credential (client_id));
+joinable!(domain -> organization (organization_id));
+joinable!(federated_identity -> user (user_id));
+joinable!(organization_member -> organization (organization_id));
+joinable!(personal_access_token -> user (user_id));
+joinable!(url -> domain (domain_id));
+joinable!(url -> organization (organization_id));
+joinable!(url_click -> url (url_id));
+joinable!(url_click -> url_rule (rule_id));
+joinable!(url_revision -> url (url_id));
+joinable!(url_rule -> url (url_id));
+joinable!(url_tag -> tag (tag_id));
+joinable!(url_tag -> url (url_id));
+joinable!(url_unlock_attempt -> url (url_id));
+
+allow_tables_to_appear_in_same_query!(
+    audit_log,
+    client_credential,
+    device_authorization,
+    domain,
+    federated_identity,
+    organization,
+    organization_member,
+    personal_access_token,
+    saml_service_provider,
+    signing_key,
+    tag,
+    url,
+    url_click,
+    url_revision,
+    url_rule,
+    url_tag,
+    url_unlock_attempt,
+    user,
+);
@@ -1,10 +1,45 @@
+use crate::config::IdentityProviderConfig;
 use crate::templater::error::TemplateResult;
 
 pub mod error;
 pub mod tera_based;
 
 pub trait Templater {
-    fn login_page(&self, client_id: &String, redirect_uri: &String) -> TemplateResult<String>;
+    fn login_page(
+        &self,
+        client_id: &String,
+        redirect_uri: &String,
+        identity_providers: &[IdentityProviderConfig],
+    ) -> TemplateResult<String>;
     fn register_page(&self) -> TemplateResult<String>;
     fn resend_activation_page(&self, message: &String) -> TemplateResult<String>;
+    fn reset_password_page(&self, code: &String, message: &String) -> TemplateResult<String>;
+    fn device_verification_page(
+        &self,
+        user_code: &String,
+        message: &String,
+    ) -> TemplateResult<String>;
+    fn url_unavailable_page(&self, message: &String) -> TemplateResult<String>;
+    fn url_password_page(&self, message: &String) -> TemplateResult<String>;
+    fn url_preview_page(
+        &self,
+        key: &String,
+        target: &String,
+        owner: &String,
+        created_at: &String,
+        is_warning: bool,
+    ) -> TemplateResult<String>;
+    fn saml_login_page(
+        &self,
+        saml_request: &String,
+        binding: &String,
+        relay_state: Option<&String>,
+        message: &String,
+    ) -> TemplateResult<String>;
+    fn saml_post_page(
+        &self,
+        acs_url: &String,
+        saml_response: &String,
+        relay_state: Option<&String>,
+    ) -> TemplateResult<String>;
 }
@@ -1,6 +1,7 @@
 use serde::Serialize;
 use tera::{Context, Tera};
 
+use crate::config::IdentityProviderConfig;
 use crate::templater::error::TemplaterError::RenderError;
 use crate::templater::error::{TemplateResult, TemplaterError};
 use crate::templater::Templater;
@@ -32,18 +33,39 @@ impl TeraTemplater {
 }
 
 impl Templater for TeraTemplater {
-    fn login_page(&self, client_id: &String, redirect_uri: &String) -> TemplateResult<String> {
+    fn login_page(
+        &self,
+        client_id: &String,
+        redirect_uri: &String,
+        identity_providers: &[IdentityProviderConfig],
+    ) -> TemplateResult<String> {
+        #[derive(Serialize)]
+        struct IdentityProvider<'a> {
+            id: &'a String,
+            display_name: &'a String,
+        }
+
         #[derive(Serialize)]
         struct Payload<'a> {
             client_id: &'a String,
             redirect_uri: &'a String,
+            identity_providers: Vec<IdentityProvider<'a>>,
         }
 
+        let identity_providers = identity_providers
+            .iter()
+            .map(|provider| IdentityProvider {
+                id: &provider.id,
+                display_name: &provider.display_name,
+            })
+            .collect();
+
         self.render::<Payload>(
             "account/login.html",
             Some(&Payload {
                 client_id,
                 redirect_uri,
+                identity_providers,
             }),
         )
     }
@@ -63,4 +85,127 @@ impl Templater for TeraTemplater {
             Some(&Payload { message }),
         )
     }
+
+    fn reset_password_page(&self, code: &String, message: &String) -> TemplateResult<String> {
+        #[derive(Serialize)]
+        struct Payload<'a> {
+            code: &'a String,
+            message: &'a String,
+        }
+
+        self.render::<Payload>(
+            "account/reset_password.html",
+            Some(&Payload { code, message }),
+        )
+    }
+
+    fn device_verification_page(
+        &self,
+        user_code: &String,
+        message: &String,
+    ) -> TemplateResult<String> {
+        #[derive(Serialize)]
+        struct Payload<'a> {
+            user_code: &'a String,
+            message: &'a String,
+        }
+
+        self.render::<Payload>("account/device.html", Some(&Payload { user_code, message }))
+    }
+
+    fn url_unavailable_page(&self, message: &String) -> TemplateResult<String> {
+        #[derive(Serialize)]
+        struct Payload<'a> {
+            message: &'a String,
+        }
+
+        self.render::<Payload>("url/unavailable.html", Some(&Payload { message }))
+    }
+
+    fn url_password_page(&self, message: &String) -> TemplateResult<String> {
+        #[derive(Serialize)]
+        struct Payload<'a> {
+            message: &'a String,
+        }
+
+        self.render::<Payload>("url/password.html", Some(&Payload { message }))
+    }
+
+    fn url_preview_page(
+        &self,
+        key: &String,
+        target: &String,
+        owner: &String,
+        created_at: &String,
+        is_warning: bool,
+    ) -> TemplateResult<String> {
+        #[derive(Serialize)]
+        struct Payload<'a> {
+            key: &'a String,
+            target: &'a String,
+            owner: &'a String,
+            created_at: &'a String,
+            is_warning: bool,
+        }
+
+        self.render::<Payload>(
+            "url/preview.html",
+            Some(&Payload {
+                key,
+                target,
+                owner,
+                created_at,
+                is_warning,
+            }),
+        )
+    }
+
+    fn saml_login_page(
+        &self,
+        saml_request: &String,
+        binding: &String,
+        relay_state: Option<&String>,
+        message: &String,
+    ) -> TemplateResult<String> {
+        #[derive(Serialize)]
+        struct Payload<'a> {
+            saml_request: &'a String,
+            binding: &'a String,
+            relay_state: Option<&'a String>,
+            message: &'a String,
+        }
+
+        self.render::<Payload>(
+            "saml/login.html",
+            Some(&Payload {
+                saml_request,
+                binding,
+                relay_state,
+                message,
+            }),
+        )
+    }
+
+    fn saml_post_page(
+        &self,
+        acs_url: &String,
+        saml_response: &String,
+        relay_state: Option<&String>,
+    ) -> TemplateResult<String> {
+        #[derive(Serialize)]
+        struct Payload<'a> {
+            acs_url: &'a String,
+            saml_response: &'a String,
+            relay_state: Option<&'a String>,
+        }
+
+        self.render::<Payload>(
+            "saml/post_response.html",
+            Some(&Payload {
+                acs_url,
+                saml_response,
+                relay_state,
+            }),
+        )
+    }
 }
@@ -0,0 +1,18 @@
+#!/bin/sh
+# Starts an OpenLDAP stand-in seeded with `seed.ldif` for the directory tests, run them with
+#   DORAEMON_TEST_LDAP_URL=ldap://localhost:3389 cargo test directory -- --include-ignored
+set -e
+
+docker run --rm -d --name doraemon-test-ldap -p 3389:389 \
+    -e LDAP_ORGANISATION=Example \
+    -e LDAP_DOMAIN=example.com \
+    -e LDAP_ADMIN_PASSWORD=admin \
+    osixia/openldap:1.4.0
+
+until docker exec doraemon-test-ldap ldapsearch -x -H ldap://localhost \
+    -D cn=admin,dc=example,dc=com -w admin -b dc=example,dc=com >/dev/null 2>&1; do
+    sleep 1
+done
+
+docker exec -i doraemon-test-ldap ldapadd -x -H ldap://localhost \
+    -D cn=admin,dc=example,dc=com -w admin < "$(dirname "$0")/seed.ldif"